@@ -0,0 +1,82 @@
+//! Checks the permissions of commands and records privileged actions.
+//!
+//! Handlers declare the permission each of their commands needs with
+//! `UpdateHandler::permission`. A privileged command calls `authorize`
+//! before doing anything, and `audit` with the outcome afterwards. Denied
+//! attempts are recorded by `authorize` itself and otherwise ignored, so
+//! plain users do not learn about admin commands.
+
+use crate::context::AppContext;
+use crate::infrastructure::database::AuditEntry;
+use crate::services::permissions::{role_of, Permission, Role};
+use crate::{debug_log, warn_log};
+use super::command::BotCommand;
+use super::dispatcher::{HandlerError, UpdateHandler};
+
+/// Domain identifier for access logs
+const ACCESS_LOGGER_DOMAIN: &str = "[BOT]";
+
+/// The audit result of commands the user may not run
+pub const DENIED_RESULT: &str = "denied";
+
+/// The audit result of commands that succeeded
+pub const OK_RESULT: &str = "ok";
+
+/// Returns the role of `user_id` if it may run `command` of `handler`.
+///
+/// Commands the handler declares no permission for may be run by anyone.
+pub async fn authorize(
+    context: &AppContext,
+    handler: &dyn UpdateHandler,
+    user_id: i64,
+    command: &BotCommand,
+) -> Result<Option<Role>, HandlerError> {
+    match handler.permission(&command.name) {
+        Some(permission) => authorize_permission(context, user_id, command, permission).await,
+        None => Ok(Some(role_of(context.database(), user_id).await?)),
+    }
+}
+
+/// Returns the role of `user_id` if it includes `permission`.
+///
+/// For actions whose permission depends on more than the command, such as
+/// the buttons of a `/user` card. Attempts without the permission are
+/// recorded as denied.
+pub async fn authorize_permission(
+    context: &AppContext,
+    user_id: i64,
+    command: &BotCommand,
+    permission: Permission,
+) -> Result<Option<Role>, HandlerError> {
+    let role = role_of(context.database(), user_id).await?;
+    if role.can(permission) {
+        return Ok(Some(role));
+    }
+
+    debug_log!(
+        ACCESS_LOGGER_DOMAIN,
+        user_id = user_id,
+        "Ignoring /{} from {} without {:?}",
+        command.name,
+        role,
+        permission
+    );
+    audit(context, user_id, None, command, DENIED_RESULT).await;
+    Ok(None)
+}
+
+/// Records a privileged command in the audit log.
+///
+/// A failure to record is logged, since the action itself already happened.
+pub async fn audit(context: &AppContext, actor_id: i64, target: Option<String>, command: &BotCommand, result: &str) {
+    let entry = AuditEntry::new(actor_id, target, &command.name, &command.args, result);
+    if let Err(error) = context.database().record_audit(&entry).await {
+        warn_log!(
+            ACCESS_LOGGER_DOMAIN,
+            user_id = actor_id,
+            "Failed to record /{} in the audit log: {}",
+            command.name,
+            error
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! Records the Telegram users who talk to the bot.
+//!
+//! Every message and button press updates the sender's username, name and
+//! last activity, which `/user` looks up.
+
+use async_trait::async_trait;
+
+use crate::infrastructure::api::TelegramClient;
+use crate::infrastructure::api::telegram_types::Update;
+use crate::infrastructure::database::{Database, TelegramUser};
+use super::dispatcher::{HandlerError, UpdateHandler};
+
+/// Handles every update by recording its sender.
+pub struct ActivityHandler {
+    /// Stores the users
+    database: Database,
+}
+
+impl ActivityHandler {
+    /// Creates a handler recording users in `database`.
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl UpdateHandler for ActivityHandler {
+    async fn handle(&self, _client: &TelegramClient, update: &Update) -> Result<(), HandlerError> {
+        let sender = match &update.callback_query {
+            Some(query) => Some(&query.from),
+            None => update.message().and_then(|message| message.from.as_ref()),
+        };
+        let Some(sender) = sender.filter(|sender| !sender.is_bot) else {
+            return Ok(());
+        };
+
+        let user = TelegramUser::new(sender.id, sender.username.clone(), &sender.first_name);
+        self.database.touch_telegram_user(&user).await?;
+        Ok(())
+    }
+}
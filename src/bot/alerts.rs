@@ -0,0 +1,32 @@
+//! Delivers log alerts to an admin chat.
+
+use std::error::Error;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::infrastructure::api::TelegramClient;
+use crate::infrastructure::logger::AlertSink;
+
+/// Sends log alerts as messages to a Telegram chat.
+pub struct TelegramAlertSink {
+    /// The client used to send the messages
+    client: Arc<TelegramClient>,
+    /// The chat receiving the alerts
+    chat_id: i64,
+}
+
+impl TelegramAlertSink {
+    /// Creates a sink sending to `chat_id`.
+    pub fn new(client: Arc<TelegramClient>, chat_id: i64) -> Self {
+        Self { client, chat_id }
+    }
+}
+
+#[async_trait]
+impl AlertSink for TelegramAlertSink {
+    async fn send_alert(&self, text: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.client.send_message(self.chat_id, text).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,325 @@
+//! Binds a Telegram user to an existing media server account.
+//!
+//! `/bind <username> <password>` signs in to the only server with the given
+//! credentials and records the account for the sender. With several servers
+//! the server is named first: `/bind <server> <username> <password>`. The
+//! message is deleted right away since it contains a password.
+//!
+//! `/bind` without arguments asks for the server, the user name and the
+//! password one at a time.
+//!
+//! After `MAX_BIND_FAILURES` wrong passwords in a row, a user has to wait
+//! `BIND_COOLDOWN` before trying again.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::context::AppContext;
+use crate::infrastructure::api::TelegramClient;
+use crate::infrastructure::api::telegram_types::{ChatKind, Message, Update};
+use crate::infrastructure::database::AccountBinding;
+use crate::infrastructure::metrics::BotMetrics;
+use crate::{info_log, warn_log};
+use super::command::BotCommand;
+use super::conversation::{expired_reply, Conversation, ConversationKey, ConversationState, ConversationStore};
+use super::dispatcher::{HandlerError, UpdateHandler};
+use super::throttle::FailureLimiter;
+use super::validators;
+
+/// Domain identifier for bind command logs
+const BIND_LOGGER_DOMAIN: &str = "[BOT]";
+
+/// The command handled by `BindHandler`
+const BIND_COMMAND: &str = "bind";
+
+/// Wrong passwords after which a user has to wait
+pub const MAX_BIND_FAILURES: u32 = 5;
+
+/// How long a user waits after too many wrong passwords
+pub const BIND_COOLDOWN: Duration = Duration::from_secs(15 * 60);
+
+/// The steps of a `/bind` dialog.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+enum BindState {
+    /// Waiting for the server name
+    Server,
+    /// Waiting for the user name
+    Username { server: String },
+    /// Waiting for the password
+    Password { server: String, username: String },
+}
+
+impl ConversationState for BindState {
+    const FLOW: &'static str = BIND_COMMAND;
+}
+
+/// The arguments of a `/bind` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindRequest {
+    /// The named server, `None` if the only server is meant
+    pub server: Option<String>,
+    /// The account name
+    pub username: String,
+    /// The account password, which may contain spaces
+    pub password: String,
+}
+
+impl BindRequest {
+    /// Parses the arguments of `/bind`.
+    ///
+    /// The first word names the server if it is one of `servers` and is
+    /// followed by the username and password.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pilipili_bot::bot::bind::BindRequest;
+    ///
+    /// let servers = ["eu".to_string()];
+    /// let request = BindRequest::parse("eu alice pass word", &servers).unwrap();
+    /// assert_eq!(request.server.as_deref(), Some("eu"));
+    /// assert_eq!(request.password, "pass word");
+    /// assert!(BindRequest::parse("alice", &servers).is_none());
+    /// ```
+    pub fn parse(args: &str, servers: &[String]) -> Option<Self> {
+        let (first, rest) = split_word(args)?;
+        if servers.iter().any(|server| server == first)
+            && let Some((username, password)) = split_word(rest)
+        {
+            return Some(Self {
+                server: Some(first.to_owned()),
+                username: username.to_owned(),
+                password: password.to_owned(),
+            });
+        }
+
+        Some(Self {
+            server: None,
+            username: first.to_owned(),
+            password: rest.to_owned(),
+        })
+    }
+}
+
+/// Splits off the first word, returning `None` unless text follows it.
+fn split_word(text: &str) -> Option<(&str, &str)> {
+    let (word, rest) = text.trim().split_once(char::is_whitespace)?;
+    let rest = rest.trim();
+    (!rest.is_empty()).then_some((word, rest))
+}
+
+/// Handles the `/bind` command.
+pub struct BindHandler {
+    /// Provides the servers and the database
+    context: Arc<AppContext>,
+    /// Stores where each `/bind` dialog stands
+    conversations: ConversationStore,
+    /// Counts the bound accounts
+    metrics: BotMetrics,
+    /// Counts wrong passwords per user
+    failures: FailureLimiter,
+}
+
+impl BindHandler {
+    /// Creates a handler binding accounts on the servers in `context`.
+    pub fn new(context: Arc<AppContext>) -> Self {
+        let conversations = ConversationStore::new(context.database().clone());
+        Self {
+            context,
+            conversations,
+            metrics: BotMetrics::default(),
+            failures: FailureLimiter::new(MAX_BIND_FAILURES, BIND_COOLDOWN),
+        }
+    }
+
+    /// Returns how long users have to answer.
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.context.config().telegram.conversation_timeout)
+    }
+
+    /// Starts the `/bind` dialog, returning the first question.
+    async fn start(&self, key: ConversationKey) -> Result<String, HandlerError> {
+        let servers = self.context.servers().names();
+        let (state, question) = match servers.as_slice() {
+            [] => return Ok("No server is configured".to_string()),
+            [server] => (
+                BindState::Username { server: server.clone() },
+                format!("Send your user name on {}", server),
+            ),
+            _ => (BindState::Server, format!("Which server? {}", servers.join(", "))),
+        };
+        self.conversations.save(key, &state, self.timeout()).await?;
+        Ok(format!("{}, or /cancel to stop.", question))
+    }
+
+    /// Handles an answer within a `/bind` dialog, returning the reply.
+    async fn answer(
+        &self,
+        client: &TelegramClient,
+        message: &Message,
+        key: ConversationKey,
+        text: &str,
+    ) -> Result<Option<String>, HandlerError> {
+        let state = match self.conversations.load::<BindState>(key).await? {
+            Some(Conversation::Active(state)) => state,
+            Some(Conversation::Expired(state)) => {
+                if let BindState::Password { .. } = state {
+                    delete_credentials(client, message, key).await;
+                }
+                self.conversations.finish(key).await?;
+                return Ok(Some(expired_reply(BIND_COMMAND)));
+            }
+            None => return Ok(None),
+        };
+
+        let reply = match state {
+            BindState::Server => match validators::server(text, &self.context.servers().names()) {
+                Ok(server) => {
+                    let reply = format!("Send your user name on {}", server);
+                    self.conversations.save(key, &BindState::Username { server }, self.timeout()).await?;
+                    reply
+                }
+                Err(reason) => reason,
+            },
+            BindState::Username { server } => match validators::username(text) {
+                Ok(username) => {
+                    let state = BindState::Password { server, username };
+                    self.conversations.save(key, &state, self.timeout()).await?;
+                    "Send your password. I will delete your message right away.".to_string()
+                }
+                Err(reason) => reason,
+            },
+            BindState::Password { server, username } => {
+                delete_credentials(client, message, key).await;
+                if !self.conversations.finish(key).await? {
+                    // Another message already ended the dialog.
+                    return Ok(None);
+                }
+                let request = BindRequest {
+                    server: Some(server),
+                    username,
+                    password: text.to_owned(),
+                };
+                self.bind(key.user_id, request).await?
+            }
+        };
+        Ok(Some(reply))
+    }
+
+    /// Checks the credentials and records the binding, returning the reply.
+    async fn bind(&self, telegram_id: i64, request: BindRequest) -> Result<String, HandlerError> {
+        if let Some(remaining) = self.failures.cooldown(telegram_id) {
+            return Ok(too_many_failures(remaining));
+        }
+        let server = match self.context.servers().resolve(request.server.as_deref()) {
+            Ok(server) => server,
+            Err(error) => return Ok(error.to_string()),
+        };
+        let name = server.name();
+        if let Some(binding) = self.context.database().account_on(telegram_id, &name).await? {
+            return Ok(format!("You are already bound to {} on {}", binding.emby_name, name));
+        }
+
+        let result = match server.authenticate(&request.username, &request.password).await {
+            Ok(result) => result,
+            Err(error) if error.is_unauthorized() => {
+                let reply = match self.failures.record_failure(telegram_id) {
+                    Some(cooldown) => {
+                        warn_log!(BIND_LOGGER_DOMAIN, user_id = telegram_id, server = %name, "Too many failed bind attempts");
+                        format!("Wrong username or password. {}", too_many_failures(cooldown))
+                    }
+                    None => "Wrong username or password".to_string(),
+                };
+                return Ok(reply);
+            }
+            Err(error) => {
+                warn_log!(BIND_LOGGER_DOMAIN, server = %name, "Failed to check credentials: {}", error);
+                return Ok(format!("{} cannot be reached, please try again later", name));
+            }
+        };
+
+        self.failures.reset(telegram_id);
+        let binding = AccountBinding::new(telegram_id, &name, &result.user.id, &result.user.name);
+        self.context.database().bind_account(&binding).await?;
+        self.metrics.registration();
+        info_log!(
+            BIND_LOGGER_DOMAIN,
+            user_id = telegram_id,
+            server = %name,
+            "Bound account {}",
+            result.user.name
+        );
+        Ok(format!("✅ Bound to {} on {}", result.user.name, name))
+    }
+}
+
+/// Deletes a message containing credentials sent by the user.
+async fn delete_credentials(client: &TelegramClient, message: &Message, key: ConversationKey) {
+    if let Err(error) = client.delete_message(message.chat.id, message.message_id).await {
+        warn_log!(BIND_LOGGER_DOMAIN, user_id = key.user_id, "Failed to delete credentials: {}", error);
+    }
+}
+
+/// Returns the reply to a user blocked for `remaining`.
+fn too_many_failures(remaining: Duration) -> String {
+    let minutes = remaining.as_secs().div_ceil(60).max(1);
+    format!(
+        "Too many failed attempts, try again in {} minute{}",
+        minutes,
+        if minutes == 1 { "" } else { "s" }
+    )
+}
+
+#[async_trait]
+impl UpdateHandler for BindHandler {
+    async fn handle(&self, client: &TelegramClient, update: &Update) -> Result<(), HandlerError> {
+        let (Some(message), Some(key)) = (update.message(), ConversationKey::from_update(update)) else {
+            return Ok(());
+        };
+        let Some(text) = message.text.as_deref() else {
+            return Ok(());
+        };
+        let private = message.chat.kind == ChatKind::Private;
+        let command = match BotCommand::parse(text) {
+            Some(command) if command.is(BIND_COMMAND) => command,
+            Some(_) => return Ok(()),
+            None => {
+                if private && let Some(reply) = self.answer(client, message, key, text).await? {
+                    client.send_message(key.chat_id, reply).await?;
+                }
+                return Ok(());
+            }
+        };
+        let (chat_id, user_id) = (key.chat_id, key.user_id);
+
+        if !command.args.is_empty()
+            && let Err(error) = client.delete_message(chat_id, message.message_id).await
+        {
+            warn_log!(BIND_LOGGER_DOMAIN, user_id = user_id, "Failed to delete credentials: {}", error);
+        }
+
+        let reply = if !private {
+            format!("Send /{} in a private chat with me", BIND_COMMAND)
+        } else if command.args.is_empty() {
+            self.start(key).await?
+        } else {
+            match BindRequest::parse(&command.args, &self.context.servers().names()) {
+                Some(request) => self.bind(user_id, request).await?,
+                None => format!(
+                    "Usage: /{} <username> <password>, or /{} <server> <username> <password>",
+                    BIND_COMMAND, BIND_COMMAND
+                ),
+            }
+        };
+        client.send_message(chat_id, reply).await?;
+        Ok(())
+    }
+
+    fn commands(&self) -> &'static [&'static str] {
+        &[BIND_COMMAND]
+    }
+}
@@ -0,0 +1,454 @@
+//! Encodes the data of inline keyboard buttons and routes their presses.
+//!
+//! Telegram sends the `callback_data` of a pressed button back to the bot,
+//! and lets any user in the chat press it. The data is therefore signed, and
+//! limited to the 64 bytes Telegram accepts. It has the compact form
+//!
+//! ```text
+//! <action>:<field>…:<issued>:<requester>:<signature>
+//! ```
+//!
+//! where `issued` is the Unix time the button was created in base 36,
+//! `requester` the base 36 id of the only user allowed to press it, or empty
+//! for everyone, and `signature` the first 8 bytes of an HMAC-SHA256 over
+//! everything before it, keyed with the bot token and encoded as base64url.
+//!
+//! Buttons expire after `[telegram] button_ttl` seconds. Changing the bot
+//! token expires all buttons at once.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use pilipili_bot::bot::callback::{CallbackData, CallbackSigner};
+//!
+//! struct Approve {
+//!     user_id: i64,
+//! }
+//!
+//! impl CallbackData for Approve {
+//!     const ACTION: &'static str = "approve";
+//!
+//!     fn fields(&self) -> Vec<String> {
+//!         vec![self.user_id.to_string()]
+//!     }
+//!
+//!     fn from_fields(fields: &[&str]) -> Option<Self> {
+//!         match fields {
+//!             [user_id] => Some(Self { user_id: user_id.parse().ok()? }),
+//!             _ => None,
+//!         }
+//!     }
+//! }
+//!
+//! let signer = CallbackSigner::new(b"secret", Duration::from_secs(3600));
+//! let data = signer.sign(&Approve { user_id: 42 }, Some(7)).unwrap();
+//! assert!(data.len() <= 64);
+//!
+//! let callback = signer.verify(&data, 7).unwrap();
+//! assert_eq!(callback.decode::<Approve>().unwrap().user_id, 42);
+//! assert!(signer.verify(&data, 8).is_err());
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::infrastructure::api::TelegramClient;
+use crate::infrastructure::api::telegram_types::{CallbackQuery, InlineKeyboardButton, Update};
+use crate::infrastructure::config::telegram::TelegramConfig;
+use crate::{debug_log, warn_log};
+use super::dispatcher::{HandlerError, UpdateHandler};
+
+/// Domain identifier for callback logs
+const CALLBACK_LOGGER_DOMAIN: &str = "[BOT]";
+
+/// The most bytes Telegram accepts as `callback_data`
+pub const MAX_CALLBACK_DATA_LENGTH: usize = 64;
+
+/// Separates the parts of callback data
+const SEPARATOR: char = ':';
+
+/// The number of signature bytes kept
+const SIGNATURE_LENGTH: usize = 8;
+
+/// The answer to presses of buttons that expired or cannot be read
+const EXPIRED_ANSWER: &str = "⌛ This button has expired";
+
+/// The answer to presses by users the button was not meant for
+const WRONG_USER_ANSWER: &str = "This button is not for you";
+
+/// The answer to presses whose handler failed
+const FAILED_ANSWER: &str = "Something went wrong, please try again later";
+
+/// A typed payload of a button.
+///
+/// The fields are stored as text and must not contain `:`. Keep them short,
+/// the whole callback data is limited to 64 bytes.
+pub trait CallbackData: Sized + Send + 'static {
+    /// A short name identifying the payload type, e.g. `rp`
+    const ACTION: &'static str;
+
+    /// Returns the fields of the payload.
+    fn fields(&self) -> Vec<String>;
+
+    /// Parses the fields written by `fields`, `None` if they are invalid.
+    fn from_fields(fields: &[&str]) -> Option<Self>;
+}
+
+/// Represents an error while signing or checking callback data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallbackError {
+    /// The encoded data exceeds 64 bytes
+    TooLong(usize),
+    /// A field contains the separator
+    InvalidField(String),
+    /// The data was not created by `CallbackSigner`
+    Malformed,
+    /// The signature does not match, e.g. because the bot token changed
+    BadSignature,
+    /// The button is older than the configured lifetime
+    Expired,
+    /// The button belongs to another user
+    WrongUser,
+}
+
+impl Display for CallbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallbackError::TooLong(length) => write!(
+                f,
+                "callback data has {} bytes, at most {} are allowed",
+                length, MAX_CALLBACK_DATA_LENGTH
+            ),
+            CallbackError::InvalidField(field) => write!(f, "callback field {:?} contains '{}'", field, SEPARATOR),
+            CallbackError::Malformed => write!(f, "malformed callback data"),
+            CallbackError::BadSignature => write!(f, "invalid callback signature"),
+            CallbackError::Expired => write!(f, "the button has expired"),
+            CallbackError::WrongUser => write!(f, "the button belongs to another user"),
+        }
+    }
+}
+
+impl std::error::Error for CallbackError {}
+
+/// A button press whose data was checked by `CallbackSigner::verify`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedCallback {
+    /// The `CallbackData::ACTION` of the payload
+    pub action: String,
+    /// The fields of the payload
+    pub fields: Vec<String>,
+    /// The only user allowed to press the button, `None` for everyone
+    pub requester: Option<i64>,
+    /// When the button was created, as a Unix timestamp
+    pub issued_at: i64,
+}
+
+impl SignedCallback {
+    /// Decodes the payload, `None` if it is not a `D` or its fields are invalid.
+    pub fn decode<D: CallbackData>(&self) -> Option<D> {
+        if self.action != D::ACTION {
+            return None;
+        }
+        let fields: Vec<&str> = self.fields.iter().map(String::as_str).collect();
+        D::from_fields(&fields)
+    }
+}
+
+/// Signs the data of buttons and checks it when they are pressed.
+#[derive(Clone)]
+pub struct CallbackSigner {
+    /// The HMAC key
+    key: Vec<u8>,
+    /// How long buttons keep working
+    ttl: Duration,
+}
+
+impl fmt::Debug for CallbackSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackSigner")
+            .field("key", &"<redacted>")
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+impl CallbackSigner {
+    /// Creates a signer with the given key whose buttons expire after `ttl`.
+    pub fn new(key: &[u8], ttl: Duration) -> Self {
+        Self { key: key.to_vec(), ttl }
+    }
+
+    /// Creates the signer of the bot in `settings`, keyed with its token.
+    pub fn from_settings(settings: &TelegramConfig) -> Self {
+        Self::new(settings.bot_token.as_bytes(), Duration::from_secs(settings.button_ttl))
+    }
+
+    /// Returns how long buttons keep working.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Encodes and signs `data` for a button created now.
+    ///
+    /// With a `requester`, only that user may press the button.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CallbackError::InvalidField` if a field contains `:` and
+    /// `CallbackError::TooLong` if the result exceeds 64 bytes.
+    pub fn sign<D: CallbackData>(&self, data: &D, requester: Option<i64>) -> Result<String, CallbackError> {
+        self.sign_at(data, requester, chrono::Utc::now().timestamp())
+    }
+
+    /// Encodes and signs `data` for a button created at `issued_at`.
+    pub fn sign_at<D: CallbackData>(
+        &self,
+        data: &D,
+        requester: Option<i64>,
+        issued_at: i64,
+    ) -> Result<String, CallbackError> {
+        let mut parts = vec![D::ACTION.to_owned()];
+        parts.extend(data.fields());
+        if let Some(field) = parts.iter().find(|part| part.contains(SEPARATOR)) {
+            return Err(CallbackError::InvalidField(field.clone()));
+        }
+        parts.push(to_base36(issued_at));
+        parts.push(requester.map(to_base36).unwrap_or_default());
+
+        let payload = parts.join(":");
+        let encoded = format!("{}{}{}", payload, SEPARATOR, self.signature(&payload));
+        if encoded.len() > MAX_CALLBACK_DATA_LENGTH {
+            return Err(CallbackError::TooLong(encoded.len()));
+        }
+        Ok(encoded)
+    }
+
+    /// Creates a button carrying signed `data`.
+    pub fn button<D: CallbackData>(
+        &self,
+        text: impl Into<String>,
+        data: &D,
+        requester: Option<i64>,
+    ) -> Result<InlineKeyboardButton, CallbackError> {
+        Ok(InlineKeyboardButton {
+            text: text.into(),
+            callback_data: Some(self.sign(data, requester)?),
+            url: None,
+        })
+    }
+
+    /// Checks the data of a button pressed now by `user_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns why the press must be rejected: the data is malformed or not
+    /// signed with this key, the button expired or belongs to another user.
+    pub fn verify(&self, data: &str, user_id: i64) -> Result<SignedCallback, CallbackError> {
+        self.verify_at(data, user_id, chrono::Utc::now().timestamp())
+    }
+
+    /// Checks the data of a button pressed at `now` by `user_id`.
+    pub fn verify_at(&self, data: &str, user_id: i64, now: i64) -> Result<SignedCallback, CallbackError> {
+        let (payload, signature) = data.rsplit_once(SEPARATOR).ok_or(CallbackError::Malformed)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| CallbackError::BadSignature)?;
+        self.mac(payload)
+            .verify_truncated_left(&signature)
+            .map_err(|_| CallbackError::BadSignature)?;
+
+        let mut parts: Vec<&str> = payload.split(SEPARATOR).collect();
+        if parts.len() < 3 {
+            return Err(CallbackError::Malformed);
+        }
+        let requester = parts.pop().unwrap_or_default();
+        let issued_at = from_base36(parts.pop().unwrap_or_default()).ok_or(CallbackError::Malformed)?;
+        let requester = match requester {
+            "" => None,
+            requester => Some(from_base36(requester).ok_or(CallbackError::Malformed)?),
+        };
+
+        if now - issued_at > self.ttl.as_secs() as i64 {
+            return Err(CallbackError::Expired);
+        }
+        if requester.is_some_and(|requester| requester != user_id) {
+            return Err(CallbackError::WrongUser);
+        }
+        Ok(SignedCallback {
+            action: parts[0].to_owned(),
+            fields: parts[1..].iter().map(|field| (*field).to_owned()).collect(),
+            requester,
+            issued_at,
+        })
+    }
+
+    /// Returns the truncated, encoded signature of `payload`.
+    fn signature(&self, payload: &str) -> String {
+        let digest = self.mac(payload).finalize().into_bytes();
+        URL_SAFE_NO_PAD.encode(&digest[..SIGNATURE_LENGTH])
+    }
+
+    /// Returns the HMAC fed with `payload`.
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
+
+/// Formats a number in base 36.
+fn to_base36(value: i64) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let mut rest = value.unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        digits.push(DIGITS[(rest % 36) as usize]);
+        rest /= 36;
+        if rest == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        digits.push(b'-');
+    }
+    digits.reverse();
+    String::from_utf8(digits).expect("base 36 digits are ASCII")
+}
+
+/// Parses a number written by `to_base36`.
+fn from_base36(text: &str) -> Option<i64> {
+    i64::from_str_radix(text, 36).ok()
+}
+
+/// Handles presses of buttons carrying a `D`.
+#[async_trait]
+pub trait CallbackHandler<D: CallbackData>: Send + Sync {
+    /// Handles a press, returning the notification shown to the user, if any.
+    async fn handle(
+        &self,
+        client: &TelegramClient,
+        query: &CallbackQuery,
+        data: D,
+    ) -> Result<Option<String>, HandlerError>;
+}
+
+/// A `CallbackHandler` with its payload type erased.
+#[async_trait]
+trait Route: Send + Sync {
+    /// Decodes the payload and calls the handler, `None` if it does not decode.
+    async fn call(
+        &self,
+        client: &TelegramClient,
+        query: &CallbackQuery,
+        callback: &SignedCallback,
+    ) -> Option<Result<Option<String>, HandlerError>>;
+}
+
+/// Adapts a `CallbackHandler` to `Route`.
+struct TypedRoute<D, H> {
+    handler: H,
+    data: PhantomData<fn() -> D>,
+}
+
+#[async_trait]
+impl<D: CallbackData, H: CallbackHandler<D>> Route for TypedRoute<D, H> {
+    async fn call(
+        &self,
+        client: &TelegramClient,
+        query: &CallbackQuery,
+        callback: &SignedCallback,
+    ) -> Option<Result<Option<String>, HandlerError>> {
+        let data = callback.decode::<D>()?;
+        Some(self.handler.handle(client, query, data).await)
+    }
+}
+
+/// Checks button presses and hands them to the handler of their action.
+///
+/// Every press is answered: with the text returned by the handler, or by
+/// telling the user that the button expired or is not theirs.
+#[derive(Default)]
+pub struct CallbackRouter {
+    /// The handlers by `CallbackData::ACTION`
+    routes: HashMap<&'static str, Box<dyn Route>>,
+}
+
+impl CallbackRouter {
+    /// Creates a router without routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Routes presses of buttons carrying a `D` to `handler`.
+    pub fn with_route<D: CallbackData, H: CallbackHandler<D> + 'static>(mut self, handler: H) -> Self {
+        let route = TypedRoute { handler, data: PhantomData::<fn() -> D> };
+        self.routes.insert(D::ACTION, Box::new(route));
+        self
+    }
+
+    /// Checks a press and runs its handler.
+    ///
+    /// Returns the answer to show and whether to show it as an alert.
+    async fn route(
+        &self,
+        client: &TelegramClient,
+        query: &CallbackQuery,
+        data: &str,
+    ) -> Result<(Option<String>, bool), HandlerError> {
+        let signer = CallbackSigner::from_settings(&client.settings());
+        let callback = match signer.verify(data, query.from.id) {
+            Ok(callback) => callback,
+            Err(CallbackError::WrongUser) => return Ok((Some(WRONG_USER_ANSWER.to_string()), true)),
+            Err(error) => {
+                debug_log!(CALLBACK_LOGGER_DOMAIN, user_id = query.from.id, "Rejected button press: {}", error);
+                return Ok((Some(EXPIRED_ANSWER.to_string()), false));
+            }
+        };
+
+        let Some(route) = self.routes.get(callback.action.as_str()) else {
+            warn_log!(CALLBACK_LOGGER_DOMAIN, "No handler for button action {}", callback.action);
+            return Ok((Some(EXPIRED_ANSWER.to_string()), false));
+        };
+        match route.call(client, query, &callback).await {
+            Some(answer) => Ok((answer?, false)),
+            None => {
+                warn_log!(CALLBACK_LOGGER_DOMAIN, "Invalid fields for button action {}", callback.action);
+                Ok((Some(EXPIRED_ANSWER.to_string()), false))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl UpdateHandler for CallbackRouter {
+    async fn handle(&self, client: &TelegramClient, update: &Update) -> Result<(), HandlerError> {
+        let Some(query) = &update.callback_query else {
+            return Ok(());
+        };
+        let Some(data) = query.data.as_deref() else {
+            return Ok(());
+        };
+
+        match self.route(client, query, data).await {
+            Ok((text, show_alert)) => {
+                client.answer_callback(&query.id, text, show_alert).await?;
+                Ok(())
+            }
+            Err(error) => {
+                if let Err(answer_error) = client.answer_callback(&query.id, Some(FAILED_ANSWER.to_string()), false).await {
+                    warn_log!(CALLBACK_LOGGER_DOMAIN, "Failed to answer button press: {}", answer_error);
+                }
+                Err(error)
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Parses bot commands from message texts.
+
+/// A command sent to the bot, e.g. `/loglevel debug`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotCommand {
+    /// The command name without the leading slash and bot mention
+    pub name: String,
+    /// Everything after the command name, trimmed
+    pub args: String,
+}
+
+impl BotCommand {
+    /// Parses a message text, returning `None` if it is not a command.
+    ///
+    /// Commands addressed to a bot in group chats, such as
+    /// `/loglevel@PiliPiliBot debug`, are accepted as well.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pilipili_bot::bot::BotCommand;
+    ///
+    /// let command = BotCommand::parse("/loglevel@PiliPiliBot  debug").unwrap();
+    /// assert_eq!(command.name, "loglevel");
+    /// assert_eq!(command.args, "debug");
+    /// assert!(BotCommand::parse("hello").is_none());
+    /// ```
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim_start().strip_prefix('/')?;
+        let (head, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let name = head.split('@').next().unwrap_or_default();
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            name: name.to_ascii_lowercase(),
+            args: args.trim().to_owned(),
+        })
+    }
+
+    /// Returns whether this is the command with the given name.
+    pub fn is(&self, name: &str) -> bool {
+        self.name == name
+    }
+}
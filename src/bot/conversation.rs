@@ -0,0 +1,332 @@
+//! Keeps track of multi-step dialogs with users.
+//!
+//! A flow such as `/bind` asks a question, stores where it is as a typed
+//! state and continues when the user answers. States are stored in the
+//! database per chat and user, so a dialog survives restarts of the bot.
+//! A user has one conversation per chat; starting another replaces it.
+//!
+//! Every step has a deadline of `[telegram] conversation_timeout` seconds.
+//! Expired conversations are ignored, and `ConversationStore::run_expiry`
+//! tells the user. It only removes them `EXPIRED_GRACE` later, so a flow
+//! that has to clean up after a late answer, e.g. delete a password sent
+//! after the deadline, can still read the expired state with
+//! `ConversationStore::load`. `/cancel` ends a conversation early.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! #[serde(tag = "step", rename_all = "snake_case")]
+//! enum SignUp {
+//!     AwaitName,
+//!     Confirm { name: String },
+//! }
+//!
+//! impl ConversationState for SignUp {
+//!     const FLOW: &'static str = "signup";
+//! }
+//!
+//! store.save(key, &SignUp::AwaitName, timeout).await?;
+//! // ... on the next message of that user:
+//! if let Some(SignUp::AwaitName) = store.state::<SignUp>(key).await? {
+//!     let name = validators::username(text)?;
+//!     store.save(key, &SignUp::Confirm { name }, timeout).await?;
+//! }
+//! ```
+
+use std::fmt::{self, Display};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::infrastructure::api::TelegramClient;
+use crate::infrastructure::api::telegram_types::Update;
+use crate::infrastructure::database::{ConversationRecord, Database, DatabaseError};
+use crate::{debug_log, warn_log};
+use super::command::BotCommand;
+use super::dispatcher::{HandlerError, UpdateHandler};
+
+/// Domain identifier for conversation logs
+const CONVERSATION_LOGGER_DOMAIN: &str = "[BOT]";
+
+/// The command handled by `CancelHandler`
+const CANCEL_COMMAND: &str = "cancel";
+
+/// How long an expired conversation is kept after telling the user
+const EXPIRED_GRACE: Duration = Duration::from_secs(10 * 60);
+
+/// The state of a flow, stored between the messages of a conversation.
+///
+/// States are stored as JSON, so renaming fields or variants breaks
+/// conversations that are in progress while the bot is upgraded. Those are
+/// dropped with a warning.
+pub trait ConversationState: Serialize + DeserializeOwned + Send + Sync {
+    /// The name of the flow, by convention the command that starts it
+    const FLOW: &'static str;
+}
+
+/// Identifies a conversation: a user in a chat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConversationKey {
+    /// The chat the conversation takes place in
+    pub chat_id: i64,
+    /// The user the bot talks to
+    pub user_id: i64,
+}
+
+impl ConversationKey {
+    /// Creates the key of a user's conversation in a chat.
+    pub fn new(chat_id: i64, user_id: i64) -> Self {
+        Self { chat_id, user_id }
+    }
+
+    /// Returns the key of the conversation an update belongs to.
+    pub fn from_update(update: &Update) -> Option<Self> {
+        Some(Self::new(update.chat_id()?, update.user_id()?))
+    }
+}
+
+/// Represents an error while storing a conversation.
+#[derive(Debug)]
+pub enum ConversationError {
+    /// The database could not be read or written
+    Database(DatabaseError),
+    /// The state could not be encoded
+    Encode(serde_json::Error),
+}
+
+impl Display for ConversationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversationError::Database(error) => write!(f, "{}", error),
+            ConversationError::Encode(error) => write!(f, "invalid conversation state: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ConversationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConversationError::Database(error) => Some(error),
+            ConversationError::Encode(error) => Some(error),
+        }
+    }
+}
+
+impl From<DatabaseError> for ConversationError {
+    fn from(error: DatabaseError) -> Self {
+        ConversationError::Database(error)
+    }
+}
+
+/// A stored conversation state.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Conversation<S> {
+    /// The user may still answer
+    Active(S),
+    /// The deadline passed, but the conversation has not been removed yet
+    Expired(S),
+}
+
+/// Stores conversation states in the database.
+///
+/// Cloning is cheap, clones share the database pool.
+#[derive(Debug, Clone)]
+pub struct ConversationStore {
+    database: Database,
+}
+
+impl ConversationStore {
+    /// Creates a store keeping its conversations in `database`.
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Starts a conversation or moves it to the next step.
+    ///
+    /// The user has `timeout` to answer. Any other conversation of the user
+    /// in that chat is replaced.
+    pub async fn save<S: ConversationState>(
+        &self,
+        key: ConversationKey,
+        state: &S,
+        timeout: Duration,
+    ) -> Result<(), ConversationError> {
+        let now = chrono::Utc::now().timestamp();
+        let record = ConversationRecord {
+            chat_id: key.chat_id,
+            user_id: key.user_id,
+            flow: S::FLOW.to_owned(),
+            state: serde_json::to_string(state).map_err(ConversationError::Encode)?,
+            expires_at: now + timeout.as_secs() as i64,
+            updated_at: now,
+            expired_at: None,
+        };
+        self.database.save_conversation(&record).await?;
+        debug_log!(
+            CONVERSATION_LOGGER_DOMAIN,
+            user_id = key.user_id,
+            "Saved /{} conversation",
+            S::FLOW
+        );
+        Ok(())
+    }
+
+    /// Returns the state of the user's conversation if it belongs to flow `S`.
+    ///
+    /// Returns `None` if there is no conversation, it belongs to another
+    /// flow or it expired. A state that no longer decodes is removed.
+    pub async fn state<S: ConversationState>(&self, key: ConversationKey) -> Result<Option<S>, ConversationError> {
+        Ok(match self.load(key).await? {
+            Some(Conversation::Active(state)) => Some(state),
+            _ => None,
+        })
+    }
+
+    /// Returns the user's conversation if it belongs to flow `S`, expired or not.
+    ///
+    /// Returns `None` if there is no conversation or it belongs to another
+    /// flow. A state that no longer decodes is removed.
+    pub async fn load<S: ConversationState>(
+        &self,
+        key: ConversationKey,
+    ) -> Result<Option<Conversation<S>>, ConversationError> {
+        let Some(record) = self.database.conversation(key.chat_id, key.user_id).await? else {
+            return Ok(None);
+        };
+        if record.flow != S::FLOW {
+            return Ok(None);
+        }
+        match serde_json::from_str(&record.state) {
+            Ok(state) if record.expires_at > chrono::Utc::now().timestamp() => Ok(Some(Conversation::Active(state))),
+            Ok(state) => Ok(Some(Conversation::Expired(state))),
+            Err(error) => {
+                warn_log!(
+                    CONVERSATION_LOGGER_DOMAIN,
+                    user_id = key.user_id,
+                    "Dropping /{} conversation with an outdated state: {}",
+                    record.flow,
+                    error
+                );
+                self.database.delete_conversation(key.chat_id, key.user_id).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the flow of the user's conversation, unless it expired.
+    pub async fn flow(&self, key: ConversationKey) -> Result<Option<String>, ConversationError> {
+        Ok(self.active(key).await?.map(|record| record.flow))
+    }
+
+    /// Ends the user's conversation, returning whether there was one.
+    pub async fn finish(&self, key: ConversationKey) -> Result<bool, ConversationError> {
+        Ok(self.database.delete_conversation(key.chat_id, key.user_id).await?)
+    }
+
+    /// Tells the users of expired conversations every `interval`, and
+    /// removes the conversations `EXPIRED_GRACE` later.
+    ///
+    /// Runs until the task is dropped.
+    pub async fn run_expiry(self, telegram: Arc<TelegramClient>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let now = chrono::Utc::now().timestamp();
+            let grace_start = now - EXPIRED_GRACE.as_secs() as i64;
+            if let Err(error) = self.database.delete_expired_conversations(grace_start).await {
+                warn_log!(CONVERSATION_LOGGER_DOMAIN, "Failed to remove expired conversations: {}", error);
+            }
+            let expired = match self.database.mark_expired_conversations(now).await {
+                Ok(expired) => expired,
+                Err(error) => {
+                    warn_log!(CONVERSATION_LOGGER_DOMAIN, "Failed to read expired conversations: {}", error);
+                    continue;
+                }
+            };
+            for record in expired {
+                if let Err(error) = telegram.send_message(record.chat_id, expired_reply(&record.flow)).await {
+                    warn_log!(
+                        CONVERSATION_LOGGER_DOMAIN,
+                        user_id = record.user_id,
+                        "Failed to report expired conversation: {}",
+                        error
+                    );
+                }
+            }
+        }
+    }
+
+    /// Returns the user's conversation unless it expired.
+    async fn active(&self, key: ConversationKey) -> Result<Option<ConversationRecord>, ConversationError> {
+        let now = chrono::Utc::now().timestamp();
+        Ok(self
+            .database
+            .conversation(key.chat_id, key.user_id)
+            .await?
+            .filter(|record| record.expires_at > now))
+    }
+}
+
+/// Returns the message telling a user that a `/flow` conversation timed out.
+pub(crate) fn expired_reply(flow: &str) -> String {
+    format!("⌛ /{} timed out, send it again to start over", flow)
+}
+
+/// Describes a duration for users, e.g. `5 minutes`.
+pub(crate) fn describe_seconds(seconds: u64) -> String {
+    match seconds {
+        60 => "1 minute".to_string(),
+        seconds if seconds % 60 == 0 => format!("{} minutes", seconds / 60),
+        seconds => format!("{} seconds", seconds),
+    }
+}
+
+/// Handles the `/cancel` command, which ends the sender's conversation.
+pub struct CancelHandler {
+    /// The conversations of all flows
+    store: ConversationStore,
+}
+
+impl CancelHandler {
+    /// Creates a handler ending conversations in `store`.
+    pub fn new(store: ConversationStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl UpdateHandler for CancelHandler {
+    async fn handle(&self, client: &TelegramClient, update: &Update) -> Result<(), HandlerError> {
+        let Some(command) = update
+            .message()
+            .and_then(|message| message.text.as_deref())
+            .and_then(BotCommand::parse)
+        else {
+            return Ok(());
+        };
+        if !command.is(CANCEL_COMMAND) {
+            return Ok(());
+        }
+        let Some(key) = ConversationKey::from_update(update) else {
+            return Ok(());
+        };
+
+        let reply = match self.store.flow(key).await? {
+            Some(flow) => {
+                self.store.finish(key).await?;
+                format!("Cancelled /{}", flow)
+            }
+            None => "Nothing to cancel".to_string(),
+        };
+        client.send_message(key.chat_id, reply).await?;
+        Ok(())
+    }
+
+    fn commands(&self) -> &'static [&'static str] {
+        &[CANCEL_COMMAND]
+    }
+}
@@ -0,0 +1,89 @@
+//! Deletes messages some time after they were sent.
+//!
+//! Messages such as a new password are scheduled with `DeletionQueue::schedule`
+//! and stored in the database. `DeletionQueue::run` deletes them once they
+//! are due. Its first pass runs right away, so messages that came due while
+//! the bot was down are deleted at startup. Messages stay scheduled until
+//! Telegram deleted them or refused for good.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::infrastructure::api::TelegramClient;
+use crate::infrastructure::database::{Database, DatabaseError, PendingDeletion};
+use crate::warn_log;
+
+/// Domain identifier for message deletion logs
+const DELETION_LOGGER_DOMAIN: &str = "[BOT]";
+
+/// Stores messages to delete and deletes them when they are due.
+///
+/// Cloning is cheap, clones share the database pool.
+#[derive(Debug, Clone)]
+pub struct DeletionQueue {
+    database: Database,
+}
+
+impl DeletionQueue {
+    /// Creates a queue keeping its messages in `database`.
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Schedules a message for deletion after `delay`.
+    pub async fn schedule(&self, chat_id: i64, message_id: i64, delay: Duration) -> Result<(), DatabaseError> {
+        let deletion = PendingDeletion {
+            chat_id,
+            message_id,
+            delete_at: chrono::Utc::now().timestamp() + delay.as_secs() as i64,
+        };
+        self.database.schedule_deletion(&deletion).await
+    }
+
+    /// Deletes due messages every `interval`.
+    ///
+    /// Runs until the task is dropped.
+    pub async fn run(self, telegram: Arc<TelegramClient>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(error) = self.delete_due(&telegram, chrono::Utc::now().timestamp()).await {
+                warn_log!(DELETION_LOGGER_DOMAIN, "Failed to process pending message deletions: {}", error);
+            }
+        }
+    }
+
+    /// Deletes the messages due by `now`.
+    ///
+    /// A message stays pending if Telegram cannot be reached or asks to try
+    /// again later. A message Telegram refuses to delete, e.g. because the
+    /// user already deleted it, is dropped with a warning.
+    pub async fn delete_due(&self, telegram: &TelegramClient, now: i64) -> Result<(), DatabaseError> {
+        for deletion in self.database.due_deletions(now).await? {
+            match telegram.delete_message(deletion.chat_id, deletion.message_id).await {
+                Ok(_) => {}
+                Err(error) if error.is_retryable() => {
+                    warn_log!(
+                        DELETION_LOGGER_DOMAIN,
+                        chat_id = deletion.chat_id,
+                        "Failed to delete message {}, trying again: {}",
+                        deletion.message_id,
+                        error
+                    );
+                    continue;
+                }
+                Err(error) => {
+                    warn_log!(
+                        DELETION_LOGGER_DOMAIN,
+                        chat_id = deletion.chat_id,
+                        "Telegram refused to delete message {}: {}",
+                        deletion.message_id,
+                        error
+                    );
+                }
+            }
+            self.database.remove_deletion(deletion.chat_id, deletion.message_id).await?;
+        }
+        Ok(())
+    }
+}
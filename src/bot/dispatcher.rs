@@ -0,0 +1,194 @@
+//! Receives Telegram updates and hands them to the registered handlers.
+//!
+//! Updates are fetched with long polling. Each update is handled in its own
+//! task, inside an `update` span carrying the update, chat and user ids and a
+//! fresh `RequestId`. Network requests made by the handlers become child spans
+//! of it and send the same id as `X-Request-Id`, so the complete interaction
+//! can be found in the logs with a filter such as `[update{user_id=42}]=debug`.
+//!
+//! Commands claimed by a handler through `UpdateHandler::commands` are counted
+//! in the `pilipili_bot_commands_total` metric, as failed if any handler
+//! returned an error.
+
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tracing::{Instrument, Span};
+
+use crate::infrastructure::api::telegram_types::Update;
+use crate::infrastructure::api::{TelegramClient, TelegramError};
+use crate::infrastructure::metrics::BotMetrics;
+use crate::infrastructure::network::RequestId;
+use crate::services::permissions::Permission;
+use crate::{debug_log, error_log, warn_log};
+use super::command::BotCommand;
+
+/// Domain identifier for dispatcher logs
+const BOT_LOGGER_DOMAIN: &str = "[BOT]";
+
+/// Delay before polling again after a failed poll
+const POLL_ERROR_DELAY: Duration = Duration::from_secs(5);
+
+/// The error type returned by update handlers.
+pub type HandlerError = Box<dyn Error + Send + Sync>;
+
+/// Handles incoming updates.
+///
+/// Every registered handler is called for every update and decides on its
+/// own whether the update is relevant to it.
+#[async_trait]
+pub trait UpdateHandler: Send + Sync {
+    /// Handles a single update.
+    async fn handle(&self, client: &TelegramClient, update: &Update) -> Result<(), HandlerError>;
+
+    /// Returns the names of the commands this handler answers.
+    fn commands(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Returns the permission needed to run `command`, `None` if anyone may run it.
+    ///
+    /// Checked by `access::authorize`.
+    fn permission(&self, _command: &str) -> Option<Permission> {
+        None
+    }
+}
+
+/// Polls Telegram for updates and dispatches them to the handlers.
+pub struct Dispatcher {
+    /// The client used for polling and passed to the handlers
+    client: Arc<TelegramClient>,
+    /// The registered handlers, in registration order
+    handlers: Arc<Vec<Arc<dyn UpdateHandler>>>,
+    /// Long polling timeout in seconds
+    poll_timeout: u64,
+    /// Counts the handled commands
+    metrics: BotMetrics,
+}
+
+impl Dispatcher {
+    /// Creates a dispatcher without handlers.
+    pub fn new(client: Arc<TelegramClient>) -> Self {
+        Self {
+            client,
+            handlers: Arc::new(Vec::new()),
+            poll_timeout: 30,
+            metrics: BotMetrics::default(),
+        }
+    }
+
+    /// Registers a handler.
+    pub fn with_handler(mut self, handler: impl UpdateHandler + 'static) -> Self {
+        Arc::make_mut(&mut self.handlers).push(Arc::new(handler));
+        self
+    }
+
+    /// Records command metrics with `metrics` instead of the global registry.
+    pub fn with_metrics(mut self, metrics: BotMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Sets the long polling timeout in seconds.
+    pub fn with_poll_timeout(mut self, poll_timeout: u64) -> Self {
+        self.poll_timeout = poll_timeout;
+        self
+    }
+
+    /// Polls for updates forever, handling each one in its own task.
+    pub async fn run(self) {
+        let mut offset = None;
+        loop {
+            let updates = match self.client.get_updates(offset, self.poll_timeout).await {
+                Ok(updates) => updates,
+                Err(error) => {
+                    let delay = match &error {
+                        TelegramError::Api { retry_after: Some(retry_after), .. } => *retry_after,
+                        _ => POLL_ERROR_DELAY,
+                    };
+                    warn_log!(
+                        BOT_LOGGER_DOMAIN,
+                        "Failed to poll updates, retrying in {:?}: {}",
+                        delay,
+                        error
+                    );
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+            };
+
+            for update in updates {
+                offset = Some(update.update_id + 1);
+                let client = self.client.clone();
+                let handlers = self.handlers.clone();
+                let metrics = self.metrics.clone();
+                tokio::spawn(Self::handle_update(client, handlers, metrics, update));
+            }
+        }
+    }
+
+    /// Handles a single update and waits until all handlers are done.
+    pub async fn dispatch(&self, update: Update) {
+        Self::handle_update(self.client.clone(), self.handlers.clone(), self.metrics.clone(), update).await;
+    }
+
+    /// Runs all handlers for `update` inside its span and request id scope.
+    async fn handle_update(
+        client: Arc<TelegramClient>,
+        handlers: Arc<Vec<Arc<dyn UpdateHandler>>>,
+        metrics: BotMetrics,
+        update: Update,
+    ) {
+        let request_id = RequestId::generate();
+        let span = update_span(&update, &request_id);
+        let command = update
+            .message()
+            .and_then(|message| message.text.as_deref())
+            .and_then(BotCommand::parse)
+            .filter(|command| handlers.iter().any(|handler| handler.commands().contains(&command.name.as_str())));
+
+        let handling = async {
+            debug_log!(BOT_LOGGER_DOMAIN, "Handling update");
+            let mut succeeded = true;
+            for handler in handlers.iter() {
+                if let Err(error) = handler.handle(&client, &update).await {
+                    succeeded = false;
+                    error_log!(
+                        BOT_LOGGER_DOMAIN,
+                        "Failed to handle update {}: {}",
+                        update.update_id,
+                        error
+                    );
+                }
+            }
+            if let Some(command) = &command {
+                metrics.command_handled(&command.name, succeeded);
+            }
+        };
+
+        request_id.scope(handling.instrument(span)).await;
+    }
+}
+
+/// Creates the span an update is handled in.
+///
+/// The span carries the `update_id`, `chat_id`, `user_id` and `request_id`
+/// fields; ids that the update does not have are left empty.
+pub fn update_span(update: &Update, request_id: &RequestId) -> Span {
+    let span = tracing::info_span!(
+        "update",
+        update_id = update.update_id,
+        chat_id = tracing::field::Empty,
+        user_id = tracing::field::Empty,
+        request_id = %request_id,
+    );
+    if let Some(chat_id) = update.chat_id() {
+        span.record("chat_id", chat_id);
+    }
+    if let Some(user_id) = update.user_id() {
+        span.record("user_id", user_id);
+    }
+    span
+}
@@ -0,0 +1,164 @@
+//! Builds inline keyboards and splits long lists into pages.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let keyboard = KeyboardBuilder::new(&signer)
+//!     .with_requester(user_id)
+//!     .button("✅ Yes", &Confirm(true))?
+//!     .button("❌ No", &Confirm(false))?
+//!     .row()
+//!     .pagination(&page, |number| UsersPage { number })?
+//!     .build();
+//! client.send_keyboard(chat_id, text, keyboard).await?;
+//! ```
+
+use crate::infrastructure::api::telegram_types::{InlineKeyboardButton, InlineKeyboardMarkup};
+use super::callback::{CallbackData, CallbackError, CallbackSigner};
+
+/// The label of the button to the previous page
+const PREVIOUS_LABEL: &str = "‹ Previous";
+
+/// The label of the button to the next page
+const NEXT_LABEL: &str = "Next ›";
+
+/// Builds an inline keyboard row by row.
+///
+/// Buttons are appended to the current row until `row` starts a new one.
+/// Their data is signed with the given signer.
+pub struct KeyboardBuilder<'a> {
+    /// Signs the data of the buttons
+    signer: &'a CallbackSigner,
+    /// The only user allowed to press the buttons
+    requester: Option<i64>,
+    /// The completed rows
+    rows: Vec<Vec<InlineKeyboardButton>>,
+    /// The row buttons are appended to
+    current: Vec<InlineKeyboardButton>,
+}
+
+impl<'a> KeyboardBuilder<'a> {
+    /// Creates an empty keyboard whose buttons anyone may press.
+    pub fn new(signer: &'a CallbackSigner) -> Self {
+        Self {
+            signer,
+            requester: None,
+            rows: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+
+    /// Restricts the buttons added afterwards to the given user.
+    pub fn with_requester(mut self, user_id: i64) -> Self {
+        self.requester = Some(user_id);
+        self
+    }
+
+    /// Appends a button carrying `data` to the current row.
+    pub fn button<D: CallbackData>(mut self, text: impl Into<String>, data: &D) -> Result<Self, CallbackError> {
+        let button = self.signer.button(text, data, self.requester)?;
+        self.current.push(button);
+        Ok(self)
+    }
+
+    /// Appends a button opening `url` to the current row.
+    pub fn url(mut self, text: impl Into<String>, url: impl Into<String>) -> Self {
+        self.current.push(InlineKeyboardButton {
+            text: text.into(),
+            callback_data: None,
+            url: Some(url.into()),
+        });
+        self
+    }
+
+    /// Starts a new row.
+    pub fn row(mut self) -> Self {
+        if !self.current.is_empty() {
+            self.rows.push(std::mem::take(&mut self.current));
+        }
+        self
+    }
+
+    /// Adds a row with buttons to the previous and next page.
+    ///
+    /// `to_page` creates the data of the button leading to a page number.
+    /// Nothing is added if there is a single page.
+    pub fn pagination<T, D: CallbackData>(
+        self,
+        page: &Page<'_, T>,
+        to_page: impl Fn(usize) -> D,
+    ) -> Result<Self, CallbackError> {
+        let mut builder = self.row();
+        if page.has_previous() {
+            builder = builder.button(PREVIOUS_LABEL, &to_page(page.number - 1))?;
+        }
+        if page.has_next() {
+            builder = builder.button(NEXT_LABEL, &to_page(page.number + 1))?;
+        }
+        Ok(builder.row())
+    }
+
+    /// Returns the keyboard.
+    pub fn build(self) -> InlineKeyboardMarkup {
+        let mut rows = self.row().rows;
+        rows.retain(|row| !row.is_empty());
+        InlineKeyboardMarkup { inline_keyboard: rows }
+    }
+}
+
+/// One page of a list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page<'a, T> {
+    /// The items on this page
+    pub items: &'a [T],
+    /// The number of this page, starting at 0
+    pub number: usize,
+    /// The number of pages, at least 1
+    pub count: usize,
+}
+
+impl<'a, T> Page<'a, T> {
+    /// Returns page `number` of `items` with `per_page` items each.
+    ///
+    /// A number past the end returns the last page, so buttons pressed after
+    /// the list shrank still show something.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pilipili_bot::bot::keyboard::Page;
+    ///
+    /// let items = [1, 2, 3, 4, 5];
+    /// let page = Page::new(&items, 2, 2);
+    /// assert_eq!(page.items, &[5]);
+    /// assert_eq!(page.label(), "3/3");
+    /// assert_eq!(Page::new(&items, 9, 2).number, 2);
+    /// ```
+    pub fn new(items: &'a [T], number: usize, per_page: usize) -> Self {
+        let per_page = per_page.max(1);
+        let count = items.len().div_ceil(per_page).max(1);
+        let number = number.min(count - 1);
+        let start = number * per_page;
+        let end = (start + per_page).min(items.len());
+        Self {
+            items: &items[start.min(end)..end],
+            number,
+            count,
+        }
+    }
+
+    /// Returns whether there is a page before this one.
+    pub fn has_previous(&self) -> bool {
+        self.number > 0
+    }
+
+    /// Returns whether there is a page after this one.
+    pub fn has_next(&self) -> bool {
+        self.number + 1 < self.count
+    }
+
+    /// Describes the position for users, e.g. `2/5`.
+    pub fn label(&self) -> String {
+        format!("{}/{}", self.number + 1, self.count)
+    }
+}
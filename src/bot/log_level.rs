@@ -0,0 +1,95 @@
+//! Lets admins change the log filter without restarting the bot.
+//!
+//! `/loglevel` replies with the active filter, `/loglevel <filter>` replaces
+//! it. The filter uses the `EnvFilter` syntax, so both a plain level such as
+//! `debug` and per-domain directives such as `info,NETWORK=trace` are
+//! accepted. Both require `Permission::ChangeLogLevel`.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::context::AppContext;
+use crate::info_log;
+use crate::infrastructure::api::TelegramClient;
+use crate::infrastructure::api::telegram_types::Update;
+use crate::infrastructure::logger::LoggerHandle;
+use crate::services::permissions::Permission;
+use super::access::{audit, authorize, OK_RESULT};
+use super::command::BotCommand;
+use super::dispatcher::{HandlerError, UpdateHandler};
+
+/// Domain identifier for log level command logs
+const LOG_LEVEL_LOGGER_DOMAIN: &str = "[BOT]";
+
+/// The command handled by `LogLevelHandler`
+const LOG_LEVEL_COMMAND: &str = "loglevel";
+
+/// Handles the `/loglevel` admin command.
+pub struct LogLevelHandler {
+    /// The handle to the filter of the running logger
+    logger: LoggerHandle,
+    /// Provides the roles and the audit log
+    context: Arc<AppContext>,
+}
+
+impl LogLevelHandler {
+    /// Creates a handler changing the filter of `logger`.
+    pub fn new(logger: LoggerHandle, context: Arc<AppContext>) -> Self {
+        Self { logger, context }
+    }
+
+    /// Applies the command and returns the reply text and the audit result.
+    fn apply(&self, args: &str) -> (String, String) {
+        if args.is_empty() {
+            return match self.logger.current_filter() {
+                Ok(filter) => (format!("Current log filter: {}", filter), OK_RESULT.to_string()),
+                Err(error) => (format!("Failed to read log filter: {}", error), error.to_string()),
+            };
+        }
+
+        match self.logger.set_filter(args) {
+            Ok(()) => {
+                info_log!(LOG_LEVEL_LOGGER_DOMAIN, filter = %args, "Log filter changed");
+                (format!("Log filter changed to: {}", args), OK_RESULT.to_string())
+            }
+            Err(error) => (error.to_string(), error.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl UpdateHandler for LogLevelHandler {
+    async fn handle(&self, client: &TelegramClient, update: &Update) -> Result<(), HandlerError> {
+        let Some(command) = update
+            .message()
+            .and_then(|message| message.text.as_deref())
+            .and_then(BotCommand::parse)
+        else {
+            return Ok(());
+        };
+        if !command.is(LOG_LEVEL_COMMAND) {
+            return Ok(());
+        }
+
+        let (Some(chat_id), Some(user_id)) = (update.chat_id(), update.user_id()) else {
+            return Ok(());
+        };
+        if authorize(&self.context, self, user_id, &command).await?.is_none() {
+            return Ok(());
+        }
+
+        let (reply, result) = self.apply(&command.args);
+        audit(&self.context, user_id, None, &command, &result).await;
+        client.send_message(chat_id, reply).await?;
+        Ok(())
+    }
+
+    fn commands(&self) -> &'static [&'static str] {
+        &[LOG_LEVEL_COMMAND]
+    }
+
+    fn permission(&self, command: &str) -> Option<Permission> {
+        self.commands().contains(&command).then_some(Permission::ChangeLogLevel)
+    }
+}
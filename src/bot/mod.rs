@@ -0,0 +1,46 @@
+//! The Telegram bot built on top of the infrastructure layer.
+//!
+//! This module provides:
+//! - A long polling update dispatcher
+//! - Tracing spans and request ids per incoming update
+//! - Command parsing and the `/loglevel` and `/servers` admin commands
+//! - Role-based permissions managed with `/grant` and `/revoke`, and an audit log
+//! - Conversations for multi-step dialogs, ended with `/cancel`
+//! - Inline keyboards with signed button data, routed by action
+//! - Binding Telegram users to media server accounts with `/bind`
+//! - Password resets with `/resetpw`, whose messages are deleted later
+//! - Looking up and managing users with `/user`, and recording who talks to the bot
+//! - Log alerts sent to an admin chat
+
+pub mod dispatcher;
+pub mod command;
+pub mod access;
+pub mod log_level;
+pub mod alerts;
+pub mod servers;
+pub mod roles;
+pub mod conversation;
+pub mod deletion;
+pub mod validators;
+pub mod throttle;
+pub mod callback;
+pub mod keyboard;
+pub mod bind;
+pub mod reset_password;
+pub mod activity;
+pub mod user_admin;
+
+pub use dispatcher::{Dispatcher, HandlerError, UpdateHandler};
+pub use command::BotCommand;
+pub use log_level::LogLevelHandler;
+pub use alerts::TelegramAlertSink;
+pub use servers::ServersHandler;
+pub use roles::RolesHandler;
+pub use conversation::{CancelHandler, Conversation, ConversationKey, ConversationState, ConversationStore};
+pub use deletion::DeletionQueue;
+pub use callback::{CallbackData, CallbackHandler, CallbackRouter, CallbackSigner};
+pub use keyboard::{KeyboardBuilder, Page};
+pub use bind::BindHandler;
+pub use reset_password::ResetPasswordHandler;
+pub use activity::ActivityHandler;
+pub use user_admin::UserAdminHandler;
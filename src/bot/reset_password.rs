@@ -0,0 +1,415 @@
+//! Resets the media server password of a bound account.
+//!
+//! `/resetpw` asks for confirmation with Yes and No buttons, then sends a
+//! random password to the user and sets it. `/resetpw set` asks the user for
+//! a new password instead.
+//! Users with accounts on several servers name the server first, e.g.
+//! `/resetpw eu` or `/resetpw eu set`.
+//!
+//! Passwords are only handled in private chats. A password sent by the user
+//! is deleted right away, a password sent by the bot after
+//! `[password] delete_after` seconds by the `DeletionQueue`, even if the bot
+//! restarts in between.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::context::AppContext;
+use crate::infrastructure::api::TelegramClient;
+use crate::infrastructure::api::telegram_types::{CallbackQuery, ChatKind, InlineKeyboardMarkup, Message, Update};
+use crate::infrastructure::database::AccountBinding;
+use crate::services::password::generate_password;
+use crate::{info_log, warn_log};
+use super::callback::{CallbackData, CallbackHandler, CallbackSigner};
+use super::command::BotCommand;
+use super::conversation::{
+    describe_seconds, expired_reply, Conversation, ConversationKey, ConversationState, ConversationStore,
+};
+use super::deletion::DeletionQueue;
+use super::dispatcher::{HandlerError, UpdateHandler};
+use super::keyboard::KeyboardBuilder;
+use super::validators;
+
+/// Domain identifier for password reset logs
+const RESET_PASSWORD_LOGGER_DOMAIN: &str = "[BOT]";
+
+/// The command handled by `ResetPasswordHandler`
+const RESET_PASSWORD_COMMAND: &str = "resetpw";
+
+/// The argument asking for a password chosen by the user
+const SET_ARGUMENT: &str = "set";
+
+/// The reply to an answer for a reset that already ended
+const NOT_PENDING: &str = "This request is no longer pending";
+
+/// The steps of a password reset.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+enum ResetPasswordState {
+    /// Waiting for the user to confirm a random password
+    Confirm { binding: AccountBinding },
+    /// Waiting for the password chosen by the user
+    AwaitPassword { binding: AccountBinding },
+}
+
+impl ConversationState for ResetPasswordState {
+    const FLOW: &'static str = RESET_PASSWORD_COMMAND;
+}
+
+/// The data of the Yes and No buttons confirming a random password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResetPasswordChoice {
+    /// Whether the user pressed Yes
+    pub confirm: bool,
+}
+
+impl CallbackData for ResetPasswordChoice {
+    const ACTION: &'static str = "rp";
+
+    fn fields(&self) -> Vec<String> {
+        vec![if self.confirm { "y" } else { "n" }.to_string()]
+    }
+
+    fn from_fields(fields: &[&str]) -> Option<Self> {
+        match fields {
+            ["y"] => Some(Self { confirm: true }),
+            ["n"] => Some(Self { confirm: false }),
+            _ => None,
+        }
+    }
+}
+
+/// Handles the `/resetpw` command, the answers users send after it and the
+/// presses of its confirmation buttons.
+#[derive(Clone)]
+pub struct ResetPasswordHandler {
+    /// Provides the servers, the database, the Telegram client and the settings
+    context: Arc<AppContext>,
+    /// Stores where each reset stands
+    conversations: ConversationStore,
+    /// Deletes sent passwords later
+    deletions: DeletionQueue,
+}
+
+impl ResetPasswordHandler {
+    /// Creates a handler resetting passwords on the servers in `context`.
+    pub fn new(context: Arc<AppContext>) -> Self {
+        let conversations = ConversationStore::new(context.database().clone());
+        let deletions = DeletionQueue::new(context.database().clone());
+        Self { context, conversations, deletions }
+    }
+
+    /// Returns how long users have to answer.
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.context.config().telegram.conversation_timeout)
+    }
+
+    /// Handles `/resetpw`, returning the reply and its keyboard.
+    async fn start(
+        &self,
+        key: ConversationKey,
+        args: &str,
+    ) -> Result<(String, Option<InlineKeyboardMarkup>), HandlerError> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        let (server, user_chosen) = match words.as_slice() {
+            [] => (None, false),
+            [word] if *word == SET_ARGUMENT => (None, true),
+            [server] => (Some(*server), false),
+            [server, word] if *word == SET_ARGUMENT => (Some(*server), true),
+            _ => {
+                let usage = format!("Usage: /{} [server] [{}]", RESET_PASSWORD_COMMAND, SET_ARGUMENT);
+                return Ok((usage, None));
+            }
+        };
+        let binding = match self.binding(key.user_id, server).await? {
+            Ok(binding) => binding,
+            Err(reply) => return Ok((reply, None)),
+        };
+
+        let (reply, keyboard) = if user_chosen {
+            let reply = format!(
+                "Send the new password for {} on {} within {}. I will delete your message right away. \
+                 Send /cancel to keep the current password.",
+                binding.emby_name,
+                binding.server,
+                describe_seconds(self.timeout().as_secs())
+            );
+            (reply, None)
+        } else {
+            let reply = format!(
+                "Reset the password of {} on {} to a random one?",
+                binding.emby_name, binding.server
+            );
+            let signer = CallbackSigner::from_settings(&self.context.config().telegram);
+            let keyboard = KeyboardBuilder::new(&signer)
+                .with_requester(key.user_id)
+                .button("✅ Yes", &ResetPasswordChoice { confirm: true })?
+                .button("❌ No", &ResetPasswordChoice { confirm: false })?
+                .build();
+            (reply, Some(keyboard))
+        };
+        let state = if user_chosen {
+            ResetPasswordState::AwaitPassword { binding }
+        } else {
+            ResetPasswordState::Confirm { binding }
+        };
+        self.conversations.save(key, &state, self.timeout()).await?;
+        Ok((reply, keyboard))
+    }
+
+    /// Handles an answer within a reset, returning the reply.
+    async fn answer(
+        &self,
+        client: &TelegramClient,
+        message: &Message,
+        key: ConversationKey,
+        text: &str,
+    ) -> Result<Option<String>, HandlerError> {
+        let state = match self.conversations.load::<ResetPasswordState>(key).await? {
+            Some(Conversation::Active(state)) => state,
+            Some(Conversation::Expired(state)) => {
+                if let ResetPasswordState::AwaitPassword { .. } = state {
+                    delete_password_message(client, message, key).await;
+                }
+                self.conversations.finish(key).await?;
+                return Ok(Some(expired_reply(RESET_PASSWORD_COMMAND)));
+            }
+            None => return Ok(None),
+        };
+
+        match state {
+            ResetPasswordState::Confirm { binding } => match validators::yes_no(text) {
+                Ok(confirm) => self.confirm(key, &binding, confirm).await.map(Some),
+                Err(reason) => Ok(Some(reason)),
+            },
+            ResetPasswordState::AwaitPassword { binding } => {
+                delete_password_message(client, message, key).await;
+                let min_length = self.context.config().password.min_length;
+                let password = match validators::password(text, min_length) {
+                    Ok(password) => password,
+                    Err(reason) => return Ok(Some(format!("{}. Send another password.", reason))),
+                };
+
+                if !self.conversations.finish(key).await? {
+                    return Ok(Some(NOT_PENDING.to_string()));
+                }
+                Ok(Some(match self.apply(&binding, &password).await {
+                    Ok(()) => format!("✅ Password of {} on {} changed", binding.emby_name, binding.server),
+                    Err(reply) => reply,
+                }))
+            }
+        }
+    }
+
+    /// Ends the confirmation, resetting the password if `confirm` is set.
+    ///
+    /// Does nothing if the confirmation already ended, e.g. because the
+    /// user pressed a button and answered with text at the same time.
+    async fn confirm(
+        &self,
+        key: ConversationKey,
+        binding: &AccountBinding,
+        confirm: bool,
+    ) -> Result<String, HandlerError> {
+        if !self.conversations.finish(key).await? {
+            Ok(NOT_PENDING.to_string())
+        } else if confirm {
+            Ok(self.reset_random(key.chat_id, binding).await.unwrap_or_else(|reply| reply))
+        } else {
+            Ok("The password was not changed".to_string())
+        }
+    }
+
+    /// Sends a random password to `chat_id` and sets it.
+    ///
+    /// The password is only set once it reached the user, so a user who
+    /// cannot be messaged keeps the current one. If setting it fails, the
+    /// message is deleted right away.
+    ///
+    /// Returns the reply, or the reply explaining why the password was not changed.
+    pub(crate) async fn reset_random(
+        &self,
+        chat_id: i64,
+        binding: &AccountBinding,
+    ) -> Result<String, String> {
+        let settings = self.context.config().password.clone();
+        let password = generate_password(settings.length);
+        let text = format!(
+            "🔑 New password for {} on {}:\n{}\n\nThis message is deleted in {}.",
+            binding.emby_name,
+            binding.server,
+            password,
+            describe_seconds(settings.delete_after)
+        );
+        let message = match self.context.telegram().send_secret(chat_id, text).await {
+            Ok(message) => message,
+            Err(error) => {
+                warn_log!(
+                    RESET_PASSWORD_LOGGER_DOMAIN,
+                    user_id = binding.telegram_id,
+                    "Failed to send the new password of {}: {}",
+                    binding.emby_name,
+                    error
+                );
+                return Err(format!(
+                    "The password of {} on {} was not changed, as the new one could not be sent",
+                    binding.emby_name, binding.server
+                ));
+            }
+        };
+
+        if let Err(reply) = self.apply(binding, &password).await {
+            if self.context.telegram().delete_message(chat_id, message.message_id).await.is_err() {
+                self.schedule_deletion(chat_id, message.message_id, Duration::ZERO, binding).await;
+            }
+            return Err(reply);
+        }
+        let delay = Duration::from_secs(settings.delete_after);
+        self.schedule_deletion(chat_id, message.message_id, delay, binding).await;
+        Ok(format!("✅ Password of {} on {} reset", binding.emby_name, binding.server))
+    }
+
+    /// Schedules the deletion of a message carrying the password of `binding`.
+    async fn schedule_deletion(&self, chat_id: i64, message_id: i64, delay: Duration, binding: &AccountBinding) {
+        if let Err(error) = self.deletions.schedule(chat_id, message_id, delay).await {
+            warn_log!(
+                RESET_PASSWORD_LOGGER_DOMAIN,
+                user_id = binding.telegram_id,
+                "Failed to schedule deletion of password message: {}",
+                error
+            );
+        }
+    }
+
+    /// Returns the account named by `server`, or the only one, or the reply explaining why there is none.
+    async fn binding(
+        &self,
+        telegram_id: i64,
+        server: Option<&str>,
+    ) -> Result<Result<AccountBinding, String>, HandlerError> {
+        let mut accounts = self.context.database().accounts_of(telegram_id).await?;
+        Ok(match server {
+            Some(server) => accounts
+                .into_iter()
+                .find(|account| account.server == server)
+                .ok_or_else(|| format!("You have no account on {}", server)),
+            None if accounts.len() == 1 => Ok(accounts.remove(0)),
+            None if accounts.is_empty() => Err("You have no account yet, use /bind first".to_string()),
+            None => {
+                let servers: Vec<&str> = accounts.iter().map(|account| account.server.as_str()).collect();
+                Err(format!(
+                    "You have accounts on several servers, name one: /{} <{}>",
+                    RESET_PASSWORD_COMMAND,
+                    servers.join("|")
+                ))
+            }
+        })
+    }
+
+    /// Sets the password on the server, returning the reply on failure.
+    async fn apply(&self, binding: &AccountBinding, password: &str) -> Result<(), String> {
+        let Some(server) = self.context.servers().get(&binding.server) else {
+            return Err(format!("{} is no longer configured", binding.server));
+        };
+        match server.set_password(&binding.emby_user_id, password).await {
+            Ok(()) => {
+                info_log!(
+                    RESET_PASSWORD_LOGGER_DOMAIN,
+                    user_id = binding.telegram_id,
+                    server = %binding.server,
+                    "Reset password of {}",
+                    binding.emby_name
+                );
+                Ok(())
+            }
+            Err(error) => {
+                warn_log!(
+                    RESET_PASSWORD_LOGGER_DOMAIN,
+                    server = %binding.server,
+                    "Failed to reset password of {}: {}",
+                    binding.emby_name,
+                    error
+                );
+                Err(format!("The password could not be changed on {}, please try again later", binding.server))
+            }
+        }
+    }
+}
+
+/// Deletes a message containing a password sent by the user.
+async fn delete_password_message(client: &TelegramClient, message: &Message, key: ConversationKey) {
+    if let Err(error) = client.delete_message(message.chat.id, message.message_id).await {
+        warn_log!(
+            RESET_PASSWORD_LOGGER_DOMAIN,
+            user_id = key.user_id,
+            "Failed to delete password message: {}",
+            error
+        );
+    }
+}
+
+#[async_trait]
+impl UpdateHandler for ResetPasswordHandler {
+    async fn handle(&self, client: &TelegramClient, update: &Update) -> Result<(), HandlerError> {
+        let (Some(message), Some(key)) = (update.message(), ConversationKey::from_update(update)) else {
+            return Ok(());
+        };
+        let Some(text) = message.text.as_deref() else {
+            return Ok(());
+        };
+        let private = message.chat.kind == ChatKind::Private;
+
+        let (reply, keyboard) = match BotCommand::parse(text) {
+            Some(command) if command.is(RESET_PASSWORD_COMMAND) => {
+                if private {
+                    self.start(key, &command.args).await?
+                } else {
+                    (format!("Send /{} in a private chat with me", RESET_PASSWORD_COMMAND), None)
+                }
+            }
+            Some(_) => return Ok(()),
+            None if private => match self.answer(client, message, key, text).await? {
+                Some(reply) => (reply, None),
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+        match keyboard {
+            Some(keyboard) => client.send_keyboard(key.chat_id, reply, keyboard).await?,
+            None => client.send_message(key.chat_id, reply).await?,
+        };
+        Ok(())
+    }
+
+    fn commands(&self) -> &'static [&'static str] {
+        &[RESET_PASSWORD_COMMAND]
+    }
+}
+
+#[async_trait]
+impl CallbackHandler<ResetPasswordChoice> for ResetPasswordHandler {
+    async fn handle(
+        &self,
+        client: &TelegramClient,
+        query: &CallbackQuery,
+        choice: ResetPasswordChoice,
+    ) -> Result<Option<String>, HandlerError> {
+        let Some(message) = &query.message else {
+            return Ok(Some(NOT_PENDING.to_string()));
+        };
+        let key = ConversationKey::new(message.chat.id, query.from.id);
+        let Some(ResetPasswordState::Confirm { binding }) = self.conversations.state(key).await? else {
+            client
+                .edit_message(message.chat.id, message.message_id, NOT_PENDING, None)
+                .await?;
+            return Ok(None);
+        };
+
+        let reply = self.confirm(key, &binding, choice.confirm).await?;
+        client.edit_message(message.chat.id, message.message_id, reply, None).await?;
+        Ok(None)
+    }
+}
@@ -0,0 +1,169 @@
+//! Lets admins hand out and take back roles.
+//!
+//! `/grant <telegram_id> <role>` gives a user a role below the sender's own,
+//! `/revoke <telegram_id>` makes them a plain user again and `/roles` lists
+//! everyone holding a role. All three require `Permission::ManageRoles`.
+//! Users whose role is set in the configuration can only be changed there.
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::context::AppContext;
+use crate::info_log;
+use crate::infrastructure::api::TelegramClient;
+use crate::infrastructure::api::telegram_types::Update;
+use crate::infrastructure::database::RoleAssignment;
+use crate::services::permissions::{check_role_change, configured_roles, role_of, Permission, Role};
+use super::access::{audit, authorize, DENIED_RESULT, OK_RESULT};
+use super::command::BotCommand;
+use super::dispatcher::{HandlerError, UpdateHandler};
+
+/// Domain identifier for role command logs
+const ROLES_LOGGER_DOMAIN: &str = "[BOT]";
+
+/// The command granting a role
+const GRANT_COMMAND: &str = "grant";
+
+/// The command revoking a role
+const REVOKE_COMMAND: &str = "revoke";
+
+/// The command listing roles
+const ROLES_COMMAND: &str = "roles";
+
+/// Handles the `/grant`, `/revoke` and `/roles` commands.
+pub struct RolesHandler {
+    /// Provides the configuration, the roles and the audit log
+    context: Arc<AppContext>,
+}
+
+impl RolesHandler {
+    /// Creates a handler managing the roles stored in `context`.
+    pub fn new(context: Arc<AppContext>) -> Self {
+        Self { context }
+    }
+
+    /// Applies `/grant` or `/revoke` for an actor holding `actor`.
+    ///
+    /// Returns the reply, the audited target and the audit result.
+    async fn change(
+        &self,
+        actor: Role,
+        actor_id: i64,
+        command: &BotCommand,
+    ) -> Result<(String, Option<String>, String), HandlerError> {
+        let words: Vec<&str> = command.args.split_whitespace().collect();
+        let parsed = match (command.name.as_str(), words.as_slice()) {
+            (GRANT_COMMAND, [user, role]) => user.parse::<i64>().ok().zip(Role::parse(role)),
+            (REVOKE_COMMAND, [user]) => user.parse::<i64>().ok().map(|user| (user, Role::User)),
+            _ => None,
+        };
+        let Some((target_id, role)) = parsed else {
+            let usage = format!(
+                "Usage: /{} <telegram_id> <moderator|admin>, /{} <telegram_id>",
+                GRANT_COMMAND, REVOKE_COMMAND
+            );
+            return Ok((usage, None, "invalid arguments".to_string()));
+        };
+        let target = Some(target_id.to_string());
+
+        if configured_roles(&self.context.config()).iter().any(|(id, _)| *id == target_id) {
+            let reply = format!("The role of {} is set in the configuration, change it there", target_id);
+            return Ok((reply, target, DENIED_RESULT.to_string()));
+        }
+        let current = role_of(self.context.database(), target_id).await?;
+        if let Err(reason) = check_role_change(actor, current, role) {
+            return Ok((reason, target, DENIED_RESULT.to_string()));
+        }
+        if current == role {
+            return Ok((format!("{} already has the {} role", target_id, role), target, OK_RESULT.to_string()));
+        }
+
+        if role == Role::User {
+            self.context.database().remove_role(target_id).await?;
+        } else {
+            let assignment = RoleAssignment::new(target_id, role.name(), Some(actor_id));
+            self.context.database().assign_role(&assignment).await?;
+        }
+        info_log!(
+            ROLES_LOGGER_DOMAIN,
+            user_id = actor_id,
+            "Changed role of {} from {} to {}",
+            target_id,
+            current,
+            role
+        );
+        Ok((format!("✅ {} now has the {} role", target_id, role), target, OK_RESULT.to_string()))
+    }
+
+    /// Lists everyone holding a role, most powerful first.
+    async fn list(&self) -> Result<String, HandlerError> {
+        let mut assignments: Vec<(Role, RoleAssignment)> = self
+            .context
+            .database()
+            .role_assignments()
+            .await?
+            .into_iter()
+            .filter_map(|assignment| Some((Role::parse(&assignment.role)?, assignment)))
+            .collect();
+        assignments.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.telegram_id.cmp(&b.1.telegram_id)));
+
+        let mut reply = String::from("Roles:");
+        for (role, assignment) in &assignments {
+            let _ = write!(reply, "\n• {} {}", assignment.telegram_id, role);
+            match assignment.granted_by {
+                Some(granted_by) => {
+                    let _ = write!(reply, ", granted by {}", granted_by);
+                }
+                None => reply.push_str(", configured"),
+            }
+        }
+        if assignments.is_empty() {
+            reply.push_str(" none");
+        }
+        Ok(reply)
+    }
+}
+
+#[async_trait]
+impl UpdateHandler for RolesHandler {
+    async fn handle(&self, client: &TelegramClient, update: &Update) -> Result<(), HandlerError> {
+        let Some(command) = update
+            .message()
+            .and_then(|message| message.text.as_deref())
+            .and_then(BotCommand::parse)
+        else {
+            return Ok(());
+        };
+        if ![GRANT_COMMAND, REVOKE_COMMAND, ROLES_COMMAND].contains(&command.name.as_str()) {
+            return Ok(());
+        }
+        let (Some(chat_id), Some(user_id)) = (update.chat_id(), update.user_id()) else {
+            return Ok(());
+        };
+        let Some(actor) = authorize(&self.context, self, user_id, &command).await? else {
+            return Ok(());
+        };
+
+        let reply = if command.is(ROLES_COMMAND) {
+            let reply = self.list().await?;
+            audit(&self.context, user_id, None, &command, OK_RESULT).await;
+            reply
+        } else {
+            let (reply, target, result) = self.change(actor, user_id, &command).await?;
+            audit(&self.context, user_id, target, &command, &result).await;
+            reply
+        };
+        client.send_message(chat_id, reply).await?;
+        Ok(())
+    }
+
+    fn commands(&self) -> &'static [&'static str] {
+        &[GRANT_COMMAND, REVOKE_COMMAND, ROLES_COMMAND]
+    }
+
+    fn permission(&self, command: &str) -> Option<Permission> {
+        self.commands().contains(&command).then_some(Permission::ManageRoles)
+    }
+}
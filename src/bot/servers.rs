@@ -0,0 +1,129 @@
+//! Shows the load of the media servers to admins.
+//!
+//! `/servers` lists every server with its bound accounts and active sessions
+//! and marks the one new accounts would be placed on. `/servers <name>`
+//! lists the active sessions of a single server. Both require
+//! `Permission::ViewServers`.
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::context::AppContext;
+use crate::infrastructure::api::TelegramClient;
+use crate::infrastructure::api::telegram_types::Update;
+use crate::services::permissions::Permission;
+use crate::services::placement::{least_loaded, server_loads};
+use super::access::{audit, authorize, OK_RESULT};
+use super::command::BotCommand;
+use super::dispatcher::{HandlerError, UpdateHandler};
+
+/// The command handled by `ServersHandler`
+const SERVERS_COMMAND: &str = "servers";
+
+/// Handles the `/servers` admin command.
+pub struct ServersHandler {
+    /// Provides the servers and the database
+    context: Arc<AppContext>,
+}
+
+impl ServersHandler {
+    /// Creates a handler for the servers in `context`.
+    pub fn new(context: Arc<AppContext>) -> Self {
+        Self { context }
+    }
+
+    /// Describes the load of all servers.
+    async fn overview(&self) -> Result<String, HandlerError> {
+        let placement = self.context.config().placement.clone();
+        let loads = server_loads(&self.context, true).await?;
+        let next = least_loaded(&loads, placement.strategy).map(|load| load.server.clone());
+
+        let mut reply = String::from("Servers:");
+        for load in &loads {
+            let _ = write!(reply, "\n• {}: {} accounts", load.server, load.users);
+            if let Some(max_users) = load.max_users {
+                let _ = write!(reply, " of {}", max_users);
+            }
+            match load.sessions {
+                Some(sessions) => {
+                    let _ = write!(reply, ", {} active sessions", sessions);
+                }
+                None => reply.push_str(", sessions unavailable"),
+            }
+            if next.as_ref() == Some(&load.server) {
+                reply.push_str(" ← next");
+            }
+        }
+        if loads.is_empty() {
+            reply.push_str(" none");
+        }
+        Ok(reply)
+    }
+
+    /// Lists the active sessions of a single server.
+    async fn sessions(&self, name: &str) -> Result<String, HandlerError> {
+        let server = match self.context.servers().resolve(Some(name)) {
+            Ok(server) => server,
+            Err(error) => return Ok(error.to_string()),
+        };
+        let session_window = self.context.config().placement.session_window;
+        let sessions = match server.sessions(session_window).await {
+            Ok(sessions) => sessions,
+            Err(error) => return Ok(format!("Failed to read sessions of {}: {}", name, error)),
+        };
+
+        let mut reply = format!("{}: {} active sessions", name, sessions.len());
+        for session in &sessions {
+            let _ = write!(
+                reply,
+                "\n• {} on {} ({})",
+                session.user_name.as_deref().unwrap_or("anonymous"),
+                session.device_name.as_deref().unwrap_or("unknown device"),
+                session.client.as_deref().unwrap_or("unknown client"),
+            );
+        }
+        Ok(reply)
+    }
+}
+
+#[async_trait]
+impl UpdateHandler for ServersHandler {
+    async fn handle(&self, client: &TelegramClient, update: &Update) -> Result<(), HandlerError> {
+        let Some(command) = update
+            .message()
+            .and_then(|message| message.text.as_deref())
+            .and_then(BotCommand::parse)
+        else {
+            return Ok(());
+        };
+        if !command.is(SERVERS_COMMAND) {
+            return Ok(());
+        }
+
+        let (Some(chat_id), Some(user_id)) = (update.chat_id(), update.user_id()) else {
+            return Ok(());
+        };
+        if authorize(&self.context, self, user_id, &command).await?.is_none() {
+            return Ok(());
+        }
+
+        let reply = if command.args.is_empty() {
+            self.overview().await?
+        } else {
+            self.sessions(&command.args).await?
+        };
+        audit(&self.context, user_id, None, &command, OK_RESULT).await;
+        client.send_message(chat_id, reply).await?;
+        Ok(())
+    }
+
+    fn commands(&self) -> &'static [&'static str] {
+        &[SERVERS_COMMAND]
+    }
+
+    fn permission(&self, command: &str) -> Option<Permission> {
+        self.commands().contains(&command).then_some(Permission::ViewServers)
+    }
+}
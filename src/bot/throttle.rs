@@ -0,0 +1,94 @@
+//! Slows down users repeating a failing action, such as guessing passwords.
+//!
+//! `FailureLimiter` counts the failures of each user. Once a user reaches the
+//! limit, they are blocked until the cooldown has passed since their last
+//! failure. A success, or a cooldown without failures, starts the count over.
+//!
+//! Counts are kept in memory only; a restart of the bot forgets them.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use pilipili_bot::bot::throttle::FailureLimiter;
+//!
+//! let limiter = FailureLimiter::new(2, Duration::from_secs(60));
+//! assert!(limiter.cooldown(42).is_none());
+//!
+//! limiter.record_failure(42);
+//! limiter.record_failure(42);
+//! assert!(limiter.cooldown(42).is_some());
+//!
+//! limiter.reset(42);
+//! assert!(limiter.cooldown(42).is_none());
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The failures of one user.
+#[derive(Debug, Clone, Copy)]
+struct Failures {
+    /// The number of failures since the count started
+    count: u32,
+    /// When the last failure happened
+    last: Instant,
+}
+
+/// Counts failures per Telegram user and blocks users with too many.
+#[derive(Debug)]
+pub struct FailureLimiter {
+    /// The number of failures after which a user is blocked
+    max_failures: u32,
+    /// How long a user is blocked after their last failure
+    cooldown: Duration,
+    /// The failures per Telegram user id
+    failures: Mutex<HashMap<i64, Failures>>,
+}
+
+impl FailureLimiter {
+    /// Creates a limiter blocking users for `cooldown` after `max_failures`.
+    pub fn new(max_failures: u32, cooldown: Duration) -> Self {
+        Self {
+            max_failures,
+            cooldown,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns how much longer a user is blocked, or `None` if they are not.
+    pub fn cooldown(&self, user_id: i64) -> Option<Duration> {
+        let mut failures = self.failures.lock().unwrap();
+        let entry = *failures.get(&user_id)?;
+        let elapsed = entry.last.elapsed();
+        if elapsed >= self.cooldown {
+            failures.remove(&user_id);
+            return None;
+        }
+        (entry.count >= self.max_failures).then(|| self.cooldown - elapsed)
+    }
+
+    /// Records a failure, returning the cooldown if the user is now blocked.
+    pub fn record_failure(&self, user_id: i64) -> Option<Duration> {
+        let mut failures = self.failures.lock().unwrap();
+        let now = Instant::now();
+        failures.retain(|id, entry| *id == user_id || now.duration_since(entry.last) < self.cooldown);
+        let entry = failures
+            .entry(user_id)
+            .and_modify(|entry| {
+                if now.duration_since(entry.last) >= self.cooldown {
+                    entry.count = 0;
+                }
+            })
+            .or_insert(Failures { count: 0, last: now });
+        entry.count += 1;
+        entry.last = now;
+        (entry.count >= self.max_failures).then_some(self.cooldown)
+    }
+
+    /// Forgets the failures of a user, e.g. after a success.
+    pub fn reset(&self, user_id: i64) {
+        self.failures.lock().unwrap().remove(&user_id);
+    }
+}
@@ -0,0 +1,650 @@
+//! Lets staff look up users and manage their accounts.
+//!
+//! `/user <telegram_id|@username|emby_name>` shows what the bot knows about a
+//! user: their Telegram profile, role, points and strikes, and for every
+//! bound account its status, expiry, last activity and current sessions.
+//! Looking users up requires `Permission::ViewUsers` and only works in
+//! private chats, as cards show who uses which account.
+//!
+//! Users with `Permission::ManageUsers` also get buttons to disable or enable
+//! an account, extend its expiry unless it never expires, reset its
+//! password, end its sessions, unbind it or delete it from the server.
+//! Unbinding and deleting ask for confirmation first. Every lookup and
+//! action is recorded in the audit log.
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::context::AppContext;
+use crate::infrastructure::api::emby_types::{SessionInfo, UserInfo};
+use crate::infrastructure::api::telegram_types::{CallbackQuery, ChatKind, InlineKeyboardMarkup, Update};
+use crate::infrastructure::api::{MediaServer, MediaServerError, TelegramClient};
+use crate::infrastructure::database::{AccountBinding, TelegramUser};
+use crate::infrastructure::metrics::BotMetrics;
+use crate::services::permissions::{role_of, Permission, Role};
+use crate::{info_log, warn_log};
+use super::access::{audit, authorize, authorize_permission, OK_RESULT};
+use super::callback::{CallbackData, CallbackError, CallbackHandler, CallbackSigner};
+use super::command::BotCommand;
+use super::dispatcher::{HandlerError, UpdateHandler};
+use super::keyboard::KeyboardBuilder;
+use super::reset_password::ResetPasswordHandler;
+
+/// Domain identifier for user management logs
+const USER_ADMIN_LOGGER_DOMAIN: &str = "[BOT]";
+
+/// The command handled by `UserAdminHandler`
+const USER_COMMAND: &str = "user";
+
+/// How many days the extend button adds to an account
+pub const EXTEND_DAYS: i64 = 30;
+
+/// The reason recorded in the metrics for accounts disabled by staff
+const DISABLE_REASON: &str = "admin";
+
+/// The number of seconds in a day
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// What a button on a user card does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserActionKind {
+    /// Show the card again
+    Show,
+    /// Disable the account on the server
+    Disable,
+    /// Enable the account on the server
+    Enable,
+    /// Extend the expiry by `EXTEND_DAYS`
+    Extend,
+    /// Reset the password and send it to the user
+    ResetPassword,
+    /// End all sessions of the account
+    Kick,
+    /// Remove the binding, keeping the account on the server
+    Unbind,
+    /// Delete the account from the server and remove the binding
+    Delete,
+}
+
+impl UserActionKind {
+    /// All kinds of actions.
+    const ALL: [UserActionKind; 8] = [
+        UserActionKind::Show,
+        UserActionKind::Disable,
+        UserActionKind::Enable,
+        UserActionKind::Extend,
+        UserActionKind::ResetPassword,
+        UserActionKind::Kick,
+        UserActionKind::Unbind,
+        UserActionKind::Delete,
+    ];
+
+    /// Returns the letter identifying the action in button data.
+    fn code(&self) -> &'static str {
+        match self {
+            UserActionKind::Show => "s",
+            UserActionKind::Disable => "d",
+            UserActionKind::Enable => "e",
+            UserActionKind::Extend => "x",
+            UserActionKind::ResetPassword => "p",
+            UserActionKind::Kick => "k",
+            UserActionKind::Unbind => "u",
+            UserActionKind::Delete => "r",
+        }
+    }
+
+    /// Returns the name of the action, as recorded in the audit log.
+    pub fn name(&self) -> &'static str {
+        match self {
+            UserActionKind::Show => "show",
+            UserActionKind::Disable => "disable",
+            UserActionKind::Enable => "enable",
+            UserActionKind::Extend => "extend",
+            UserActionKind::ResetPassword => "resetpw",
+            UserActionKind::Kick => "kick",
+            UserActionKind::Unbind => "unbind",
+            UserActionKind::Delete => "delete",
+        }
+    }
+
+    /// Returns whether the action has to be confirmed before it is applied.
+    pub fn needs_confirmation(&self) -> bool {
+        matches!(self, UserActionKind::Unbind | UserActionKind::Delete)
+    }
+}
+
+/// The data of the buttons on a user card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAction {
+    /// What the button does
+    pub kind: UserActionKind,
+    /// The user the card is about
+    pub telegram_id: i64,
+    /// The server of the account, empty for `UserActionKind::Show`
+    pub server: String,
+    /// Whether the action was confirmed
+    pub confirmed: bool,
+}
+
+impl UserAction {
+    /// Creates an unconfirmed action on the account of `telegram_id` on `server`.
+    pub fn new(kind: UserActionKind, telegram_id: i64, server: impl Into<String>) -> Self {
+        Self { kind, telegram_id, server: server.into(), confirmed: false }
+    }
+
+    /// Returns the same action, confirmed.
+    pub fn confirm(&self) -> Self {
+        Self { confirmed: true, ..self.clone() }
+    }
+}
+
+impl CallbackData for UserAction {
+    const ACTION: &'static str = "usr";
+
+    fn fields(&self) -> Vec<String> {
+        let confirmed = if self.confirmed { "!" } else { "" };
+        vec![
+            format!("{}{}", self.kind.code(), confirmed),
+            self.telegram_id.to_string(),
+            self.server.clone(),
+        ]
+    }
+
+    fn from_fields(fields: &[&str]) -> Option<Self> {
+        let [kind, telegram_id, server] = fields else {
+            return None;
+        };
+        let (code, confirmed) = match kind.strip_suffix('!') {
+            Some(code) => (code, true),
+            None => (*kind, false),
+        };
+        let kind = UserActionKind::ALL.into_iter().find(|kind| kind.code() == code)?;
+        Some(Self {
+            kind,
+            telegram_id: telegram_id.parse().ok()?,
+            server: server.to_string(),
+            confirmed,
+        })
+    }
+}
+
+/// What a server reports about a bound account.
+struct AccountStatus {
+    /// The binding
+    binding: AccountBinding,
+    /// The account on the server, or why it could not be read
+    user: Result<UserInfo, String>,
+    /// The recent sessions of the account
+    sessions: Vec<SessionInfo>,
+}
+
+/// Handles the `/user` command and the buttons of the cards it sends.
+#[derive(Clone)]
+pub struct UserAdminHandler {
+    /// Provides the servers, the database and the settings
+    context: Arc<AppContext>,
+    /// Resets passwords and sends them to their owners
+    passwords: ResetPasswordHandler,
+    /// Counts the disabled accounts
+    metrics: BotMetrics,
+}
+
+impl UserAdminHandler {
+    /// Creates a handler managing the users known to `context`.
+    pub fn new(context: Arc<AppContext>) -> Self {
+        let passwords = ResetPasswordHandler::new(context.clone());
+        Self { context, passwords, metrics: BotMetrics::default() }
+    }
+
+    /// Finds the Telegram id a query names, or the reply explaining why there is none.
+    async fn find(&self, query: &str) -> Result<Result<i64, String>, HandlerError> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(Err(format!("Usage: /{} <telegram_id|@username|emby_name>", USER_COMMAND)));
+        }
+        if let Some(username) = query.strip_prefix('@') {
+            return Ok(match self.context.database().telegram_user_named(username).await? {
+                Some(user) => Ok(user.telegram_id),
+                None => Err(format!("I have not seen @{} yet", username)),
+            });
+        }
+        if let Ok(telegram_id) = query.parse::<i64>() {
+            return Ok(Ok(telegram_id));
+        }
+
+        let mut telegram_ids: Vec<i64> = self
+            .context
+            .database()
+            .accounts_named(query)
+            .await?
+            .into_iter()
+            .map(|binding| binding.telegram_id)
+            .collect();
+        telegram_ids.dedup();
+        Ok(match telegram_ids.as_slice() {
+            [] => Err(format!("No account is named {}", query)),
+            [telegram_id] => Ok(*telegram_id),
+            _ => {
+                let mut reply = format!("Several users have an account named {}:", query);
+                for telegram_id in &telegram_ids {
+                    let _ = write!(reply, "\n• /{} {}", USER_COMMAND, telegram_id);
+                }
+                Err(reply)
+            }
+        })
+    }
+
+    /// Reads what the servers report about the accounts of a user.
+    async fn statuses(&self, telegram_id: i64) -> Result<Vec<AccountStatus>, HandlerError> {
+        let session_window = self.context.config().placement.session_window;
+        let mut statuses = Vec::new();
+        for binding in self.context.database().accounts_of(telegram_id).await? {
+            let Some(server) = self.context.servers().get(&binding.server) else {
+                let user = Err("server no longer configured".to_string());
+                statuses.push(AccountStatus { binding, user, sessions: Vec::new() });
+                continue;
+            };
+            let user = server.user(&binding.emby_user_id).await.map_err(|error| error.to_string());
+            let sessions = match server.sessions(session_window).await {
+                Ok(sessions) => sessions
+                    .into_iter()
+                    .filter(|session| session.user_id.as_deref() == Some(binding.emby_user_id.as_str()))
+                    .collect(),
+                Err(_) => Vec::new(),
+            };
+            statuses.push(AccountStatus { binding, user, sessions });
+        }
+        Ok(statuses)
+    }
+
+    /// Describes a user, with buttons if `viewer_id` may manage users.
+    async fn card(
+        &self,
+        viewer_id: i64,
+        telegram_id: i64,
+    ) -> Result<(String, Option<InlineKeyboardMarkup>), HandlerError> {
+        let database = self.context.database();
+        let profile = database.telegram_user(telegram_id).await?;
+        let role = role_of(database, telegram_id).await?;
+        let statuses = self.statuses(telegram_id).await?;
+
+        let mut card = describe_profile(telegram_id, profile.as_ref(), role);
+        for status in &statuses {
+            card.push_str("\n\n");
+            card.push_str(&describe_account(status));
+        }
+        if statuses.is_empty() {
+            card.push_str("\n\nNo bound accounts");
+        }
+
+        if !role_of(database, viewer_id).await?.can(Permission::ManageUsers) {
+            return Ok((card, None));
+        }
+        let keyboard = self.keyboard(viewer_id, telegram_id, &statuses)?;
+        Ok((card, Some(keyboard)))
+    }
+
+    /// Builds the buttons managing the accounts of a user.
+    fn keyboard(
+        &self,
+        viewer_id: i64,
+        telegram_id: i64,
+        statuses: &[AccountStatus],
+    ) -> Result<InlineKeyboardMarkup, CallbackError> {
+        let signer = CallbackSigner::from_settings(&self.context.config().telegram);
+        let mut keyboard = KeyboardBuilder::new(&signer).with_requester(viewer_id);
+        for status in statuses {
+            let binding = &status.binding;
+            let suffix = if statuses.len() > 1 { format!(" ({})", binding.server) } else { String::new() };
+            let action = |kind| UserAction::new(kind, telegram_id, binding.server.as_str());
+
+            let disabled = status.user.as_ref().is_ok_and(is_disabled);
+            keyboard = if disabled {
+                keyboard.button(format!("✅ Enable{}", suffix), &action(UserActionKind::Enable))?
+            } else {
+                keyboard.button(format!("⛔ Disable{}", suffix), &action(UserActionKind::Disable))?
+            };
+            if binding.expires_at.is_some() {
+                keyboard = keyboard
+                    .button(format!("⏳ +{} days{}", EXTEND_DAYS, suffix), &action(UserActionKind::Extend))?;
+            }
+            keyboard = keyboard
+                .button(format!("🔑 Reset password{}", suffix), &action(UserActionKind::ResetPassword))?
+                .row()
+                .button(format!("⏏ Kick{}", suffix), &action(UserActionKind::Kick))?
+                .button(format!("🔗 Unbind{}", suffix), &action(UserActionKind::Unbind))?
+                .button(format!("🗑 Delete{}", suffix), &action(UserActionKind::Delete))?
+                .row();
+        }
+        Ok(keyboard
+            .button("🔄 Refresh", &UserAction::new(UserActionKind::Show, telegram_id, ""))?
+            .build())
+    }
+
+    /// Asks the viewer to confirm an action.
+    fn confirmation(
+        &self,
+        viewer_id: i64,
+        action: &UserAction,
+    ) -> Result<(String, InlineKeyboardMarkup), CallbackError> {
+        let question = match action.kind {
+            UserActionKind::Delete => format!(
+                "Delete the account of {} on {}? This cannot be undone.",
+                action.telegram_id, action.server
+            ),
+            _ => format!(
+                "Unbind the account of {} on {}? It is kept on the server.",
+                action.telegram_id, action.server
+            ),
+        };
+        let signer = CallbackSigner::from_settings(&self.context.config().telegram);
+        let keyboard = KeyboardBuilder::new(&signer)
+            .with_requester(viewer_id)
+            .button("✅ Confirm", &action.confirm())?
+            .button("↩ Back", &UserAction::new(UserActionKind::Show, action.telegram_id, ""))?
+            .build();
+        Ok((question, keyboard))
+    }
+
+    /// Applies an action, returning the notice for the viewer and the audit result.
+    async fn apply(&self, action: &UserAction) -> Result<(String, String), HandlerError> {
+        let database = self.context.database();
+        let Some(binding) = database.account_on(action.telegram_id, &action.server).await? else {
+            return Ok((format!("There is no account on {} any more", action.server), "not bound".to_string()));
+        };
+        if action.kind == UserActionKind::Unbind {
+            database.unbind_account(binding.telegram_id, &binding.server).await?;
+            info_log!(
+                USER_ADMIN_LOGGER_DOMAIN,
+                user_id = binding.telegram_id,
+                server = %binding.server,
+                "Unbound {}",
+                binding.emby_name
+            );
+            return Ok((format!("Unbound {}", binding.emby_name), OK_RESULT.to_string()));
+        }
+        let Some(server) = self.context.servers().get(&binding.server) else {
+            let notice = format!("{} is no longer configured", binding.server);
+            return Ok((notice, "server not configured".to_string()));
+        };
+
+        let outcome = match action.kind {
+            UserActionKind::Show | UserActionKind::Unbind => return Ok((String::new(), OK_RESULT.to_string())),
+            UserActionKind::Disable | UserActionKind::Enable => {
+                let disabled = action.kind == UserActionKind::Disable;
+                let result = server.set_disabled(&binding.emby_user_id, disabled).await;
+                if disabled && result.is_ok() {
+                    self.metrics.account_disabled(DISABLE_REASON);
+                }
+                result.map(|()| {
+                    if disabled {
+                        format!("Disabled {}", binding.emby_name)
+                    } else {
+                        format!("Enabled {}", binding.emby_name)
+                    }
+                })
+            }
+            UserActionKind::Extend => match extended_expiry(binding.expires_at, chrono::Utc::now().timestamp()) {
+                Some(expires_at) => {
+                    database.set_account_expiry(binding.telegram_id, &binding.server, Some(expires_at)).await?;
+                    Ok(format!("{} now expires on {}", binding.emby_name, format_timestamp(expires_at)))
+                }
+                None => Ok(format!("{} never expires", binding.emby_name)),
+            },
+            UserActionKind::ResetPassword => {
+                return Ok(match self.passwords.reset_random(binding.telegram_id, &binding).await {
+                    Ok(notice) => (notice, OK_RESULT.to_string()),
+                    Err(notice) => (notice, "failed".to_string()),
+                });
+            }
+            UserActionKind::Kick => self.kick(server.as_ref(), &binding).await,
+            UserActionKind::Delete => match server.delete_user(&binding.emby_user_id).await {
+                Ok(()) => {
+                    database.unbind_account(binding.telegram_id, &binding.server).await?;
+                    Ok(format!("Deleted {}", binding.emby_name))
+                }
+                Err(error) => Err(error),
+            },
+        };
+
+        Ok(match outcome {
+            Ok(notice) => {
+                info_log!(
+                    USER_ADMIN_LOGGER_DOMAIN,
+                    user_id = binding.telegram_id,
+                    server = %binding.server,
+                    "Applied {} to {}",
+                    action.kind.name(),
+                    binding.emby_name
+                );
+                (notice, OK_RESULT.to_string())
+            }
+            Err(error) => {
+                warn_log!(
+                    USER_ADMIN_LOGGER_DOMAIN,
+                    user_id = binding.telegram_id,
+                    server = %binding.server,
+                    "Failed to {} {}: {}",
+                    action.kind.name(),
+                    binding.emby_name,
+                    error
+                );
+                (format!("Failed on {}: {}", binding.server, error), error.to_string())
+            }
+        })
+    }
+
+    /// Ends the recent sessions of an account, returning the notice.
+    async fn kick(
+        &self,
+        server: &dyn MediaServer,
+        binding: &AccountBinding,
+    ) -> Result<String, MediaServerError> {
+        let session_window = self.context.config().placement.session_window;
+        let sessions: Vec<SessionInfo> = server
+            .sessions(session_window)
+            .await?
+            .into_iter()
+            .filter(|session| session.user_id.as_deref() == Some(binding.emby_user_id.as_str()))
+            .collect();
+        for session in &sessions {
+            server.end_session(session).await?;
+        }
+        Ok(format!("Ended {} sessions of {}", sessions.len(), binding.emby_name))
+    }
+}
+
+#[async_trait]
+impl UpdateHandler for UserAdminHandler {
+    async fn handle(&self, client: &TelegramClient, update: &Update) -> Result<(), HandlerError> {
+        let Some(message) = update.message() else {
+            return Ok(());
+        };
+        let Some(command) = message.text.as_deref().and_then(BotCommand::parse) else {
+            return Ok(());
+        };
+        if !command.is(USER_COMMAND) {
+            return Ok(());
+        }
+        let (Some(chat_id), Some(user_id)) = (update.chat_id(), update.user_id()) else {
+            return Ok(());
+        };
+        if authorize(&self.context, self, user_id, &command).await?.is_none() {
+            return Ok(());
+        }
+        if message.chat.kind != ChatKind::Private {
+            client
+                .send_message(chat_id, format!("Send /{} in a private chat with me", USER_COMMAND))
+                .await?;
+            return Ok(());
+        }
+
+        let telegram_id = match self.find(&command.args).await? {
+            Ok(telegram_id) => telegram_id,
+            Err(reply) => {
+                audit(&self.context, user_id, None, &command, "not found").await;
+                client.send_message(chat_id, reply).await?;
+                return Ok(());
+            }
+        };
+        let (card, keyboard) = self.card(user_id, telegram_id).await?;
+        audit(&self.context, user_id, Some(telegram_id.to_string()), &command, OK_RESULT).await;
+        match keyboard {
+            Some(keyboard) => client.send_keyboard(chat_id, card, keyboard).await?,
+            None => client.send_message(chat_id, card).await?,
+        };
+        Ok(())
+    }
+
+    fn commands(&self) -> &'static [&'static str] {
+        &[USER_COMMAND]
+    }
+
+    fn permission(&self, command: &str) -> Option<Permission> {
+        self.commands().contains(&command).then_some(Permission::ViewUsers)
+    }
+}
+
+#[async_trait]
+impl CallbackHandler<UserAction> for UserAdminHandler {
+    async fn handle(
+        &self,
+        client: &TelegramClient,
+        query: &CallbackQuery,
+        action: UserAction,
+    ) -> Result<Option<String>, HandlerError> {
+        let Some(message) = &query.message else {
+            return Ok(Some("This card is no longer available".to_string()));
+        };
+        let viewer_id = query.from.id;
+        let command = BotCommand {
+            name: USER_COMMAND.to_string(),
+            args: format!("{} {}", action.kind.name(), action.server).trim_end().to_string(),
+        };
+        let permission = match action.kind {
+            UserActionKind::Show => Permission::ViewUsers,
+            _ => Permission::ManageUsers,
+        };
+        if authorize_permission(&self.context, viewer_id, &command, permission).await?.is_none() {
+            return Ok(Some("You may not do this".to_string()));
+        }
+
+        if action.kind.needs_confirmation() && !action.confirmed {
+            let (question, keyboard) = self.confirmation(viewer_id, &action)?;
+            client.edit_message(message.chat.id, message.message_id, question, Some(keyboard)).await?;
+            return Ok(None);
+        }
+
+        let target = Some(action.telegram_id.to_string());
+        let notice = if action.kind == UserActionKind::Show {
+            audit(&self.context, viewer_id, target, &command, OK_RESULT).await;
+            None
+        } else {
+            let (notice, result) = self.apply(&action).await?;
+            audit(&self.context, viewer_id, target, &command, &result).await;
+            Some(notice)
+        };
+
+        let (card, keyboard) = self.card(viewer_id, action.telegram_id).await?;
+        match client.edit_message(message.chat.id, message.message_id, card, keyboard).await {
+            Ok(_) => {}
+            Err(error) if error.is_not_modified() => {}
+            Err(error) => return Err(error.into()),
+        }
+        Ok(notice)
+    }
+}
+
+/// Returns the expiry extended by `EXTEND_DAYS`, counting from `now` if the
+/// account has already expired. Accounts that never expire are left alone.
+///
+/// # Examples
+///
+/// ```rust
+/// use pilipili_bot::bot::user_admin::{extended_expiry, EXTEND_DAYS};
+///
+/// let day = 24 * 60 * 60;
+/// assert_eq!(extended_expiry(Some(10 * day), 0), Some((10 + EXTEND_DAYS) * day));
+/// assert_eq!(extended_expiry(Some(0), 10 * day), Some((10 + EXTEND_DAYS) * day));
+/// assert_eq!(extended_expiry(None, 10 * day), None);
+/// ```
+pub fn extended_expiry(expires_at: Option<i64>, now: i64) -> Option<i64> {
+    expires_at.map(|expires_at| expires_at.max(now) + EXTEND_DAYS * SECONDS_PER_DAY)
+}
+
+/// Returns whether the server has disabled an account.
+fn is_disabled(user: &UserInfo) -> bool {
+    user.policy.as_ref().is_some_and(|policy| policy.is_disabled)
+}
+
+/// Formats a Unix timestamp for cards.
+fn format_timestamp(timestamp: i64) -> String {
+    match chrono::DateTime::from_timestamp(timestamp, 0) {
+        Some(time) => time.format("%Y-%m-%d %H:%M UTC").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// Formats a date reported by a media server for cards.
+fn format_server_date(date: &str) -> String {
+    match chrono::DateTime::parse_from_rfc3339(date) {
+        Ok(time) => format_timestamp(time.timestamp()),
+        Err(_) => date.to_string(),
+    }
+}
+
+/// Describes the Telegram side of a user.
+pub fn describe_profile(telegram_id: i64, profile: Option<&TelegramUser>, role: Role) -> String {
+    let mut text = format!("👤 {}", telegram_id);
+    let Some(profile) = profile else {
+        let _ = write!(text, "\nRole: {}\nNever talked to me", role);
+        return text;
+    };
+    if let Some(username) = &profile.username {
+        let _ = write!(text, " @{}", username);
+    }
+    let _ = write!(text, " ({})", profile.first_name);
+    let _ = write!(text, "\nRole: {}", role);
+    let _ = write!(text, "\nPoints: {} · Strikes: {}", profile.points, profile.strikes);
+    let _ = write!(text, "\nLast seen: {}", format_timestamp(profile.last_seen_at));
+    text
+}
+
+/// Describes a bound account.
+fn describe_account(status: &AccountStatus) -> String {
+    let binding = &status.binding;
+    let mut text = format!("🖥 {}: {}", binding.server, binding.emby_name);
+    match &status.user {
+        Ok(user) => {
+            let state = if is_disabled(user) { "disabled" } else { "active" };
+            let _ = write!(text, "\nStatus: {}", state);
+            if let Some(date) = &user.last_activity_date {
+                let _ = write!(text, "\nLast activity: {}", format_server_date(date));
+            }
+        }
+        Err(error) => {
+            let _ = write!(text, "\nStatus unavailable: {}", error);
+        }
+    }
+    match binding.expires_at {
+        Some(expires_at) => {
+            let _ = write!(text, "\nExpires: {}", format_timestamp(expires_at));
+            if expires_at <= chrono::Utc::now().timestamp() {
+                text.push_str(" (expired)");
+            }
+        }
+        None => text.push_str("\nExpires: never"),
+    }
+    let _ = write!(text, "\nSessions: {}", status.sessions.len());
+    for session in &status.sessions {
+        let _ = write!(
+            text,
+            "\n  • {} ({})",
+            session.device_name.as_deref().unwrap_or("unknown device"),
+            session.client.as_deref().unwrap_or("unknown client")
+        );
+    }
+    text
+}
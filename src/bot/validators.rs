@@ -0,0 +1,58 @@
+//! Checks the answers users give in conversations.
+//!
+//! Each validator turns the text of a message into the value a step needs,
+//! or returns the message telling the user what to send instead.
+
+use crate::services::password::check_password;
+
+/// The longest accepted user name
+const MAX_USERNAME_LENGTH: usize = 64;
+
+/// Accepts a media server user name.
+///
+/// # Examples
+///
+/// ```rust
+/// use pilipili_bot::bot::validators;
+///
+/// assert_eq!(validators::username("  alice "), Ok("alice".to_string()));
+/// assert!(validators::username("/start").is_err());
+/// ```
+pub fn username(input: &str) -> Result<String, String> {
+    let name = input.trim();
+    if name.is_empty() || name.starts_with('/') {
+        return Err("Send a user name".to_string());
+    }
+    if name.chars().count() > MAX_USERNAME_LENGTH {
+        return Err(format!("User names have at most {} characters", MAX_USERNAME_LENGTH));
+    }
+    if name.chars().any(char::is_control) {
+        return Err("User names must fit on one line".to_string());
+    }
+    Ok(name.to_owned())
+}
+
+/// Accepts a password the user chose, see `check_password`.
+pub fn password(input: &str, min_length: usize) -> Result<String, String> {
+    check_password(input, min_length)?;
+    Ok(input.to_owned())
+}
+
+/// Accepts `yes` or `no`.
+pub fn yes_no(input: &str) -> Result<bool, String> {
+    match input.trim().to_lowercase().as_str() {
+        "yes" | "y" => Ok(true),
+        "no" | "n" => Ok(false),
+        _ => Err("Reply yes or no".to_string()),
+    }
+}
+
+/// Accepts one of the given server names.
+pub fn server(input: &str, servers: &[String]) -> Result<String, String> {
+    let name = input.trim();
+    if servers.iter().any(|server| server == name) {
+        Ok(name.to_owned())
+    } else {
+        Err(format!("Choose one of: {}", servers.join(", ")))
+    }
+}
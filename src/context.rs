@@ -0,0 +1,140 @@
+//! Holds the shared state of the running bot.
+//!
+//! `AppContext` owns the configuration, the network provider, the API
+//! clients and the database. It is created once in `main` and handed to
+//! whatever needs it, usually as an `Arc<AppContext>`, instead of reading
+//! global state. Tests build their own context from any `Config`.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let context = Arc::new(AppContext::connect(config, provider).await?);
+//!
+//! let server = context.servers().resolve(Some("main"))?;
+//! let response = server.send(&EmbyAPI::GetUser { user_id }).await?;
+//! ```
+
+use std::sync::{Arc, RwLock};
+
+use tokio::sync::broadcast;
+
+use crate::infrastructure::api::{EmbyServers, TelegramClient};
+use crate::infrastructure::config::{Config, ConfigChange, ConfigError, ConfigSection};
+use crate::infrastructure::database::{Database, DatabaseError};
+use crate::infrastructure::network::NetworkProvider;
+
+/// The number of changes kept for subscribers that fall behind
+const CHANGE_CHANNEL_CAPACITY: usize = 16;
+
+/// The configuration, clients and database of the running bot.
+pub struct AppContext {
+    /// The active configuration
+    config: RwLock<Arc<Config>>,
+    /// Publishes configuration changes to subscribers
+    changes: broadcast::Sender<ConfigChange>,
+    /// Sends all network requests
+    provider: Arc<NetworkProvider>,
+    /// The clients of the media servers
+    servers: EmbyServers,
+    /// The Telegram Bot API client
+    telegram: Arc<TelegramClient>,
+    /// The database pool
+    database: Database,
+}
+
+impl AppContext {
+    /// Creates a context using an already connected database.
+    pub fn new(config: Config, provider: NetworkProvider, database: Database) -> Self {
+        let provider = Arc::new(provider);
+        let servers = EmbyServers::new(provider.clone(), &config.servers);
+        let telegram = Arc::new(TelegramClient::new(provider.clone(), config.telegram.clone()));
+        Self {
+            config: RwLock::new(Arc::new(config)),
+            changes: broadcast::channel(CHANGE_CHANNEL_CAPACITY).0,
+            provider,
+            servers,
+            telegram,
+            database,
+        }
+    }
+
+    /// Connects to the configured database and creates a context.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be reached.
+    pub async fn connect(config: Config, provider: NetworkProvider) -> Result<Self, DatabaseError> {
+        let database = Database::connect(&config.database).await?;
+        Ok(Self::new(config, provider, database))
+    }
+
+    /// Returns the active configuration.
+    pub fn config(&self) -> Arc<Config> {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Returns the provider shared by all clients.
+    pub fn provider(&self) -> &Arc<NetworkProvider> {
+        &self.provider
+    }
+
+    /// Returns the clients of the media servers.
+    pub fn servers(&self) -> &EmbyServers {
+        &self.servers
+    }
+
+    /// Returns the Telegram Bot API client.
+    pub fn telegram(&self) -> &Arc<TelegramClient> {
+        &self.telegram
+    }
+
+    /// Returns the database.
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+
+    /// Subscribes to configuration changes made by `replace_config`.
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigChange> {
+        self.changes.subscribe()
+    }
+
+    /// Validates `config` and swaps it in as the active configuration.
+    ///
+    /// The clients pick up changed settings with their next request, and
+    /// subscribers are notified if any section changed. Returns the changed
+    /// sections, which are empty if the configuration is unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Invalid` and keeps the active configuration if
+    /// `config` does not pass `Config::validate`.
+    pub fn replace_config(&self, config: Config) -> Result<Vec<ConfigSection>, ConfigError> {
+        config.validate()?;
+
+        let config = Arc::new(config);
+        let sections = {
+            let mut current = self.config.write().unwrap();
+            let sections = current.changed_sections(&config);
+            if !sections.is_empty() {
+                *current = config.clone();
+            }
+            sections
+        };
+        if sections.is_empty() {
+            return Ok(sections);
+        }
+
+        if sections.contains(&ConfigSection::Servers) {
+            self.servers.update(&config.servers);
+        }
+        if sections.contains(&ConfigSection::Telegram) {
+            self.telegram.set_settings(config.telegram.clone());
+        }
+        // Sending only fails when nobody is subscribed
+        let _ = self.changes.send(ConfigChange {
+            sections: sections.clone(),
+            config,
+        });
+        Ok(sections)
+    }
+}
@@ -1,48 +1,213 @@
 use std::collections::HashMap;
 
-use crate::infrastructure::network::{HttpMethod, NetworkTask, NetworkTarget};
-use crate::infrastructure::config::Config;
+use serde_json::{json, Value};
 
+use crate::infrastructure::network::{HttpMethod, NetworkHeaders, NetworkTask, NetworkTarget};
+use crate::infrastructure::config::emby::EmbyConfig;
+
+/// The client name reported to the media server
+const CLIENT_NAME: &str = "PiliPili Bot";
+
+/// The requests sent to a media server.
+///
+/// Emby and Jellyfin answer the same requests, `target` and
+/// `jellyfin_target` address them to either.
 pub enum EmbyAPI {
     GetUser { user_id: String },
+    GetUsers,
+    GetSessions { active_within_seconds: u64 },
+    GetLibraries,
+    /// Signs in as a user; `device_id` replaces the bot's own device, so
+    /// checking a user's credentials does not take over the bot's session
+    AuthenticateByName { username: String, password: String, device_id: Option<String> },
+    /// Ends the session of the access token the request is sent with
+    Logout { device_id: String },
+    UpdatePassword { user_id: String, new_password: String },
+    UpdatePolicy { user_id: String, policy: Value },
+    DeleteUser { user_id: String },
+    StopPlayback { session_id: String },
+    DeleteDevice { device_id: String },
+}
+
+impl EmbyAPI {
+
+    /// Returns the HTTP method of this request.
+    pub(crate) fn http_method(&self) -> HttpMethod {
+        match self {
+            EmbyAPI::AuthenticateByName { .. }
+            | EmbyAPI::Logout { .. }
+            | EmbyAPI::UpdatePassword { .. }
+            | EmbyAPI::UpdatePolicy { .. }
+            | EmbyAPI::StopPlayback { .. } => HttpMethod::Post,
+            EmbyAPI::DeleteUser { .. } | EmbyAPI::DeleteDevice { .. } => HttpMethod::Delete,
+            _ => HttpMethod::Get,
+        }
+    }
+
+    /// Returns the JSON body of this request, if it has one.
+    pub(crate) fn body(&self) -> Option<Value> {
+        match self {
+            EmbyAPI::AuthenticateByName { username, password, .. } => Some(json!({
+                "Username": username,
+                "Pw": password,
+            })),
+            EmbyAPI::UpdatePassword { user_id, new_password } => Some(json!({
+                "Id": user_id,
+                "NewPw": new_password,
+            })),
+            EmbyAPI::UpdatePolicy { policy, .. } => Some(policy.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the query parameters of this request, without credentials.
+    pub(crate) fn parameters(&self) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        match self {
+            EmbyAPI::GetSessions { active_within_seconds } => {
+                params.insert("ActiveWithinSeconds".to_string(), active_within_seconds.to_string());
+            }
+            EmbyAPI::DeleteDevice { device_id } => {
+                params.insert("Id".to_string(), device_id.clone());
+            }
+            EmbyAPI::GetUser { .. }
+            | EmbyAPI::GetUsers
+            | EmbyAPI::GetLibraries
+            | EmbyAPI::AuthenticateByName { .. }
+            | EmbyAPI::Logout { .. }
+            | EmbyAPI::UpdatePassword { .. }
+            | EmbyAPI::UpdatePolicy { .. }
+            | EmbyAPI::DeleteUser { .. }
+            | EmbyAPI::StopPlayback { .. } => {}
+        }
+        params
+    }
+
+    /// Returns the device this request claims to come from, if it is not
+    /// the bot itself.
+    pub(crate) fn device_id(&self) -> Option<&str> {
+        match self {
+            EmbyAPI::AuthenticateByName { device_id, .. } => device_id.as_deref(),
+            EmbyAPI::Logout { device_id } => Some(device_id),
+            _ => None,
+        }
+    }
+
+    /// Returns a target sending this request to the server in `settings`.
+    ///
+    /// The request is authorized with the `api_key` of the server unless an
+    /// access token is added with `EmbyTarget::with_token`.
+    pub fn target<'a>(&'a self, settings: &'a EmbyConfig) -> EmbyTarget<'a> {
+        EmbyTarget { settings, api: self, token: None }
+    }
+}
+
+/// Returns the `MediaBrowser` authorization value identifying the bot.
+///
+/// The server ties access tokens to the device id, which is derived from the
+/// server name so that every server sees one stable device. Requests made on
+/// behalf of a user, such as checking their password, pass their own
+/// `device_id` instead.
+pub(crate) fn client_authorization(settings: &EmbyConfig, token: Option<&str>, device_id: Option<&str>) -> String {
+    let device_id = match device_id {
+        Some(device_id) => device_id.to_owned(),
+        None => format!("pilipili-bot-{}", settings.name),
+    };
+    let mut value = format!(
+        "MediaBrowser Client=\"{}\", Device=\"{}\", DeviceId=\"{}\", Version=\"{}\"",
+        CLIENT_NAME,
+        CLIENT_NAME,
+        device_id,
+        env!("CARGO_PKG_VERSION"),
+    );
+    if let Some(token) = token {
+        value.push_str(&format!(", Token=\"{}\"", token));
+    }
+    value
+}
+
+/// An `EmbyAPI` request bound to the server it is sent to.
+pub struct EmbyTarget<'a> {
+    /// The server address and credentials
+    settings: &'a EmbyConfig,
+    /// The request
+    api: &'a EmbyAPI,
+    /// The access token replacing the `api_key`
+    token: Option<&'a str>,
+}
+
+impl<'a> EmbyTarget<'a> {
+
+    /// Authorizes the request with an access token instead of the `api_key`.
+    pub fn with_token(mut self, token: &'a str) -> Self {
+        self.token = Some(token);
+        self
+    }
 }
 
-impl NetworkTarget for EmbyAPI {
+impl NetworkTarget for EmbyTarget<'_> {
 
     fn base_url(&self) -> String {
-        Config::get().emby.base_url.clone()
+        self.settings.base_url.clone()
     }
 
     fn path(&self) -> String {
-        match self {
+        match self.api {
             EmbyAPI::GetUser { user_id, .. } => {
                 format!("emby/Users/{}", user_id)
             }
+            EmbyAPI::GetUsers => "emby/Users/Query".to_string(),
+            EmbyAPI::GetSessions { .. } => "emby/Sessions".to_string(),
+            EmbyAPI::GetLibraries => "emby/Library/VirtualFolders".to_string(),
+            EmbyAPI::AuthenticateByName { .. } => "emby/Users/AuthenticateByName".to_string(),
+            EmbyAPI::Logout { .. } => "emby/Sessions/Logout".to_string(),
+            EmbyAPI::UpdatePassword { user_id, .. } => format!("emby/Users/{}/Password", user_id),
+            EmbyAPI::UpdatePolicy { user_id, .. } => format!("emby/Users/{}/Policy", user_id),
+            EmbyAPI::DeleteUser { user_id } => format!("emby/Users/{}", user_id),
+            EmbyAPI::StopPlayback { session_id } => format!("emby/Sessions/{}/Playing/Stop", session_id),
+            EmbyAPI::DeleteDevice { .. } => "emby/Devices".to_string(),
+        }
+    }
+
+    fn path_template(&self) -> String {
+        match self.api {
+            EmbyAPI::GetUser { .. } | EmbyAPI::DeleteUser { .. } => "emby/Users/{user_id}".to_string(),
+            EmbyAPI::UpdatePassword { .. } => "emby/Users/{user_id}/Password".to_string(),
+            EmbyAPI::UpdatePolicy { .. } => "emby/Users/{user_id}/Policy".to_string(),
+            EmbyAPI::StopPlayback { .. } => "emby/Sessions/{session_id}/Playing/Stop".to_string(),
+            _ => self.path(),
         }
     }
 
     fn method(&self) -> HttpMethod {
-        HttpMethod::Get
+        self.api.http_method()
     }
 
     fn task(&self) -> NetworkTask {
-        match self {
-            EmbyAPI::GetUser { user_id: _ } => {
-                let api_key = Config::get().emby.api_key.clone();
-                let mut params = HashMap::new();
-                params.insert("api_key".to_string(), api_key);
-                NetworkTask::RequestParameters(params)
-            }
+        if let Some(body) = self.api.body() {
+            return NetworkTask::RequestJson(body);
+        }
+        let mut params = self.api.parameters();
+        if self.token.is_none() {
+            params.insert("api_key".to_string(), self.settings.api_key.clone());
         }
+        NetworkTask::RequestParameters(params)
     }
 
-    fn headers(&self) -> Option<Vec<(&'static str, String)>> {
-        let base_url = Config::get().emby.base_url.clone();
-        Some(vec![
-            ("accept", "application/json".to_string()),
-            ("origin", base_url.clone()),
-            ("referer", format!("{}/", base_url)),
-            ("user-agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36".to_string()),
-        ])
+    fn headers(&self) -> Option<NetworkHeaders> {
+        let base_url = &self.settings.base_url;
+        // JSON requests carry no `api_key` parameter, so the key is sent as token as well
+        let api_key = Some(self.settings.api_key.as_str()).filter(|key| !key.is_empty());
+        Some(
+            NetworkHeaders::new()
+                .with("accept", "application/json")
+                .with("origin", base_url.clone())
+                .with("referer", format!("{}/", base_url))
+                .with("user-agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36")
+                .with_sensitive(
+                    "X-Emby-Authorization",
+                    client_authorization(self.settings, self.token.or(api_key), self.api.device_id()),
+                )
+        )
     }
 }
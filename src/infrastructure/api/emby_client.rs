@@ -0,0 +1,152 @@
+//! Provides a client for the Emby API.
+//!
+//! The client sends `EmbyAPI` requests to the server in its settings. They
+//! are authorized with the `api_key` parameter, or, if a service account is
+//! configured with `username` and `password`, with an access token obtained
+//! from `Users/AuthenticateByName`.
+//!
+//! The token is cached until the server rejects it with `401 Unauthorized`,
+//! or until the address or account of the server changes. A rejected request
+//! signs in again and is retried once.
+
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use tokio::sync::Mutex;
+
+use crate::info_log;
+use crate::infrastructure::config::emby::{EmbyConfig, ServerKind};
+use crate::infrastructure::network::{NetworkError, NetworkProvider};
+use super::emby_api::EmbyAPI;
+use super::emby_types::{AuthenticationResult, QueryResult, UserInfo};
+use super::media_server::{decode, MediaServer, MediaServerError};
+
+/// Domain identifier for Emby client logs
+const EMBY_LOGGER_DOMAIN: &str = "[EMBY]";
+
+/// An access token of the service account.
+struct AccessToken {
+    /// The token
+    value: String,
+    /// The server address it was issued by
+    base_url: String,
+    /// The account it was issued for
+    username: Option<String>,
+    /// The password it was issued with
+    password: Option<String>,
+}
+
+impl AccessToken {
+    /// Returns whether the token was issued for the server and account in `settings`.
+    fn is_for(&self, settings: &EmbyConfig) -> bool {
+        self.base_url == settings.base_url
+            && self.username == settings.username
+            && self.password == settings.password
+    }
+}
+
+/// A client for an Emby server.
+pub struct EmbyClient {
+    /// Sends the requests
+    provider: Arc<NetworkProvider>,
+    /// The server address and credentials, replaced on configuration reloads
+    settings: RwLock<Arc<EmbyConfig>>,
+    /// The cached access token, held while signing in so only one request does
+    token: Mutex<Option<AccessToken>>,
+}
+
+impl EmbyClient {
+    /// Creates a client for the server in `settings`, sending its requests through `provider`.
+    pub fn new(provider: impl Into<Arc<NetworkProvider>>, settings: EmbyConfig) -> Self {
+        Self {
+            provider: provider.into(),
+            settings: RwLock::new(Arc::new(settings)),
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Returns an access token for the service account in `settings`.
+    ///
+    /// Signs in if no token for that account is cached, or if the cached one
+    /// is `rejected`. A token that another request obtained in the meantime
+    /// is reused.
+    async fn access_token(&self, settings: &EmbyConfig, rejected: Option<&str>) -> Result<String, NetworkError> {
+        let mut cached = self.token.lock().await;
+        if let Some(token) = cached.as_ref()
+            && token.is_for(settings)
+            && Some(token.value.as_str()) != rejected
+        {
+            return Ok(token.value.clone());
+        }
+
+        let value = self.sign_in(settings).await?;
+        *cached = Some(AccessToken {
+            value: value.clone(),
+            base_url: settings.base_url.clone(),
+            username: settings.username.clone(),
+            password: settings.password.clone(),
+        });
+        Ok(value)
+    }
+
+    /// Signs in as the service account and returns the new access token.
+    async fn sign_in(&self, settings: &EmbyConfig) -> Result<String, NetworkError> {
+        let api = EmbyAPI::AuthenticateByName {
+            username: settings.username.clone().unwrap_or_default(),
+            password: settings.password.clone().unwrap_or_default(),
+            device_id: None,
+        };
+        let response = self.provider.send_request(&api.target(settings)).await?;
+        if !response.status().is_success() {
+            return Err(NetworkError::UnexpectedStatus(response.status()));
+        }
+
+        let result: AuthenticationResult = response.json().await?;
+        info_log!(EMBY_LOGGER_DOMAIN, server = %settings.name, "Signed in as {}", result.user.name);
+        Ok(result.access_token)
+    }
+}
+
+#[async_trait]
+impl MediaServer for EmbyClient {
+    fn kind(&self) -> ServerKind {
+        ServerKind::Emby
+    }
+
+    fn settings(&self) -> Arc<EmbyConfig> {
+        self.settings.read().unwrap().clone()
+    }
+
+    fn set_settings(&self, settings: EmbyConfig) {
+        *self.settings.write().unwrap() = Arc::new(settings);
+    }
+
+    async fn send(&self, api: &EmbyAPI) -> Result<reqwest::Response, NetworkError> {
+        let settings = self.settings();
+        if settings.username.is_none() || matches!(api, EmbyAPI::AuthenticateByName { .. }) {
+            return self.provider.send_request(&api.target(&settings)).await;
+        }
+
+        let token = self.access_token(&settings, None).await?;
+        let response = self.provider.send_request(&api.target(&settings).with_token(&token)).await?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        info_log!(EMBY_LOGGER_DOMAIN, server = %settings.name, "Access token was rejected, signing in again");
+        let token = self.access_token(&settings, Some(&token)).await?;
+        self.provider.send_request(&api.target(&settings).with_token(&token)).await
+    }
+
+    async fn send_with_token(&self, api: &EmbyAPI, token: &str) -> Result<reqwest::Response, NetworkError> {
+        let settings = self.settings();
+        self.provider.send_request(&api.target(&settings).with_token(token)).await
+    }
+
+    /// Emby lists users as a `QueryResult` page holding all users.
+    async fn users(&self) -> Result<Vec<UserInfo>, MediaServerError> {
+        let page: QueryResult<UserInfo> = decode(self.send(&EmbyAPI::GetUsers).await?).await?;
+        Ok(page.items)
+    }
+}
@@ -0,0 +1,148 @@
+//! Keeps one `MediaServer` client per configured server.
+//!
+//! Servers are looked up by the name of their `[servers.<name>]` table.
+//! When the configuration is reloaded, clients of servers that still exist
+//! keep running with the new settings, new servers get a client and removed
+//! servers are dropped. A server whose `kind` changed gets a new client of
+//! the matching kind.
+
+use std::fmt::{self, Display};
+use std::sync::{Arc, RwLock};
+
+use crate::infrastructure::config::emby::{EmbyConfig, ServerKind};
+use crate::infrastructure::network::NetworkProvider;
+use super::emby_client::EmbyClient;
+use super::jellyfin_client::JellyfinClient;
+use super::media_server::MediaServer;
+
+/// Represents an error while choosing a server by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerLookupError {
+    /// No server has the given name
+    Unknown {
+        /// The requested name
+        name: String,
+        /// The names of all servers
+        available: Vec<String>,
+    },
+    /// No name was given, but several servers are configured
+    Ambiguous {
+        /// The names of all servers
+        available: Vec<String>,
+    },
+    /// No server is configured
+    NoServers,
+}
+
+impl Display for ServerLookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerLookupError::Unknown { name, available } => {
+                write!(f, "Unknown server `{}`, choose one of: {}", name, available.join(", "))
+            }
+            ServerLookupError::Ambiguous { available } => {
+                write!(f, "Several servers are configured, name one of: {}", available.join(", "))
+            }
+            ServerLookupError::NoServers => write!(f, "No server is configured"),
+        }
+    }
+}
+
+impl std::error::Error for ServerLookupError {}
+
+/// The clients of all configured servers, ordered by name.
+pub struct EmbyServers {
+    /// Shared by all clients
+    provider: Arc<NetworkProvider>,
+    /// One client per server
+    clients: RwLock<Vec<Arc<dyn MediaServer>>>,
+}
+
+impl EmbyServers {
+    /// Creates a client for each server in `servers`.
+    pub fn new(provider: Arc<NetworkProvider>, servers: &[EmbyConfig]) -> Self {
+        let clients = servers
+            .iter()
+            .map(|server| client(&provider, server))
+            .collect();
+        Self {
+            provider,
+            clients: RwLock::new(clients),
+        }
+    }
+
+    /// Returns the clients of all servers.
+    pub fn all(&self) -> Vec<Arc<dyn MediaServer>> {
+        self.clients.read().unwrap().clone()
+    }
+
+    /// Returns the names of all servers.
+    pub fn names(&self) -> Vec<String> {
+        self.clients.read().unwrap().iter().map(|client| client.name()).collect()
+    }
+
+    /// Returns the client of the server with the given name.
+    pub fn get(&self, name: &str) -> Option<Arc<dyn MediaServer>> {
+        self.clients
+            .read()
+            .unwrap()
+            .iter()
+            .find(|client| client.settings().name == name)
+            .cloned()
+    }
+
+    /// Returns the named server, or the only server if no name is given.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is unknown, or if no name is given and
+    /// there is not exactly one server.
+    pub fn resolve(&self, name: Option<&str>) -> Result<Arc<dyn MediaServer>, ServerLookupError> {
+        let clients = self.clients.read().unwrap();
+        let available = || clients.iter().map(|client| client.name()).collect();
+        match name {
+            Some(name) => clients
+                .iter()
+                .find(|client| client.settings().name == name)
+                .cloned()
+                .ok_or_else(|| ServerLookupError::Unknown {
+                    name: name.to_owned(),
+                    available: available(),
+                }),
+            None => match clients.as_slice() {
+                [] => Err(ServerLookupError::NoServers),
+                [client] => Ok(client.clone()),
+                _ => Err(ServerLookupError::Ambiguous { available: available() }),
+            },
+        }
+    }
+
+    /// Applies a reloaded server list.
+    pub fn update(&self, servers: &[EmbyConfig]) {
+        let mut clients = self.clients.write().unwrap();
+        let updated = servers
+            .iter()
+            .map(|server| {
+                let existing = clients
+                    .iter()
+                    .find(|client| client.settings().name == server.name && client.kind() == server.kind);
+                match existing {
+                    Some(existing) => {
+                        existing.set_settings(server.clone());
+                        existing.clone()
+                    }
+                    None => client(&self.provider, server),
+                }
+            })
+            .collect();
+        *clients = updated;
+    }
+}
+
+/// Creates the client matching the kind of `server`.
+fn client(provider: &Arc<NetworkProvider>, server: &EmbyConfig) -> Arc<dyn MediaServer> {
+    match server.kind {
+        ServerKind::Emby => Arc::new(EmbyClient::new(provider.clone(), server.clone())),
+        ServerKind::Jellyfin => Arc::new(JellyfinClient::new(provider.clone(), server.clone())),
+    }
+}
@@ -0,0 +1,95 @@
+//! Defines the Emby API types used by the bot.
+//!
+//! Jellyfin sends the same shapes, except where noted.
+//!
+//! Only the fields the bot needs are declared; Emby sends many more, which
+//! are ignored.
+
+use serde::Deserialize;
+
+/// A client connected to the server.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SessionInfo {
+    /// The session id
+    pub id: String,
+    /// The user of the session, absent before sign-in
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// The name of that user
+    #[serde(default)]
+    pub user_name: Option<String>,
+    /// The client application, e.g. `Emby Web`
+    #[serde(default)]
+    pub client: Option<String>,
+    /// The device name
+    #[serde(default)]
+    pub device_name: Option<String>,
+    /// The device id, which access tokens are tied to
+    #[serde(default)]
+    pub device_id: Option<String>,
+}
+
+/// A user account on the server.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct UserInfo {
+    /// The user id
+    pub id: String,
+    /// The login name
+    pub name: String,
+    /// What the user may do, absent in some list responses
+    #[serde(default)]
+    pub policy: Option<UserPolicy>,
+    /// When the user was last active, as an ISO 8601 timestamp
+    #[serde(default)]
+    pub last_activity_date: Option<String>,
+}
+
+/// The permissions of a user.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct UserPolicy {
+    /// Whether the user administers the server
+    pub is_administrator: bool,
+    /// Whether the user is blocked from signing in
+    pub is_disabled: bool,
+}
+
+/// A library of the server.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LibraryInfo {
+    /// The library name
+    pub name: String,
+    /// The id of the library folder
+    #[serde(default)]
+    pub item_id: Option<String>,
+    /// The content type, e.g. `movies`, absent for mixed libraries
+    #[serde(default)]
+    pub collection_type: Option<String>,
+}
+
+/// A page of query results, as returned by Emby's `Users/Query`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct QueryResult<T> {
+    /// The items on this page
+    pub items: Vec<T>,
+    /// The number of items across all pages
+    #[serde(default)]
+    pub total_record_count: u64,
+}
+
+/// The answer to `Users/AuthenticateByName`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AuthenticationResult {
+    /// The signed-in user
+    pub user: UserInfo,
+    /// The token authorizing further requests as that user
+    pub access_token: String,
+    /// The id of the server
+    #[serde(default)]
+    pub server_id: Option<String>,
+}
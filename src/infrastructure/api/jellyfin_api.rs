@@ -0,0 +1,98 @@
+//! Addresses `EmbyAPI` requests to a Jellyfin server.
+//!
+//! Jellyfin serves the Emby API without the `emby/` path prefix and expects
+//! the API key in a `MediaBrowser` authorization header instead of the
+//! `api_key` parameter.
+
+use crate::infrastructure::network::{HttpMethod, NetworkHeaders, NetworkTask, NetworkTarget};
+use crate::infrastructure::config::emby::EmbyConfig;
+use super::emby_api::{client_authorization, EmbyAPI};
+
+impl EmbyAPI {
+
+    /// Returns a target sending this request to the Jellyfin server in `settings`.
+    ///
+    /// The request is authorized with the `api_key` of the server unless an
+    /// access token is added with `JellyfinTarget::with_token`.
+    pub fn jellyfin_target<'a>(&'a self, settings: &'a EmbyConfig) -> JellyfinTarget<'a> {
+        JellyfinTarget { settings, api: self, token: None }
+    }
+}
+
+/// An `EmbyAPI` request bound to the Jellyfin server it is sent to.
+pub struct JellyfinTarget<'a> {
+    /// The server address and credentials
+    settings: &'a EmbyConfig,
+    /// The request
+    api: &'a EmbyAPI,
+    /// The access token replacing the `api_key`
+    token: Option<&'a str>,
+}
+
+impl<'a> JellyfinTarget<'a> {
+
+    /// Authorizes the request with an access token instead of the `api_key`.
+    pub fn with_token(mut self, token: &'a str) -> Self {
+        self.token = Some(token);
+        self
+    }
+}
+
+impl NetworkTarget for JellyfinTarget<'_> {
+
+    fn base_url(&self) -> String {
+        self.settings.base_url.clone()
+    }
+
+    fn path(&self) -> String {
+        match self.api {
+            EmbyAPI::GetUser { user_id } => format!("Users/{}", user_id),
+            EmbyAPI::GetUsers => "Users".to_string(),
+            EmbyAPI::GetSessions { .. } => "Sessions".to_string(),
+            EmbyAPI::GetLibraries => "Library/VirtualFolders".to_string(),
+            EmbyAPI::AuthenticateByName { .. } => "Users/AuthenticateByName".to_string(),
+            EmbyAPI::Logout { .. } => "Sessions/Logout".to_string(),
+            EmbyAPI::UpdatePassword { user_id, .. } => format!("Users/{}/Password", user_id),
+            EmbyAPI::UpdatePolicy { user_id, .. } => format!("Users/{}/Policy", user_id),
+            EmbyAPI::DeleteUser { user_id } => format!("Users/{}", user_id),
+            EmbyAPI::StopPlayback { session_id } => format!("Sessions/{}/Playing/Stop", session_id),
+            EmbyAPI::DeleteDevice { .. } => "Devices".to_string(),
+        }
+    }
+
+    fn path_template(&self) -> String {
+        match self.api {
+            EmbyAPI::GetUser { .. } | EmbyAPI::DeleteUser { .. } => "Users/{user_id}".to_string(),
+            EmbyAPI::UpdatePassword { .. } => "Users/{user_id}/Password".to_string(),
+            EmbyAPI::UpdatePolicy { .. } => "Users/{user_id}/Policy".to_string(),
+            EmbyAPI::StopPlayback { .. } => "Sessions/{session_id}/Playing/Stop".to_string(),
+            _ => self.path(),
+        }
+    }
+
+    fn method(&self) -> HttpMethod {
+        self.api.http_method()
+    }
+
+    fn task(&self) -> NetworkTask {
+        match self.api.body() {
+            Some(body) => NetworkTask::RequestJson(body),
+            None => NetworkTask::RequestParameters(self.api.parameters()),
+        }
+    }
+
+    fn headers(&self) -> Option<NetworkHeaders> {
+        Some(
+            NetworkHeaders::new()
+                .with("accept", "application/json")
+                .with_sensitive(
+                    "authorization",
+                    client_authorization(
+                        self.settings,
+                        Some(self.token.unwrap_or(&self.settings.api_key)),
+                        self.api.device_id(),
+                    ),
+                )
+        )
+    }
+}
@@ -0,0 +1,62 @@
+//! Provides a client for the Jellyfin API.
+//!
+//! The client sends `EmbyAPI` requests to the server in its settings through
+//! `JellyfinTarget`, authenticated with a `MediaBrowser` authorization header.
+
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+
+use crate::infrastructure::config::emby::{EmbyConfig, ServerKind};
+use crate::infrastructure::network::{NetworkError, NetworkProvider};
+use super::emby_api::EmbyAPI;
+use super::emby_types::UserInfo;
+use super::media_server::{decode, MediaServer, MediaServerError};
+
+/// A client for a Jellyfin server.
+pub struct JellyfinClient {
+    /// Sends the requests
+    provider: Arc<NetworkProvider>,
+    /// The server address and credentials, replaced on configuration reloads
+    settings: RwLock<Arc<EmbyConfig>>,
+}
+
+impl JellyfinClient {
+    /// Creates a client for the server in `settings`, sending its requests through `provider`.
+    pub fn new(provider: impl Into<Arc<NetworkProvider>>, settings: EmbyConfig) -> Self {
+        Self {
+            provider: provider.into(),
+            settings: RwLock::new(Arc::new(settings)),
+        }
+    }
+}
+
+#[async_trait]
+impl MediaServer for JellyfinClient {
+    fn kind(&self) -> ServerKind {
+        ServerKind::Jellyfin
+    }
+
+    fn settings(&self) -> Arc<EmbyConfig> {
+        self.settings.read().unwrap().clone()
+    }
+
+    fn set_settings(&self, settings: EmbyConfig) {
+        *self.settings.write().unwrap() = Arc::new(settings);
+    }
+
+    async fn send(&self, api: &EmbyAPI) -> Result<reqwest::Response, NetworkError> {
+        let settings = self.settings();
+        self.provider.send_request(&api.jellyfin_target(&settings)).await
+    }
+
+    async fn send_with_token(&self, api: &EmbyAPI, token: &str) -> Result<reqwest::Response, NetworkError> {
+        let settings = self.settings();
+        self.provider.send_request(&api.jellyfin_target(&settings).with_token(token)).await
+    }
+
+    /// Jellyfin lists users as a plain array.
+    async fn users(&self) -> Result<Vec<UserInfo>, MediaServerError> {
+        decode(self.send(&EmbyAPI::GetUsers).await?).await
+    }
+}
@@ -0,0 +1,216 @@
+//! Defines the operations the bot performs on a media server.
+//!
+//! `MediaServer` hides whether a server runs Emby or Jellyfin. Both answer
+//! the same `EmbyAPI` requests; an implementation only decides how a request
+//! is addressed and authenticated, and decodes the few responses whose
+//! shape differs.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let server = context.servers().resolve(Some("main"))?;
+//! for user in server.users().await? {
+//!     println!("{} ({})", user.name, user.id);
+//! }
+//! ```
+
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::warn_log;
+use crate::infrastructure::config::emby::{EmbyConfig, ServerKind};
+use crate::infrastructure::network::{NetworkError, RedactionRules};
+use super::emby_api::EmbyAPI;
+use super::emby_types::{AuthenticationResult, LibraryInfo, SessionInfo, UserInfo};
+
+/// Domain identifier for media server logs
+const MEDIA_SERVER_LOGGER_DOMAIN: &str = "[EMBY]";
+
+/// Represents an error returned by a media server client.
+#[derive(Debug)]
+pub enum MediaServerError {
+    /// The request could not be sent or was rejected
+    Network(NetworkError),
+    /// The response body did not have the expected shape
+    Decode(reqwest::Error),
+}
+
+impl Display for MediaServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaServerError::Network(error) => write!(f, "{}", error),
+            MediaServerError::Decode(error) => {
+                let error = RedactionRules::global().redact_error(error);
+                write!(f, "invalid media server response: {}", error)
+            }
+        }
+    }
+}
+
+impl MediaServerError {
+    /// Returns whether the server rejected the credentials.
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, MediaServerError::Network(NetworkError::UnexpectedStatus(StatusCode::UNAUTHORIZED)))
+    }
+}
+
+impl std::error::Error for MediaServerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MediaServerError::Network(error) => Some(error),
+            MediaServerError::Decode(error) => Some(error),
+        }
+    }
+}
+
+impl From<NetworkError> for MediaServerError {
+    fn from(error: NetworkError) -> Self {
+        MediaServerError::Network(error)
+    }
+}
+
+/// A client for an Emby or Jellyfin server.
+///
+/// The settings are read once per request, so a configuration reload
+/// applies to the next request without affecting those in flight.
+#[async_trait]
+pub trait MediaServer: Send + Sync {
+    /// Returns the API the server speaks.
+    fn kind(&self) -> ServerKind;
+
+    /// Returns the settings used for new requests.
+    fn settings(&self) -> Arc<EmbyConfig>;
+
+    /// Replaces the settings used for new requests.
+    fn set_settings(&self, settings: EmbyConfig);
+
+    /// Returns the name of the server.
+    fn name(&self) -> String {
+        self.settings().name.clone()
+    }
+
+    /// Sends a request to the server.
+    async fn send(&self, api: &EmbyAPI) -> Result<reqwest::Response, NetworkError>;
+
+    /// Sends a request on behalf of the user an access token was issued to.
+    async fn send_with_token(&self, api: &EmbyAPI, token: &str) -> Result<reqwest::Response, NetworkError>;
+
+    /// Returns all users of the server.
+    async fn users(&self) -> Result<Vec<UserInfo>, MediaServerError>;
+
+    /// Returns the user with the given id.
+    async fn user(&self, user_id: &str) -> Result<UserInfo, MediaServerError> {
+        let api = EmbyAPI::GetUser { user_id: user_id.to_owned() };
+        decode(self.send(&api).await?).await
+    }
+
+    /// Returns the sessions active within the given number of seconds.
+    async fn sessions(&self, active_within_seconds: u64) -> Result<Vec<SessionInfo>, MediaServerError> {
+        decode(self.send(&EmbyAPI::GetSessions { active_within_seconds }).await?).await
+    }
+
+    /// Checks credentials a user sent by signing in as that user.
+    ///
+    /// The sign-in uses a device id of its own, so it neither replaces the
+    /// bot's session nor shows up as one of the user's devices, and the
+    /// session is logged out again right away. The returned access token is
+    /// therefore no longer valid.
+    ///
+    /// # Errors
+    ///
+    /// Wrong credentials are reported as an error for which
+    /// `MediaServerError::is_unauthorized` returns `true`.
+    async fn authenticate(&self, username: &str, password: &str) -> Result<AuthenticationResult, MediaServerError> {
+        let device_id = format!("pilipili-check-{:016x}", rand::random::<u64>());
+        let api = EmbyAPI::AuthenticateByName {
+            username: username.to_owned(),
+            password: password.to_owned(),
+            device_id: Some(device_id.clone()),
+        };
+        let result: AuthenticationResult = decode(self.send(&api).await?).await?;
+
+        let logout = EmbyAPI::Logout { device_id };
+        let logged_out = match self.send_with_token(&logout, &result.access_token).await {
+            Ok(response) => check_status(response),
+            Err(error) => Err(error.into()),
+        };
+        if let Err(error) = logged_out {
+            warn_log!(
+                MEDIA_SERVER_LOGGER_DOMAIN,
+                server = %self.name(),
+                "Failed to log out after checking credentials: {}",
+                error
+            );
+        }
+        Ok(result)
+    }
+
+    /// Sets the password of a user, without knowing the current one.
+    ///
+    /// Requires administrator rights, i.e. an `api_key` or an administrator
+    /// service account.
+    async fn set_password(&self, user_id: &str, new_password: &str) -> Result<(), MediaServerError> {
+        let api = EmbyAPI::UpdatePassword {
+            user_id: user_id.to_owned(),
+            new_password: new_password.to_owned(),
+        };
+        check_status(self.send(&api).await?)
+    }
+
+    /// Blocks a user from signing in, or allows it again.
+    ///
+    /// The server replaces the whole policy, so the current policy is read
+    /// first and sent back with only `IsDisabled` changed.
+    async fn set_disabled(&self, user_id: &str, disabled: bool) -> Result<(), MediaServerError> {
+        let user: Value = decode(self.send(&EmbyAPI::GetUser { user_id: user_id.to_owned() }).await?).await?;
+        let mut policy = user.get("Policy").cloned().unwrap_or_else(|| json!({}));
+        policy["IsDisabled"] = json!(disabled);
+        check_status(self.send(&EmbyAPI::UpdatePolicy { user_id: user_id.to_owned(), policy }).await?)
+    }
+
+    /// Ends a session: stops its playback and removes its device, which
+    /// revokes the access token the client signed in with.
+    async fn end_session(&self, session: &SessionInfo) -> Result<(), MediaServerError> {
+        // Sessions that play nothing reject the stop command, which is harmless
+        let _ = self.send(&EmbyAPI::StopPlayback { session_id: session.id.clone() }).await?;
+        match &session.device_id {
+            Some(device_id) => check_status(self.send(&EmbyAPI::DeleteDevice { device_id: device_id.clone() }).await?),
+            None => Ok(()),
+        }
+    }
+
+    /// Deletes a user and everything the server stores about them.
+    async fn delete_user(&self, user_id: &str) -> Result<(), MediaServerError> {
+        check_status(self.send(&EmbyAPI::DeleteUser { user_id: user_id.to_owned() }).await?)
+    }
+
+    /// Returns the libraries of the server.
+    async fn libraries(&self) -> Result<Vec<LibraryInfo>, MediaServerError> {
+        decode(self.send(&EmbyAPI::GetLibraries).await?).await
+    }
+}
+
+/// Checks that a response without a body was successful.
+fn check_status(response: reqwest::Response) -> Result<(), MediaServerError> {
+    if !response.status().is_success() {
+        return Err(NetworkError::UnexpectedStatus(response.status()).into());
+    }
+    Ok(())
+}
+
+/// Decodes the JSON body of a successful response.
+///
+/// # Errors
+///
+/// Returns `NetworkError::UnexpectedStatus` for any status other than 2xx.
+pub(crate) async fn decode<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, MediaServerError> {
+    if !response.status().is_success() {
+        return Err(NetworkError::UnexpectedStatus(response.status()).into());
+    }
+    response.json().await.map_err(MediaServerError::Decode)
+}
@@ -1,3 +1,19 @@
 pub mod emby_api;
+pub mod emby_types;
+pub mod emby_client;
+pub mod emby_servers;
+pub mod jellyfin_api;
+pub mod jellyfin_client;
+pub mod media_server;
+pub mod telegram_api;
+pub mod telegram_types;
+pub mod telegram_client;
 
-pub use emby_api::EmbyAPI;
+pub use emby_api::{EmbyAPI, EmbyTarget};
+pub use emby_client::EmbyClient;
+pub use emby_servers::{EmbyServers, ServerLookupError};
+pub use jellyfin_api::JellyfinTarget;
+pub use jellyfin_client::JellyfinClient;
+pub use media_server::{MediaServer, MediaServerError};
+pub use telegram_api::{TelegramAPI, TelegramTarget};
+pub use telegram_client::{TelegramClient, TelegramError};
@@ -0,0 +1,109 @@
+use serde_json::json;
+
+use crate::infrastructure::network::{HttpMethod, NetworkTask, NetworkTarget};
+use crate::infrastructure::config::telegram::TelegramConfig;
+use super::telegram_types::InlineKeyboardMarkup;
+
+pub enum TelegramAPI {
+    GetUpdates { offset: Option<i64>, timeout: u64 },
+    SendMessage { chat_id: i64, text: String, reply_markup: Option<InlineKeyboardMarkup>, sensitive: bool },
+    EditMessageText { chat_id: i64, message_id: i64, text: String, reply_markup: Option<InlineKeyboardMarkup> },
+    DeleteMessage { chat_id: i64, message_id: i64 },
+    AnswerCallbackQuery { callback_query_id: String, text: Option<String>, show_alert: bool },
+}
+
+impl TelegramAPI {
+
+    fn method_name(&self) -> &'static str {
+        match self {
+            TelegramAPI::GetUpdates { .. } => "getUpdates",
+            TelegramAPI::SendMessage { .. } => "sendMessage",
+            TelegramAPI::EditMessageText { .. } => "editMessageText",
+            TelegramAPI::DeleteMessage { .. } => "deleteMessage",
+            TelegramAPI::AnswerCallbackQuery { .. } => "answerCallbackQuery",
+        }
+    }
+
+    /// Returns a target sending this request with the bot in `settings`.
+    pub fn target<'a>(&'a self, settings: &'a TelegramConfig) -> TelegramTarget<'a> {
+        TelegramTarget { settings, api: self }
+    }
+}
+
+/// A `TelegramAPI` request bound to the bot sending it.
+pub struct TelegramTarget<'a> {
+    /// The Bot API address and token
+    settings: &'a TelegramConfig,
+    /// The request
+    api: &'a TelegramAPI,
+}
+
+impl NetworkTarget for TelegramTarget<'_> {
+
+    fn base_url(&self) -> String {
+        self.settings.api_url.clone()
+    }
+
+    fn path(&self) -> String {
+        format!("bot{}/{}", self.settings.bot_token, self.api.method_name())
+    }
+
+    fn path_template(&self) -> String {
+        format!("bot{{token}}/{}", self.api.method_name())
+    }
+
+    fn method(&self) -> HttpMethod {
+        HttpMethod::Post
+    }
+
+    fn is_body_sensitive(&self) -> bool {
+        matches!(self.api, TelegramAPI::SendMessage { sensitive: true, .. })
+    }
+
+    fn task(&self) -> NetworkTask {
+        match self.api {
+            TelegramAPI::GetUpdates { offset, timeout } => {
+                NetworkTask::RequestJson(json!({
+                    "offset": offset,
+                    "timeout": timeout,
+                    "allowed_updates": ["message", "edited_message", "callback_query"],
+                }))
+            }
+            TelegramAPI::SendMessage { chat_id, text, reply_markup, .. } => {
+                let mut body = json!({
+                    "chat_id": chat_id,
+                    "text": text,
+                });
+                if let Some(reply_markup) = reply_markup {
+                    body["reply_markup"] = json!(reply_markup);
+                }
+                NetworkTask::RequestJson(body)
+            }
+            TelegramAPI::EditMessageText { chat_id, message_id, text, reply_markup } => {
+                // Without `reply_markup` Telegram removes the keyboard
+                let mut body = json!({
+                    "chat_id": chat_id,
+                    "message_id": message_id,
+                    "text": text,
+                });
+                if let Some(reply_markup) = reply_markup {
+                    body["reply_markup"] = json!(reply_markup);
+                }
+                NetworkTask::RequestJson(body)
+            }
+            TelegramAPI::DeleteMessage { chat_id, message_id } => {
+                NetworkTask::RequestJson(json!({
+                    "chat_id": chat_id,
+                    "message_id": message_id,
+                }))
+            }
+            TelegramAPI::AnswerCallbackQuery { callback_query_id, text, show_alert } => {
+                NetworkTask::RequestJson(json!({
+                    "callback_query_id": callback_query_id,
+                    "text": text,
+                    "show_alert": show_alert,
+                }))
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Provides a typed client for the Telegram Bot API.
+//!
+//! Every Bot API method answers with a `{"ok": ..., "result": ...}` envelope.
+//! The client sends `TelegramAPI` targets through a `NetworkProvider` and
+//! unwraps the envelope into the method's result type or a `TelegramError`.
+
+use std::fmt::{self, Display};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+
+use crate::infrastructure::config::telegram::TelegramConfig;
+use crate::infrastructure::network::{NetworkError, NetworkProvider, RedactionRules};
+use super::telegram_api::TelegramAPI;
+use super::telegram_types::{InlineKeyboardMarkup, Message, TelegramResponse, Update};
+
+/// Represents an error returned by the Telegram client.
+#[derive(Debug)]
+pub enum TelegramError {
+    /// The request could not be sent
+    Network(NetworkError),
+    /// The response was not a valid Bot API response
+    Decode(reqwest::Error),
+    /// The Bot API rejected the request
+    Api {
+        /// The error code, usually mirroring the HTTP status
+        code: i32,
+        /// A human-readable description
+        description: String,
+        /// Time to wait before retrying a rate-limited request
+        retry_after: Option<Duration>,
+    },
+}
+
+impl Display for TelegramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TelegramError::Network(error) => write!(f, "{}", error),
+            TelegramError::Decode(error) => {
+                let error = RedactionRules::global().redact_error(error);
+                write!(f, "invalid Bot API response: {}", error)
+            }
+            TelegramError::Api { code, description, .. } => {
+                write!(f, "Bot API error {}: {}", code, description)
+            }
+        }
+    }
+}
+
+impl TelegramError {
+    /// Returns whether an edit was rejected because it changes nothing.
+    pub fn is_not_modified(&self) -> bool {
+        matches!(self, TelegramError::Api { description, .. } if description.contains("message is not modified"))
+    }
+
+    /// Returns whether the same request may succeed later.
+    ///
+    /// Network failures, unreadable responses, rate limits and server errors
+    /// are temporary. Other Bot API errors, such as a message that cannot be
+    /// deleted, are permanent.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            TelegramError::Network(_) | TelegramError::Decode(_) => true,
+            TelegramError::Api { code, .. } => *code == 429 || *code >= 500,
+        }
+    }
+}
+
+impl std::error::Error for TelegramError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TelegramError::Network(error) => Some(error),
+            TelegramError::Decode(error) => Some(error),
+            TelegramError::Api { .. } => None,
+        }
+    }
+}
+
+impl From<NetworkError> for TelegramError {
+    fn from(error: NetworkError) -> Self {
+        TelegramError::Network(error)
+    }
+}
+
+/// A client for the Telegram Bot API.
+pub struct TelegramClient {
+    /// Sends the requests
+    provider: Arc<NetworkProvider>,
+    /// The Bot API address and token, replaced on configuration reloads
+    settings: RwLock<Arc<TelegramConfig>>,
+}
+
+impl TelegramClient {
+    /// Creates a client for the bot in `settings`, sending its requests through `provider`.
+    pub fn new(provider: impl Into<Arc<NetworkProvider>>, settings: TelegramConfig) -> Self {
+        Self {
+            provider: provider.into(),
+            settings: RwLock::new(Arc::new(settings)),
+        }
+    }
+
+    /// Returns the settings used for new requests.
+    pub fn settings(&self) -> Arc<TelegramConfig> {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Replaces the settings used for new requests.
+    pub fn set_settings(&self, settings: TelegramConfig) {
+        *self.settings.write().unwrap() = Arc::new(settings);
+    }
+
+    /// Receives incoming updates using long polling.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The id of the first update to return
+    /// * `timeout` - How long the server may wait for updates, in seconds
+    pub async fn get_updates(
+        &self,
+        offset: Option<i64>,
+        timeout: u64,
+    ) -> Result<Vec<Update>, TelegramError> {
+        self.call(TelegramAPI::GetUpdates { offset, timeout }).await
+    }
+
+    /// Sends a text message.
+    pub async fn send_message(
+        &self,
+        chat_id: i64,
+        text: impl Into<String>,
+    ) -> Result<Message, TelegramError> {
+        let text = text.into();
+        self.call(TelegramAPI::SendMessage { chat_id, text, reply_markup: None, sensitive: false }).await
+    }
+
+    /// Sends a text message containing credentials.
+    ///
+    /// Network logs show the request body as `[REDACTED]`, so the text
+    /// never reaches log files or alerts.
+    pub async fn send_secret(
+        &self,
+        chat_id: i64,
+        text: impl Into<String>,
+    ) -> Result<Message, TelegramError> {
+        let text = text.into();
+        self.call(TelegramAPI::SendMessage { chat_id, text, reply_markup: None, sensitive: true }).await
+    }
+
+    /// Sends a text message with an inline keyboard below it.
+    pub async fn send_keyboard(
+        &self,
+        chat_id: i64,
+        text: impl Into<String>,
+        keyboard: InlineKeyboardMarkup,
+    ) -> Result<Message, TelegramError> {
+        let text = text.into();
+        let reply_markup = Some(keyboard);
+        self.call(TelegramAPI::SendMessage { chat_id, text, reply_markup, sensitive: false }).await
+    }
+
+    /// Replaces the text of a message sent by the bot.
+    ///
+    /// The inline keyboard is replaced by `keyboard`, or removed if it is `None`.
+    pub async fn edit_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        text: impl Into<String>,
+        keyboard: Option<InlineKeyboardMarkup>,
+    ) -> Result<Message, TelegramError> {
+        let text = text.into();
+        self.call(TelegramAPI::EditMessageText { chat_id, message_id, text, reply_markup: keyboard }).await
+    }
+
+    /// Answers a button press, showing `text` as a notification or, with
+    /// `show_alert`, as an alert the user has to dismiss.
+    ///
+    /// Every press has to be answered, otherwise the client keeps showing
+    /// a progress indicator on the button.
+    pub async fn answer_callback(
+        &self,
+        callback_query_id: impl Into<String>,
+        text: Option<String>,
+        show_alert: bool,
+    ) -> Result<bool, TelegramError> {
+        let callback_query_id = callback_query_id.into();
+        self.call(TelegramAPI::AnswerCallbackQuery { callback_query_id, text, show_alert }).await
+    }
+
+    /// Deletes a message, e.g. one containing credentials.
+    ///
+    /// Bots can only delete messages younger than 48 hours, and messages of
+    /// other users only in private chats or where they are an admin.
+    pub async fn delete_message(&self, chat_id: i64, message_id: i64) -> Result<bool, TelegramError> {
+        self.call(TelegramAPI::DeleteMessage { chat_id, message_id }).await
+    }
+
+    /// Sends a Bot API request and unwraps the response envelope.
+    async fn call<T: DeserializeOwned>(&self, api: TelegramAPI) -> Result<T, TelegramError> {
+        let settings = self.settings();
+        let response = self.provider.send_request(&api.target(&settings)).await?;
+        let envelope: TelegramResponse<T> =
+            response.json().await.map_err(TelegramError::Decode)?;
+
+        match envelope.result {
+            Some(result) if envelope.ok => Ok(result),
+            _ => Err(TelegramError::Api {
+                code: envelope.error_code.unwrap_or_default(),
+                description: envelope.description.unwrap_or_default(),
+                retry_after: envelope
+                    .parameters
+                    .and_then(|parameters| parameters.retry_after)
+                    .map(Duration::from_secs),
+            }),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Defines the subset of Telegram Bot API types used by the bot.
+//!
+//! Only the fields the bot needs are modelled; unknown fields are ignored
+//! when deserializing.
+
+use serde::{Deserialize, Serialize};
+
+/// The envelope every Bot API method answers with.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramResponse<T> {
+    /// Whether the request was successful
+    pub ok: bool,
+    /// The result of the request, present if `ok` is true
+    pub result: Option<T>,
+    /// A human-readable description of the error
+    pub description: Option<String>,
+    /// The error code, present if `ok` is false
+    pub error_code: Option<i32>,
+    /// Additional information about the error
+    pub parameters: Option<ResponseParameters>,
+}
+
+/// Information about why a request was unsuccessful.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResponseParameters {
+    /// Seconds to wait before repeating a rate-limited request
+    pub retry_after: Option<u64>,
+}
+
+/// An incoming update.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Update {
+    /// The unique, increasing update identifier
+    pub update_id: i64,
+    /// A new incoming message
+    pub message: Option<Message>,
+    /// A new version of a known message that was edited
+    pub edited_message: Option<Message>,
+    /// A press of an inline keyboard button
+    pub callback_query: Option<CallbackQuery>,
+}
+
+impl Update {
+    /// Returns the message carried by the update, if any.
+    pub fn message(&self) -> Option<&Message> {
+        self.message.as_ref().or(self.edited_message.as_ref())
+    }
+
+    /// Returns the id of the chat the update belongs to, if any.
+    ///
+    /// For button presses this is the chat of the message with the button.
+    pub fn chat_id(&self) -> Option<i64> {
+        match &self.callback_query {
+            Some(query) => query.message.as_ref().map(|message| message.chat.id),
+            None => self.message().map(|message| message.chat.id),
+        }
+    }
+
+    /// Returns the id of the user who caused the update, if any.
+    pub fn user_id(&self) -> Option<i64> {
+        match &self.callback_query {
+            Some(query) => Some(query.from.id),
+            None => self
+                .message()
+                .and_then(|message| message.from.as_ref())
+                .map(|user| user.id),
+        }
+    }
+}
+
+/// A press of an inline keyboard button.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CallbackQuery {
+    /// The identifier used to answer the query
+    pub id: String,
+    /// The user who pressed the button
+    pub from: User,
+    /// The message with the button, missing if it is too old
+    pub message: Option<Message>,
+    /// The `callback_data` of the button
+    pub data: Option<String>,
+}
+
+/// A message.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Message {
+    /// The message identifier inside its chat
+    pub message_id: i64,
+    /// The sender, empty for messages sent to channels
+    pub from: Option<User>,
+    /// The chat the message belongs to
+    pub chat: Chat,
+    /// The date the message was sent, as a Unix timestamp
+    pub date: i64,
+    /// The text of a text message
+    pub text: Option<String>,
+}
+
+/// A Telegram user or bot.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct User {
+    /// The unique user identifier
+    pub id: i64,
+    /// Whether the user is a bot
+    pub is_bot: bool,
+    /// The user's first name
+    pub first_name: String,
+    /// The user's last name
+    pub last_name: Option<String>,
+    /// The user's username, without the leading `@`
+    pub username: Option<String>,
+}
+
+/// A chat.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Chat {
+    /// The unique chat identifier
+    pub id: i64,
+    /// The type of the chat
+    #[serde(rename = "type")]
+    pub kind: ChatKind,
+    /// The title of groups, supergroups and channels
+    pub title: Option<String>,
+    /// The username of private chats, supergroups and channels
+    pub username: Option<String>,
+}
+
+/// The type of a chat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatKind {
+    /// A private chat with a single user
+    Private,
+    /// A basic group
+    Group,
+    /// A supergroup
+    Supergroup,
+    /// A channel
+    Channel,
+}
+
+/// An inline keyboard attached to a message.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InlineKeyboardMarkup {
+    /// The rows of buttons, from top to bottom
+    pub inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
+}
+
+/// A button of an inline keyboard.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InlineKeyboardButton {
+    /// The label of the button
+    pub text: String,
+    /// The data sent back in a `CallbackQuery`, at most 64 bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_data: Option<String>,
+    /// The address opened by the button
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
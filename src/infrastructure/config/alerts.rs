@@ -0,0 +1,27 @@
+use serde::Deserialize;
+
+use crate::infrastructure::logger::LogLevel;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AlertsConfig {
+    /// The Telegram chat receiving alerts, alerts are disabled if unset
+    pub chat_id: Option<i64>,
+    /// `warn` or `error`
+    pub level: LogLevel,
+    /// Seconds to wait for more alerts before sending a message
+    pub batch_window: u64,
+    /// Minimum seconds between two alert messages
+    pub min_interval: u64,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            chat_id: None,
+            level: LogLevel::Warn,
+            batch_window: 10,
+            min_interval: 60,
+        }
+    }
+}
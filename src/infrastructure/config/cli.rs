@@ -0,0 +1,91 @@
+//! Parses the command line flags that override the configuration.
+
+use std::path::PathBuf;
+
+use super::error::ConfigError;
+
+/// The usage text printed for `--help`.
+pub const USAGE: &str = "\
+Usage: pilipili_bot [OPTIONS] [COMMAND]
+
+Commands:
+  check-config              Validates the configuration and exits
+
+Options:
+  -c, --config <PATH>       Configuration file, defaults to $PILIPILI_CONFIG or config/config.toml
+      --set <KEY=VALUE>     Overrides a configuration value, e.g. --set emby.base_url=http://emby:8096
+      --log-level <LEVEL>   Overrides logger.level
+  -h, --help                Prints this help
+
+Environment variables such as PILIPILI__EMBY__API_KEY override the
+configuration file, command line flags override both.";
+
+/// A subcommand replacing the normal bot run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CliCommand {
+    /// Loads and validates the configuration, then exits
+    CheckConfig,
+}
+
+/// The parsed command line flags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliArgs {
+    /// The configuration file given with `--config`
+    pub config: Option<PathBuf>,
+    /// `key.path=value` overrides, in the order they were given
+    pub overrides: Vec<(String, String)>,
+    /// Whether `--help` was given
+    pub help: bool,
+    /// The subcommand, if any
+    pub command: Option<CliCommand>,
+}
+
+impl CliArgs {
+    /// Parses the arguments following the program name.
+    ///
+    /// Flags taking a value accept it both as the next argument and after
+    /// `=`, e.g. `--config bot.toml` and `--config=bot.toml`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::InvalidArgument` for unknown flags and flags
+    /// missing their value.
+    pub fn parse<I, S>(args: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().map(Into::into);
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_owned(), Some(value.to_owned())),
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| ConfigError::InvalidArgument(format!("{} requires a value", flag)))
+            };
+
+            match flag.as_str() {
+                "-c" | "--config" => parsed.config = Some(PathBuf::from(value()?)),
+                "--set" => {
+                    let assignment = value()?;
+                    let (key, value) = assignment.split_once('=').ok_or_else(|| {
+                        ConfigError::InvalidArgument(format!("--set expects KEY=VALUE, got `{}`", assignment))
+                    })?;
+                    parsed.overrides.push((key.trim().to_owned(), value.to_owned()));
+                }
+                "--log-level" => parsed.overrides.push(("logger.level".to_owned(), value()?)),
+                "-h" | "--help" => parsed.help = true,
+                "check-config" if parsed.command.is_none() => parsed.command = Some(CliCommand::CheckConfig),
+                _ => return Err(ConfigError::InvalidArgument(format!("unknown argument `{}`", flag))),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
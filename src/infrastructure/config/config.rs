@@ -1,77 +1,51 @@
 use serde::Deserialize;
-use std::fs;
-use std::io;
-use std::path::Path;
-use std::sync::RwLock;
-use once_cell::sync::Lazy;
 
-use crate::{error_log, info_log};
-use super::emby::EmbyConfig;
-
-const CONFIG_LOGGER_DOMAIN: &str = "[CONFIG]";
-const CONFIG_DIR: &str = "config";
-const CONFIG_FILE: &str = "config/config.toml";
-const TEMPLATE_FILE: &str = "src/infrastructure/config/config.template";
-
-#[derive(Debug, Deserialize, Clone)]
+use super::alerts::AlertsConfig;
+use super::database::DatabaseConfig;
+use super::emby::{deserialize_servers, EmbyConfig};
+use super::error::ConfigError;
+use super::loader::ConfigLoader;
+use super::logger::LoggerConfig;
+use super::metrics::MetricsConfig;
+use super::password::PasswordConfig;
+use super::placement::PlacementConfig;
+use super::roles::RolesConfig;
+use super::telegram::TelegramConfig;
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
 pub struct Config {
-    pub emby: EmbyConfig,
+    /// The media servers, from the `[servers.<name>]` tables, ordered by name
+    #[serde(default, deserialize_with = "deserialize_servers")]
+    pub servers: Vec<EmbyConfig>,
+    #[serde(default)]
+    pub placement: PlacementConfig,
+    #[serde(default)]
+    pub telegram: TelegramConfig,
+    #[serde(default)]
+    pub roles: RolesConfig,
+    #[serde(default)]
+    pub password: PasswordConfig,
+    #[serde(default)]
+    pub logger: LoggerConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub database: DatabaseConfig,
 }
 
-pub static CONFIG: Lazy<RwLock<Config>> = Lazy::new(|| {
-    match Config::init() {
-        Ok(config) => RwLock::new(config),
-        Err(error) => {
-            let message = format!("Failed to initialize configuration: {}", error);
-            error_log!(CONFIG_LOGGER_DOMAIN, message);
-            std::process::exit(1);
-        }
-    }
-});
-
 impl Config {
 
-    pub fn init() -> Result<Self, Box<dyn std::error::Error>> {
-        let config_path = Path::new(CONFIG_FILE);
-        let config_dir = Path::new(CONFIG_DIR);
-
-        if !config_dir.exists() {
-            fs::create_dir(config_dir)?;
-            let message = format!("📂 Create config directory: {}", CONFIG_DIR);
-            info_log!(CONFIG_LOGGER_DOMAIN, message);
-        }
-
-        if !config_path.exists() {
-            let template_path = Path::new(TEMPLATE_FILE);
-            if template_path.exists() {
-                fs::copy(template_path, config_path)?;
-                let message = format!(
-                    "📄 Copy default config: {} -> {} success!", 
-                    template_path.display(), 
-                    config_path.display()
-                );
-                info_log!(CONFIG_LOGGER_DOMAIN, message);
-            } else {
-                let message = format!("❌ Config template file missing: {}", TEMPLATE_FILE);
-                error_log!(CONFIG_LOGGER_DOMAIN, message);
-                return Err(
-                    io::Error::new(
-                        io::ErrorKind::NotFound, "Config template file missing"
-                    )
-                        .into()
-                );
-            }
-        }
-
-        let config_content = fs::read_to_string(config_path)?;
-        let config: Config = toml::from_str(&config_content)?;
-        let message = format!("✅ Config load success at {}", config_path.display());
-        info_log!(CONFIG_LOGGER_DOMAIN, message);
-
-        Ok(config)
+    /// Loads the configuration from the file and environment of the process.
+    ///
+    /// See `ConfigLoader` for the sources and their precedence.
+    pub fn init() -> Result<Self, ConfigError> {
+        ConfigLoader::from_env().load()
     }
 
-    pub fn get() -> std::sync::RwLockReadGuard<'static, Config> {
-        CONFIG.read().unwrap()
+    /// Returns the server with the given name.
+    pub fn server(&self, name: &str) -> Option<&EmbyConfig> {
+        self.servers.iter().find(|server| server.name == name)
     }
 }
@@ -0,0 +1,16 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    /// `sqlite://path`, `mysql://...` or `postgres://...`
+    pub url: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite://data/pilipili.db".to_string(),
+        }
+    }
+}
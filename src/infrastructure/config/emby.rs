@@ -1,16 +1,68 @@
-use serde::Deserialize;
+use std::collections::BTreeMap;
 
-#[derive(Debug, Deserialize, Clone)]
+use serde::{Deserialize, Deserializer};
+
+/// The API flavour a media server speaks.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerKind {
+    /// Emby, authenticated with an `api_key` parameter
+    #[default]
+    Emby,
+    /// Jellyfin, authenticated with a `MediaBrowser` authorization header
+    Jellyfin,
+}
+
+impl ServerKind {
+    /// Returns the name used in the configuration.
+    pub fn name(self) -> &'static str {
+        match self {
+            ServerKind::Emby => "emby",
+            ServerKind::Jellyfin => "jellyfin",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
 pub struct EmbyConfig {
+    /// The name of the server, taken from its `[servers.<name>]` table
+    #[serde(skip)]
+    pub name: String,
+    /// The API the server speaks
+    pub kind: ServerKind,
     pub base_url: String,
     pub api_key: String,
+    /// The Emby service account used instead of `api_key`
+    pub username: Option<String>,
+    /// The password of the service account
+    pub password: Option<String>,
+    /// The most accounts placed on this server, unlimited if unset
+    pub max_users: Option<u64>,
 }
 
 impl Default for EmbyConfig {
     fn default() -> Self {
         Self {
+            name: "main".to_string(),
+            kind: ServerKind::Emby,
             base_url: "http://127.0.0.1:8096".to_string(),
             api_key: "".to_string(),
+            username: None,
+            password: None,
+            max_users: None,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Deserializes the `[servers.<name>]` tables into a list ordered by name.
+pub(crate) fn deserialize_servers<'de, D>(deserializer: D) -> Result<Vec<EmbyConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let servers = BTreeMap::<String, EmbyConfig>::deserialize(deserializer)?;
+    Ok(servers
+        .into_iter()
+        .map(|(name, server)| EmbyConfig { name, ..server })
+        .collect())
+}
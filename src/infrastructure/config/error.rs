@@ -0,0 +1,70 @@
+//! Defines the error type returned when loading the configuration.
+
+use std::fmt::{self, Display};
+use std::io;
+use std::path::PathBuf;
+
+use super::validation::ConfigIssue;
+
+/// Represents an error that occurred while loading the configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A configuration or secret file could not be read
+    Io {
+        /// The file that could not be read
+        path: PathBuf,
+        /// The underlying error
+        error: io::Error,
+    },
+    /// A configuration source is not valid TOML or does not match `Config`
+    Parse {
+        /// Where the invalid configuration came from
+        source: String,
+        /// Why it was rejected
+        reason: String,
+    },
+    /// An environment variable or command line override is malformed
+    InvalidOverride {
+        /// The override as given
+        key: String,
+        /// Why it was rejected
+        reason: String,
+    },
+    /// A command line argument is unknown or lacks its value
+    InvalidArgument(String),
+    /// The configuration contains values that cannot work
+    Invalid(Vec<ConfigIssue>),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, error } => {
+                write!(f, "failed to read {}: {}", path.display(), error)
+            }
+            ConfigError::Parse { source, reason } => {
+                write!(f, "invalid configuration in {}: {}", source, reason)
+            }
+            ConfigError::InvalidOverride { key, reason } => {
+                write!(f, "invalid override `{}`: {}", key, reason)
+            }
+            ConfigError::InvalidArgument(reason) => write!(f, "invalid argument: {}", reason),
+            ConfigError::Invalid(issues) => {
+                write!(f, "{} configuration problem{}:", issues.len(), if issues.len() == 1 { "" } else { "s" })?;
+                for issue in issues {
+                    write!(f, "\n  {}", issue)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//! Builds the configuration from several layered sources.
+//!
+//! Later sources override earlier ones, key by key:
+//!
+//! 1. The default template embedded into the binary
+//! 2. The configuration file given with `--config` or `PILIPILI_CONFIG`,
+//!    or `config/config.toml` if neither is set
+//! 3. Environment variables such as `PILIPILI__EMBY__API_KEY`, where `__`
+//!    separates the TOML path segments
+//! 4. Command line flags
+//!
+//! Values given as environment variables or flags are strings. They are kept
+//! as strings wherever the configuration expects one, and otherwise read as
+//! TOML values, so `PILIPILI__TELEGRAM__POLL_TIMEOUT=10` sets a number while
+//! `PILIPILI__SERVERS__MAIN__API_KEY=12345` stays a string.
+//!
+//! Secrets can be read from files instead, which suits Docker and Kubernetes
+//! secrets: `api_key_file = "/run/secrets/emby_api_key"` sets `api_key` to
+//! the trimmed content of the file. This works for the keys in
+//! `SECRET_KEYS`, in every source. The file is read as part of the source
+//! naming it, so a later source setting the key itself still overrides it.
+//!
+//! A single-server `[emby]` table from older configurations is read as the
+//! server `[servers.main]`. Configurations containing both are rejected.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use pilipili_bot::infrastructure::config::ConfigLoader;
+//!
+//! let config = ConfigLoader::new()
+//!     .with_env_vars([("PILIPILI__SERVERS__MAIN__BASE_URL", "http://emby:8096")])
+//!     .with_override("telegram.poll_timeout", "10")
+//!     .load()
+//!     .unwrap();
+//!
+//! assert_eq!(config.server("main").unwrap().base_url, "http://emby:8096");
+//! assert_eq!(config.telegram.poll_timeout, 10);
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use toml::{Table, Value};
+
+use crate::{info_log, warn_log};
+use super::cli::CliArgs;
+use super::config::Config;
+use super::error::ConfigError;
+use super::validation::ConfigIssue;
+
+/// Domain identifier for configuration logs
+const CONFIG_LOGGER_DOMAIN: &str = "[CONFIG]";
+
+/// The default configuration, also written as a starting point on first run
+pub const DEFAULT_TEMPLATE: &str = include_str!("config.template");
+
+/// The configuration file used when no other file is given
+pub const DEFAULT_CONFIG_FILE: &str = "config/config.toml";
+
+/// The environment variable naming the configuration file
+pub const CONFIG_FILE_ENV: &str = "PILIPILI_CONFIG";
+
+/// The prefix of environment variables overriding configuration keys
+pub const ENV_PREFIX: &str = "PILIPILI__";
+
+/// The suffix of keys naming a file that holds the value of another key
+const SECRET_FILE_SUFFIX: &str = "_file";
+
+/// The keys that can be read from a file, `*` matching any server name
+pub const SECRET_KEYS: &[&str] = &[
+    "telegram.bot_token",
+    "database.url",
+    "servers.*.api_key",
+    "servers.*.password",
+    "emby.api_key",
+    "emby.password",
+];
+
+/// The name given to the server of a legacy `[emby]` table
+const LEGACY_SERVER_NAME: &str = "main";
+
+/// The configuration file to read.
+#[derive(Debug, Clone)]
+enum ConfigFile {
+    /// No file, only defaults and overrides
+    None,
+    /// The default file, created from the template if it is missing
+    Default(PathBuf),
+    /// A file given explicitly, which has to exist
+    Explicit(PathBuf),
+}
+
+/// Loads `Config` from layered sources.
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    /// The configuration file layer
+    file: ConfigFile,
+    /// Environment variables, unfiltered
+    env_vars: Vec<(String, String)>,
+    /// `key.path=value` overrides from the command line
+    overrides: Vec<(String, String)>,
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigLoader {
+    /// Creates a loader that only uses the embedded defaults.
+    pub fn new() -> Self {
+        Self {
+            file: ConfigFile::None,
+            env_vars: Vec::new(),
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Creates a loader for the process environment.
+    ///
+    /// The file comes from `PILIPILI_CONFIG` or defaults to
+    /// `config/config.toml`, and all `PILIPILI__` variables are applied.
+    pub fn from_env() -> Self {
+        let file = match std::env::var_os(CONFIG_FILE_ENV) {
+            Some(path) => ConfigFile::Explicit(PathBuf::from(path)),
+            None => ConfigFile::Default(PathBuf::from(DEFAULT_CONFIG_FILE)),
+        };
+        Self {
+            file,
+            ..Self::new()
+        }
+            .with_env_vars(std::env::vars())
+    }
+
+    /// Reads the given configuration file, which has to exist.
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = ConfigFile::Explicit(path.into());
+        self
+    }
+
+    /// Applies the `PILIPILI__` variables among `vars`.
+    pub fn with_env_vars<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.env_vars
+            .extend(vars.into_iter().map(|(key, value)| (key.into(), value.into())));
+        self
+    }
+
+    /// Overrides a single key, e.g. `emby.base_url`.
+    pub fn with_override(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Applies the configuration file and overrides given on the command line.
+    pub fn with_cli_args(mut self, args: &CliArgs) -> Self {
+        if let Some(path) = &args.config {
+            self = self.with_file(path.clone());
+        }
+        self.overrides.extend(args.overrides.iter().cloned());
+        self
+    }
+
+    /// Returns the configuration file to read, if any.
+    pub fn file_path(&self) -> Option<&Path> {
+        match &self.file {
+            ConfigFile::None => None,
+            ConfigFile::Default(path) | ConfigFile::Explicit(path) => Some(path),
+        }
+    }
+
+    /// Merges all sources and deserializes the result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read, a source is not valid TOML,
+    /// an override is malformed or the merged values do not fit `Config`.
+    /// The values themselves are checked by `Config::validate`.
+    pub fn load(&self) -> Result<Config, ConfigError> {
+        self.load_with_files().map(|(config, _)| config)
+    }
+
+    /// Loads the configuration like `load` and returns the files it read.
+    ///
+    /// The files are the configuration file, if it exists, followed by the
+    /// secret files, so a reload can watch all of them for changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `load`.
+    pub fn load_with_files(&self) -> Result<(Config, Vec<PathBuf>), ConfigError> {
+        let mut table = parse_table(DEFAULT_TEMPLATE, "the default template")?;
+        let mut files = Vec::new();
+
+        if let Some((path, content)) = self.read_file()? {
+            let mut file_table = parse_table(&content, &path.display().to_string())?;
+            files.push(path);
+            resolve_secret_files(&mut file_table, &mut Vec::new(), &mut files)?;
+            merge(&mut table, file_table);
+        }
+
+        let mut raw_keys = Vec::new();
+
+        let mut env_table = Table::new();
+        for (name, value) in &self.env_vars {
+            let Some(path) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let segments: Vec<String> = path.split("__").map(str::to_ascii_lowercase).collect();
+            set_path(&mut env_table, &segments, value).map_err(|reason| {
+                ConfigError::InvalidOverride { key: name.clone(), reason }
+            })?;
+            raw_keys.push(segments);
+        }
+        resolve_secret_files(&mut env_table, &mut Vec::new(), &mut files)?;
+        merge(&mut table, env_table);
+
+        let mut cli_table = Table::new();
+        for (key, value) in &self.overrides {
+            let segments: Vec<String> = key.split('.').map(str::to_owned).collect();
+            set_path(&mut cli_table, &segments, value).map_err(|reason| {
+                ConfigError::InvalidOverride { key: key.clone(), reason }
+            })?;
+            raw_keys.push(segments);
+        }
+        resolve_secret_files(&mut cli_table, &mut Vec::new(), &mut files)?;
+        merge(&mut table, cli_table);
+
+        migrate_legacy_emby(&mut table)?;
+        let raw_keys: Vec<String> = raw_keys
+            .iter()
+            .map(|segments| match segments.split_first() {
+                Some((first, rest)) if first == "emby" => {
+                    format!("servers.{}.{}", LEGACY_SERVER_NAME, rest.join("."))
+                }
+                _ => segments.join("."),
+            })
+            .collect();
+
+        deserialize(table, &raw_keys).map(|config| (config, files))
+    }
+
+    /// Returns the path and content of the configuration file, if any.
+    fn read_file(&self) -> Result<Option<(PathBuf, String)>, ConfigError> {
+        let (path, explicit) = match &self.file {
+            ConfigFile::None => return Ok(None),
+            ConfigFile::Default(path) => (path, false),
+            ConfigFile::Explicit(path) => (path, true),
+        };
+
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                info_log!(CONFIG_LOGGER_DOMAIN, "✅ Config load success at {}", path.display());
+                Ok(Some((path.clone(), content)))
+            }
+            Err(error) if !explicit && error.kind() == io::ErrorKind::NotFound => {
+                write_template(path);
+                Ok(None)
+            }
+            Err(error) => Err(ConfigError::Io { path: path.clone(), error }),
+        }
+    }
+}
+
+/// Writes the default template to `path` as a starting point for editing.
+///
+/// Failing to do so is not an error, e.g. in a read-only container the
+/// configuration usually comes from the environment.
+fn write_template(path: &Path) {
+    let result = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+        .and_then(|_| fs::write(path, DEFAULT_TEMPLATE));
+
+    match result {
+        Ok(()) => {
+            info_log!(CONFIG_LOGGER_DOMAIN, "📄 Write default config to {}", path.display());
+        }
+        Err(error) => {
+            warn_log!(
+                CONFIG_LOGGER_DOMAIN,
+                "No config file at {} and writing the default failed: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+}
+
+/// Parses a TOML document into a table.
+fn parse_table(content: &str, source: &str) -> Result<Table, ConfigError> {
+    content.parse::<Table>().map_err(|error| ConfigError::Parse {
+        source: source.to_owned(),
+        reason: error.message().to_owned(),
+    })
+}
+
+/// Merges `overlay` into `base`, recursing into tables present in both.
+fn merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(overlay_table)) => {
+                merge(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Sets the value at a key path to a raw string, creating missing tables.
+fn set_path(table: &mut Table, segments: &[String], raw: &str) -> Result<(), String> {
+    let Some((last, parents)) = segments.split_last() else {
+        return Err(String::from("empty key"));
+    };
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(String::from("empty key segment"));
+    }
+
+    let mut current = table;
+    for segment in parents {
+        let entry = current
+            .entry(segment.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        current = match entry {
+            Value::Table(child) => child,
+            _ => return Err(format!("`{}` is not a table", segment)),
+        };
+    }
+
+    current.insert(last.clone(), Value::String(raw.to_owned()));
+    Ok(())
+}
+
+/// Deserializes the merged table into `Config`.
+///
+/// Overrides are strings at first. Whenever the field at one of `raw_keys`
+/// rejects a string, the value is read as TOML instead and deserializing is
+/// retried, so every override gets the type of its field.
+fn deserialize(mut table: Table, raw_keys: &[String]) -> Result<Config, ConfigError> {
+    loop {
+        let error = match serde_path_to_error::deserialize(Value::Table(table.clone())) {
+            Ok(config) => return Ok(config),
+            Err(error) => error,
+        };
+
+        let key = error.path().to_string();
+        let typed = raw_keys
+            .contains(&key)
+            .then(|| lookup_mut(&mut table, &key))
+            .flatten()
+            .and_then(|value| {
+                let typed = value.as_str().and_then(parse_value)?;
+                *value = typed;
+                Some(())
+            });
+        if typed.is_none() {
+            return Err(ConfigError::Invalid(vec![
+                ConfigIssue::new(key, error.into_inner().message()),
+            ]));
+        }
+    }
+}
+
+/// Returns the value at a dotted key path.
+fn lookup_mut<'a>(table: &'a mut Table, key: &str) -> Option<&'a mut Value> {
+    let (parents, last) = match key.rsplit_once('.') {
+        Some((parents, last)) => (Some(parents), last),
+        None => (None, key),
+    };
+    let mut current = table;
+    for segment in parents.into_iter().flat_map(|parents| parents.split('.')) {
+        current = current.get_mut(segment)?.as_table_mut()?;
+    }
+    current.get_mut(last)
+}
+
+/// Parses a raw override as a TOML value other than a string.
+fn parse_value(raw: &str) -> Option<Value> {
+    format!("value = {}", raw)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .filter(|value| !value.is_str())
+}
+
+/// Moves a legacy `[emby]` table to `[servers.main]`.
+///
+/// # Errors
+///
+/// Returns an error if `[servers.main]` exists as well, as it is unclear
+/// which of the two tables is meant.
+fn migrate_legacy_emby(table: &mut Table) -> Result<(), ConfigError> {
+    let Some(Value::Table(emby)) = table.remove("emby") else {
+        return Ok(());
+    };
+    let servers = table
+        .entry("servers")
+        .or_insert_with(|| Value::Table(Table::new()));
+    let Value::Table(servers) = servers else {
+        return Ok(());
+    };
+    if servers.contains_key(LEGACY_SERVER_NAME) {
+        return Err(ConfigError::Invalid(vec![ConfigIssue::new(
+            "emby",
+            format!("is read as [servers.{0}], which exists as well; move its keys to [servers.{0}]", LEGACY_SERVER_NAME),
+        )]));
+    }
+
+    info_log!(CONFIG_LOGGER_DOMAIN, "Reading the [emby] section as [servers.{}]", LEGACY_SERVER_NAME);
+    servers.insert(LEGACY_SERVER_NAME.to_owned(), Value::Table(emby));
+    Ok(())
+}
+
+/// Returns whether a key path names a secret that can be read from a file.
+fn is_secret(path: &[String]) -> bool {
+    SECRET_KEYS.iter().any(|pattern| {
+        let pattern: Vec<&str> = pattern.split('.').collect();
+        pattern.len() == path.len()
+            && pattern
+                .iter()
+                .zip(path)
+                .all(|(expected, segment)| *expected == "*" || expected == segment)
+    })
+}
+
+/// Replaces the `<key>_file` entries of secrets by `<key>` holding the
+/// file's content.
+///
+/// `path` is the key path of `table`, and the files read are added to
+/// `files`. Other keys ending in `_file` are left alone. Giving both a
+/// secret and its file in one source is an error.
+fn resolve_secret_files(
+    table: &mut Table,
+    path: &mut Vec<String>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), ConfigError> {
+    let secret_keys: Vec<(String, String)> = table
+        .iter()
+        .filter(|(_, value)| value.is_str())
+        .filter_map(|(file_key, _)| {
+            let key = file_key.strip_suffix(SECRET_FILE_SUFFIX)?;
+            path.push(key.to_owned());
+            let secret = is_secret(path);
+            path.pop();
+            secret.then(|| (file_key.clone(), key.to_owned()))
+        })
+        .collect();
+
+    for (file_key, key) in secret_keys {
+        if table.contains_key(&key) {
+            let key_path = path.iter().chain([&key]).cloned().collect::<Vec<_>>().join(".");
+            return Err(ConfigError::Invalid(vec![ConfigIssue::new(
+                key_path,
+                format!("is given together with `{}`, set only one of them", file_key),
+            )]));
+        }
+        let Some(Value::String(file)) = table.remove(&file_key) else {
+            continue;
+        };
+        let file = PathBuf::from(file);
+        let secret = fs::read_to_string(&file)
+            .map_err(|error| ConfigError::Io { path: file.clone(), error })?;
+        table.insert(key, Value::String(secret.trim_end_matches(['\r', '\n']).to_owned()));
+        files.push(file);
+    }
+
+    for (key, value) in table.iter_mut() {
+        if let Value::Table(child) = value {
+            path.push(key.clone());
+            resolve_secret_files(child, path, files)?;
+            path.pop();
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use serde::Deserialize;
+
+use crate::infrastructure::logger::{LogFormat, LogLevel, LogRotation};
+use crate::infrastructure::network::RedactionRules;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LoggerConfig {
+    pub level: LogLevel,
+    /// Filter directives overriding the level per log domain or module,
+    /// e.g. `NETWORK=debug` or `pilipili_bot::bot=trace`
+    pub directives: Vec<String>,
+    pub directory: String,
+    pub prefix: String,
+    pub rotation: LogRotation,
+    pub max_file_size_mb: Option<u64>,
+    pub max_files: Option<usize>,
+    pub max_age_days: Option<u64>,
+    pub compress: bool,
+    pub file_format: LogFormat,
+    pub console_format: LogFormat,
+    pub console: bool,
+    /// Secrets masked in network logs, from `[logger.redaction]`
+    pub redaction: RedactionConfig,
+}
+
+impl Default for LoggerConfig {
+    fn default() -> Self {
+        Self {
+            level: LogLevel::Info,
+            directives: Vec::new(),
+            directory: "logs".to_string(),
+            prefix: "".to_string(),
+            rotation: LogRotation::Daily,
+            max_file_size_mb: None,
+            max_files: None,
+            max_age_days: None,
+            compress: false,
+            file_format: LogFormat::Compact,
+            console_format: LogFormat::Compact,
+            console: true,
+            redaction: RedactionConfig::default(),
+        }
+    }
+}
+
+/// Secrets masked in network logs in addition to the built-in ones.
+///
+/// The built-in rules mask the credentials of Emby, Jellyfin and Telegram,
+/// see `RedactionRules::default`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Query parameters whose values are masked, e.g. `session`
+    pub query_params: Vec<String>,
+    /// Headers whose values are masked, e.g. `X-Api-Key`
+    pub headers: Vec<String>,
+    /// JSON body fields whose values are masked, at any nesting depth
+    pub json_fields: Vec<String>,
+    /// Whether Telegram bot tokens in URL paths are masked
+    pub telegram_bot_token: bool,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            query_params: Vec::new(),
+            headers: Vec::new(),
+            json_fields: Vec::new(),
+            telegram_bot_token: true,
+        }
+    }
+}
+
+impl RedactionConfig {
+    /// Returns the built-in rules extended by the configured names.
+    pub fn rules(&self) -> RedactionRules {
+        let mut rules = RedactionRules::default().with_telegram_bot_token(self.telegram_bot_token);
+        for name in &self.query_params {
+            rules = rules.with_query_param(name);
+        }
+        for name in &self.headers {
+            rules = rules.with_header(name);
+        }
+        for name in &self.json_fields {
+            rules = rules.with_json_field(name);
+        }
+        rules
+    }
+
+}
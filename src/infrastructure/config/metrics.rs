@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+/// Where the Prometheus metrics are served.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Whether to serve `/metrics`
+    pub enabled: bool,
+    /// The address to listen on, e.g. `127.0.0.1:9898`
+    pub address: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:9898".to_string(),
+        }
+    }
+}
@@ -1,4 +1,31 @@
+#[allow(clippy::module_inception)]
 pub mod config;
 pub mod emby;
+pub mod telegram;
+pub mod logger;
+pub mod alerts;
+pub mod cli;
+pub mod error;
+pub mod loader;
+pub mod validation;
+pub mod database;
+pub mod reload;
+pub mod placement;
+pub mod password;
+pub mod roles;
+pub mod metrics;
 
-pub use config::{Config, CONFIG};
+pub use config::Config;
+pub use logger::{LoggerConfig, RedactionConfig};
+pub use alerts::AlertsConfig;
+pub use cli::{CliArgs, CliCommand};
+pub use error::ConfigError;
+pub use loader::ConfigLoader;
+pub use validation::ConfigIssue;
+pub use database::DatabaseConfig;
+pub use emby::{EmbyConfig, ServerKind};
+pub use placement::{PlacementConfig, PlacementStrategy};
+pub use password::PasswordConfig;
+pub use roles::RolesConfig;
+pub use metrics::MetricsConfig;
+pub use reload::{ConfigChange, ConfigReloader, ConfigSection};
@@ -0,0 +1,28 @@
+use serde::Deserialize;
+
+/// The shortest generated password
+pub const MIN_GENERATED_LENGTH: usize = 8;
+
+/// The longest password, generated or chosen
+pub const MAX_PASSWORD_LENGTH: usize = 128;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PasswordConfig {
+    /// The length of generated passwords
+    pub length: usize,
+    /// The shortest password users may choose
+    pub min_length: usize,
+    /// Seconds after which messages containing a password are deleted
+    pub delete_after: u64,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            length: 16,
+            min_length: 8,
+            delete_after: 5 * 60,
+        }
+    }
+}
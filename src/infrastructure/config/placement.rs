@@ -0,0 +1,30 @@
+use serde::Deserialize;
+
+/// How the server for a new account is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlacementStrategy {
+    /// The server with the fewest bound accounts
+    #[default]
+    Users,
+    /// The server with the fewest active sessions
+    Sessions,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PlacementConfig {
+    /// `users` or `sessions`
+    pub strategy: PlacementStrategy,
+    /// Sessions active within this many seconds count as active
+    pub session_window: u64,
+}
+
+impl Default for PlacementConfig {
+    fn default() -> Self {
+        Self {
+            strategy: PlacementStrategy::Users,
+            session_window: 15 * 60,
+        }
+    }
+}
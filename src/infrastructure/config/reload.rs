@@ -0,0 +1,302 @@
+//! Reloads the configuration while the bot is running.
+//!
+//! `ConfigReloader` reloads the configuration when the configuration file or
+//! one of the secret files it names (`api_key_file` and the like) changes on
+//! disk, or the process receives SIGHUP. Secret files named by environment
+//! variables or flags are watched as well. Files are polled for changes of
+//! their modification time, so secrets replaced without touching it, e.g. by
+//! some volume mounts, need a SIGHUP. The new configuration is
+//! validated first: if it is invalid, the problems are logged and the active
+//! configuration is kept. Otherwise `AppContext::replace_config` swaps it in
+//! under a write lock, so readers see either the old or the new
+//! configuration, never a mix.
+//!
+//! Components that cache settings subscribe with `AppContext::subscribe` and
+//! are told which sections changed, e.g. to apply a new log level.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let mut changes = context.subscribe();
+//! tokio::spawn(ConfigReloader::new(ConfigLoader::from_env()).run(context.clone()));
+//!
+//! loop {
+//!     let change = match changes.recv().await {
+//!         Ok(change) => change,
+//!         Err(RecvError::Lagged(_)) => continue,
+//!         Err(RecvError::Closed) => break,
+//!     };
+//!     if change.contains(ConfigSection::Logger) {
+//!         logger.set_level(change.config.logger.level, &change.config.logger.directives)?;
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tokio::time::MissedTickBehavior;
+
+use crate::context::AppContext;
+use crate::{error_log, info_log};
+use super::config::Config;
+use super::error::ConfigError;
+use super::loader::ConfigLoader;
+
+/// Domain identifier for configuration reload logs
+const CONFIG_RELOAD_LOGGER_DOMAIN: &str = "[CONFIG]";
+
+/// How often the configuration file is checked for changes by default
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A top-level section of the configuration file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigSection {
+    Servers,
+    Placement,
+    Telegram,
+    Roles,
+    Password,
+    Logger,
+    Alerts,
+    Metrics,
+    Database,
+}
+
+impl ConfigSection {
+    /// Returns the name of the section in the configuration file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigSection::Servers => "servers",
+            ConfigSection::Placement => "placement",
+            ConfigSection::Telegram => "telegram",
+            ConfigSection::Roles => "roles",
+            ConfigSection::Password => "password",
+            ConfigSection::Logger => "logger",
+            ConfigSection::Alerts => "alerts",
+            ConfigSection::Metrics => "metrics",
+            ConfigSection::Database => "database",
+        }
+    }
+}
+
+impl fmt::Display for ConfigSection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A configuration that replaced the previous one.
+#[derive(Debug, Clone)]
+pub struct ConfigChange {
+    /// The sections that differ from the previous configuration
+    pub sections: Vec<ConfigSection>,
+    /// The new configuration
+    pub config: Arc<Config>,
+}
+
+impl ConfigChange {
+    /// Returns whether the given section changed.
+    pub fn contains(&self, section: ConfigSection) -> bool {
+        self.sections.contains(&section)
+    }
+}
+
+impl Config {
+    /// Returns the sections in which `other` differs from this configuration.
+    pub fn changed_sections(&self, other: &Config) -> Vec<ConfigSection> {
+        let mut sections = Vec::new();
+        if self.servers != other.servers {
+            sections.push(ConfigSection::Servers);
+        }
+        if self.placement != other.placement {
+            sections.push(ConfigSection::Placement);
+        }
+        if self.telegram != other.telegram {
+            sections.push(ConfigSection::Telegram);
+        }
+        if self.roles != other.roles {
+            sections.push(ConfigSection::Roles);
+        }
+        if self.password != other.password {
+            sections.push(ConfigSection::Password);
+        }
+        if self.logger != other.logger {
+            sections.push(ConfigSection::Logger);
+        }
+        if self.alerts != other.alerts {
+            sections.push(ConfigSection::Alerts);
+        }
+        if self.metrics != other.metrics {
+            sections.push(ConfigSection::Metrics);
+        }
+        if self.database != other.database {
+            sections.push(ConfigSection::Database);
+        }
+        sections
+    }
+}
+
+/// Reloads the configuration on file changes and SIGHUP.
+#[derive(Debug, Clone)]
+pub struct ConfigReloader {
+    /// Loads the configuration from the same sources as at startup
+    loader: ConfigLoader,
+    /// How often the configuration file is checked for changes
+    poll_interval: Duration,
+}
+
+impl ConfigReloader {
+    /// Creates a reloader using the sources of `loader`.
+    pub fn new(loader: ConfigLoader) -> Self {
+        Self {
+            loader,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Sets how often the configuration file is checked for changes.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Loads and validates the configuration and swaps it in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error and keeps the active configuration if the sources
+    /// cannot be loaded or the result is invalid.
+    pub fn reload(&self, context: &AppContext) -> Result<Vec<ConfigSection>, ConfigError> {
+        context.replace_config(self.loader.load()?)
+    }
+
+    /// Watches for file changes and SIGHUP until the task is dropped.
+    pub async fn run(self, context: Arc<AppContext>) {
+        let mut watched = WatchedFiles::new(self.watched_files());
+        let mut ticker = tokio::time::interval(self.poll_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut hangup = listen_hangup();
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if !watched.changed() {
+                        continue;
+                    }
+                    info_log!(CONFIG_RELOAD_LOGGER_DOMAIN, "Configuration file changed, reloading");
+                }
+                _ = next_hangup(&mut hangup) => {
+                    info_log!(CONFIG_RELOAD_LOGGER_DOMAIN, "Received SIGHUP, reloading configuration");
+                }
+            }
+
+            let result = self.loader.load_with_files().and_then(|(config, files)| {
+                watched = WatchedFiles::new(files);
+                context.replace_config(config)
+            });
+            match result {
+                Ok(sections) if sections.is_empty() => {
+                    info_log!(CONFIG_RELOAD_LOGGER_DOMAIN, "Configuration unchanged");
+                }
+                Ok(sections) => {
+                    let names: Vec<&str> = sections.iter().map(ConfigSection::name).collect();
+                    info_log!(CONFIG_RELOAD_LOGGER_DOMAIN, "✅ Configuration reloaded, changed: {}", names.join(", "));
+                }
+                Err(error) => {
+                    error_log!(CONFIG_RELOAD_LOGGER_DOMAIN, "Keeping the active configuration: {}", error);
+                }
+            }
+        }
+    }
+}
+
+impl ConfigReloader {
+    /// Returns the files the configuration is currently read from.
+    ///
+    /// Falls back to the configuration file alone if loading fails, e.g.
+    /// because a secret file is missing.
+    fn watched_files(&self) -> Vec<PathBuf> {
+        match self.loader.load_with_files() {
+            Ok((_, files)) => files,
+            Err(_) => self.loader.file_path().map(PathBuf::from).into_iter().collect(),
+        }
+    }
+}
+
+/// The files watched for changes, with their last seen modification times.
+#[derive(Debug)]
+struct WatchedFiles(Vec<(PathBuf, Option<SystemTime>)>);
+
+impl WatchedFiles {
+    /// Starts watching `files` from their current state.
+    fn new(files: Vec<PathBuf>) -> Self {
+        let files = files
+            .into_iter()
+            .map(|file| {
+                let modified = modified_time(&file);
+                (file, modified)
+            })
+            .collect();
+        Self(files)
+    }
+
+    /// Returns whether any file changed since the last call.
+    fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (file, last_modified) in &mut self.0 {
+            let modified = modified_time(file);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Returns the modification time of a file, or `None` if it is missing.
+fn modified_time(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(unix)]
+type Hangup = Option<tokio::signal::unix::Signal>;
+
+#[cfg(not(unix))]
+type Hangup = ();
+
+/// Starts listening for SIGHUP, where supported.
+#[cfg(unix)]
+fn listen_hangup() -> Hangup {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::hangup()) {
+        Ok(signal) => Some(signal),
+        Err(error) => {
+            error_log!(CONFIG_RELOAD_LOGGER_DOMAIN, "Failed to listen for SIGHUP: {}", error);
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn listen_hangup() -> Hangup {}
+
+/// Waits for the next SIGHUP, or forever if it cannot be received.
+#[cfg(unix)]
+async fn next_hangup(hangup: &mut Hangup) {
+    if let Some(signal) = hangup
+        && signal.recv().await.is_some()
+    {
+        return;
+    }
+    std::future::pending().await
+}
+
+#[cfg(not(unix))]
+async fn next_hangup(_hangup: &mut Hangup) {
+    std::future::pending().await
+}
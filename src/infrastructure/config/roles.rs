@@ -0,0 +1,16 @@
+use serde::Deserialize;
+
+/// The Telegram users holding a role from the start.
+///
+/// These roles are written to the database when the bot starts and whenever
+/// the section changes, taking precedence over roles granted with `/grant`.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct RolesConfig {
+    /// Telegram user ids of the owners, who may do everything
+    pub owners: Vec<i64>,
+    /// Telegram user ids of the admins
+    pub admins: Vec<i64>,
+    /// Telegram user ids of the moderators
+    pub moderators: Vec<i64>,
+}
@@ -0,0 +1,28 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub api_url: String,
+    pub poll_timeout: u64,
+    /// Telegram user ids seeded as admins, like `[roles] admins`
+    pub admin_ids: Vec<i64>,
+    /// Seconds the bot waits for the next reply in a conversation
+    pub conversation_timeout: u64,
+    /// Seconds inline buttons keep working after they were sent
+    pub button_ttl: u64,
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        Self {
+            bot_token: "".to_string(),
+            api_url: "https://api.telegram.org".to_string(),
+            poll_timeout: 30,
+            admin_ids: Vec::new(),
+            conversation_timeout: 5 * 60,
+            button_ttl: 24 * 60 * 60,
+        }
+    }
+}
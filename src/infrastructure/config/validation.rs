@@ -0,0 +1,303 @@
+//! Checks a loaded configuration for values that would fail at runtime.
+//!
+//! Deserialization only checks types. `Config::validate` additionally checks
+//! that URLs parse, secrets are set, intervals are in a sensible range and
+//! the database location is usable. It reports every problem at once, each
+//! with the TOML key path it belongs to, so a broken deployment can be fixed
+//! in one go.
+
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::net::SocketAddr;
+use std::path::Path;
+
+use reqwest::Url;
+
+use crate::infrastructure::logger::handle::parse_filter;
+use super::config::Config;
+use super::emby::ServerKind;
+use super::password::{MAX_PASSWORD_LENGTH, MIN_GENERATED_LENGTH};
+use super::error::ConfigError;
+
+/// The longest long polling timeout accepted, in seconds
+const MAX_POLL_TIMEOUT: u64 = 60;
+
+/// The longest alert batch window accepted, in seconds
+const MAX_ALERT_BATCH_WINDOW: u64 = 600;
+
+/// The longest minimum interval between alerts accepted, in seconds
+const MAX_ALERT_MIN_INTERVAL: u64 = 24 * 60 * 60;
+
+/// The longest window for counting sessions as active, in seconds
+const MAX_SESSION_WINDOW: u64 = 24 * 60 * 60;
+
+/// The longest time a password may stay visible, in seconds
+const MAX_CREDENTIAL_LIFETIME: u64 = 60 * 60;
+
+/// The longest wait for the next reply in a conversation, in seconds
+const MAX_CONVERSATION_TIMEOUT: u64 = 24 * 60 * 60;
+
+/// The longest time inline buttons keep working, in seconds
+const MAX_BUTTON_TTL: u64 = 30 * 24 * 60 * 60;
+
+/// A single problem found in the configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// The TOML key path, e.g. `servers.main.base_url`
+    pub key: String,
+    /// What is wrong and how to fix it
+    pub message: String,
+}
+
+impl ConfigIssue {
+    /// Creates an issue for the given key.
+    pub fn new(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+impl Config {
+    /// Checks the configuration and returns every problem found.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Invalid` listing all problems.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut issues = Vec::new();
+
+        if self.servers.is_empty() {
+            issues.push(ConfigIssue::new("servers", "add at least one [servers.<name>] table"));
+        }
+        for server in &self.servers {
+            let key = format!("servers.{}", server.name);
+            if !is_server_name(&server.name) {
+                issues.push(ConfigIssue::new(
+                    &key,
+                    "server names may only contain lowercase letters, digits, `-` and `_`",
+                ));
+            }
+            check_url(&mut issues, &format!("{}.base_url", key), &server.base_url);
+            match (server.kind, &server.username) {
+                (ServerKind::Emby, Some(username)) => {
+                    if username.trim().is_empty() {
+                        issues.push(ConfigIssue::new(format!("{}.username", key), "must not be empty"));
+                    }
+                }
+                (ServerKind::Jellyfin, Some(_)) => issues.push(ConfigIssue::new(
+                    format!("{}.username", key),
+                    "service accounts are only supported on Emby servers, set api_key instead",
+                )),
+                (_, None) => check_secret(&mut issues, &format!("{}.api_key", key), &server.api_key),
+            }
+            if server.max_users == Some(0) {
+                issues.push(ConfigIssue::new(format!("{}.max_users", key), "must be at least 1, or unset for no limit"));
+            }
+        }
+        check_range(&mut issues, "placement.session_window", self.placement.session_window, 1, MAX_SESSION_WINDOW);
+
+        check_url(&mut issues, "telegram.api_url", &self.telegram.api_url);
+        check_secret(&mut issues, "telegram.bot_token", &self.telegram.bot_token);
+        if !self.telegram.bot_token.is_empty() && !is_bot_token(&self.telegram.bot_token) {
+            issues.push(ConfigIssue::new(
+                "telegram.bot_token",
+                "expected the `<bot id>:<secret>` token issued by @BotFather",
+            ));
+        }
+        check_range(&mut issues, "telegram.poll_timeout", self.telegram.poll_timeout, 1, MAX_POLL_TIMEOUT);
+        check_range(
+            &mut issues,
+            "telegram.conversation_timeout",
+            self.telegram.conversation_timeout,
+            10,
+            MAX_CONVERSATION_TIMEOUT,
+        );
+        check_range(&mut issues, "telegram.button_ttl", self.telegram.button_ttl, 60, MAX_BUTTON_TTL);
+
+        let mut seeded = HashSet::new();
+        let roles = [
+            ("roles.owners", &self.roles.owners),
+            ("roles.admins", &self.roles.admins),
+            ("roles.moderators", &self.roles.moderators),
+        ];
+        for (key, ids) in roles {
+            for (index, id) in ids.iter().enumerate() {
+                let key = format!("{}[{}]", key, index);
+                if *id <= 0 {
+                    issues.push(ConfigIssue::new(key, "must be a Telegram user id"));
+                } else if !seeded.insert(*id) {
+                    issues.push(ConfigIssue::new(key, format!("user {} is listed more than once", id)));
+                }
+            }
+        }
+
+        let password = &self.password;
+        let max_length = MAX_PASSWORD_LENGTH as u64;
+        check_range(&mut issues, "password.length", password.length as u64, MIN_GENERATED_LENGTH as u64, max_length);
+        check_range(&mut issues, "password.min_length", password.min_length as u64, 1, max_length);
+        check_range(&mut issues, "password.delete_after", password.delete_after, 10, MAX_CREDENTIAL_LIFETIME);
+
+        for (index, directive) in self.logger.directives.iter().enumerate() {
+            if let Err(error) = parse_filter(directive) {
+                issues.push(ConfigIssue::new(format!("logger.directives[{}]", index), error.to_string()));
+            }
+        }
+        let redaction = &self.logger.redaction;
+        let redacted_names = [
+            ("logger.redaction.query_params", &redaction.query_params),
+            ("logger.redaction.headers", &redaction.headers),
+            ("logger.redaction.json_fields", &redaction.json_fields),
+        ];
+        for (key, names) in redacted_names {
+            for (index, name) in names.iter().enumerate() {
+                if name.trim().is_empty() {
+                    issues.push(ConfigIssue::new(format!("{}[{}]", key, index), "must not be empty"));
+                }
+            }
+        }
+        if self.logger.max_file_size_mb == Some(0) {
+            issues.push(ConfigIssue::new("logger.max_file_size_mb", "must be at least 1"));
+        }
+        if self.logger.max_files == Some(0) {
+            issues.push(ConfigIssue::new("logger.max_files", "must be at least 1, or unset to keep all files"));
+        }
+        if self.logger.max_age_days == Some(0) {
+            issues.push(ConfigIssue::new("logger.max_age_days", "must be at least 1, or unset to keep all files"));
+        }
+        if self.logger.directory.trim().is_empty() {
+            issues.push(ConfigIssue::new("logger.directory", "must not be empty"));
+        }
+
+        if self.alerts.chat_id.is_some() {
+            check_range(&mut issues, "alerts.batch_window", self.alerts.batch_window, 1, MAX_ALERT_BATCH_WINDOW);
+            check_range(&mut issues, "alerts.min_interval", self.alerts.min_interval, 0, MAX_ALERT_MIN_INTERVAL);
+        }
+
+        if self.metrics.enabled && self.metrics.address.parse::<SocketAddr>().is_err() {
+            issues.push(ConfigIssue::new(
+                "metrics.address",
+                format!("`{}` is not an address such as 127.0.0.1:9898", self.metrics.address),
+            ));
+        }
+
+        check_database_url(&mut issues, "database.url", &self.database.url);
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(issues))
+        }
+    }
+}
+
+/// Checks that `value` is an absolute `http` or `https` URL.
+fn check_url(issues: &mut Vec<ConfigIssue>, key: &str, value: &str) {
+    match Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        Ok(url) => issues.push(ConfigIssue::new(
+            key,
+            format!("unsupported scheme `{}`, expected http or https", url.scheme()),
+        )),
+        Err(error) => issues.push(ConfigIssue::new(key, format!("`{}` is not a valid URL: {}", value, error))),
+    }
+}
+
+/// Checks that a secret is set.
+fn check_secret(issues: &mut Vec<ConfigIssue>, key: &str, value: &str) {
+    if value.trim().is_empty() {
+        issues.push(ConfigIssue::new(
+            key,
+            format!("must not be empty, set it directly or through `{}_file`", key),
+        ));
+    }
+}
+
+/// Checks that `value` lies within `min..=max`.
+fn check_range(issues: &mut Vec<ConfigIssue>, key: &str, value: u64, min: u64, max: u64) {
+    if !(min..=max).contains(&value) {
+        issues.push(ConfigIssue::new(key, format!("must be between {} and {}, got {}", min, max, value)));
+    }
+}
+
+/// Returns whether a server name can be typed in a command, e.g. `eu-1`.
+fn is_server_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+/// Returns whether a token looks like `<digits>:<secret>`.
+fn is_bot_token(token: &str) -> bool {
+    token.split_once(':').is_some_and(|(id, secret)| {
+        !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) && !secret.is_empty()
+    })
+}
+
+/// Checks the database URL and, for SQLite, that the file can be created.
+fn check_database_url(issues: &mut Vec<ConfigIssue>, key: &str, value: &str) {
+    if value == "sqlite::memory:" {
+        return;
+    }
+    let Some((scheme, location)) = value.split_once("://") else {
+        issues.push(ConfigIssue::new(key, "expected a URL such as `sqlite://data/pilipili.db`"));
+        return;
+    };
+
+    let feature = match scheme {
+        "sqlite" => Some(("sqlite", cfg!(feature = "sqlite"))),
+        "mysql" => Some(("mysql", cfg!(feature = "mysql"))),
+        "postgres" | "postgresql" => Some(("postgres", cfg!(feature = "postgres"))),
+        _ => None,
+    };
+    if let Some((feature, false)) = feature {
+        issues.push(ConfigIssue::new(
+            key,
+            format!("{} support is not compiled in, rebuild with the `{}` feature", scheme, feature),
+        ));
+        return;
+    }
+
+    match scheme {
+        "sqlite" => {
+            let path = Path::new(location.split('?').next().unwrap_or_default());
+            if path.as_os_str().is_empty() {
+                issues.push(ConfigIssue::new(key, "the SQLite path is empty"));
+            } else if path.is_dir() {
+                issues.push(ConfigIssue::new(key, format!("{} is a directory", path.display())));
+            } else if let Some(ancestor) = path
+                .ancestors()
+                .skip(1)
+                .map(|ancestor| if ancestor.as_os_str().is_empty() { Path::new(".") } else { ancestor })
+                .find(|ancestor| ancestor.exists())
+            {
+                // Missing directories are created on startup, but only below a directory.
+                if !ancestor.is_dir() {
+                    issues.push(ConfigIssue::new(
+                        key,
+                        format!("{} is not a directory, cannot create {}", ancestor.display(), path.display()),
+                    ));
+                } else if ancestor.metadata().is_ok_and(|metadata| metadata.permissions().readonly()) {
+                    issues.push(ConfigIssue::new(key, format!("{} is read-only", ancestor.display())));
+                }
+            }
+        }
+        "mysql" | "postgres" | "postgresql" => {
+            if let Err(error) = Url::parse(value) {
+                issues.push(ConfigIssue::new(key, format!("not a valid URL: {}", error)));
+            }
+        }
+        _ => issues.push(ConfigIssue::new(
+            key,
+            format!("unsupported database `{}`, expected sqlite, mysql or postgres", scheme),
+        )),
+    }
+}
@@ -0,0 +1,149 @@
+//! Stores which media server accounts belong to which Telegram user.
+//!
+//! A Telegram user has at most one account per server, but may have
+//! accounts on several servers.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::connection::Database;
+use super::error::DatabaseError;
+
+/// A Telegram user's account on a media server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountBinding {
+    /// The Telegram user id
+    pub telegram_id: i64,
+    /// The name of the server
+    pub server: String,
+    /// The user id on the server
+    pub emby_user_id: String,
+    /// The user name on the server
+    pub emby_name: String,
+    /// When the binding was created, as a Unix timestamp
+    pub created_at: i64,
+    /// When the account expires, as a Unix timestamp, `None` for never
+    pub expires_at: Option<i64>,
+}
+
+rbatis::crud!(AccountBinding {}, "account_bindings");
+
+/// A row of the per-server account count.
+#[derive(Deserialize)]
+struct ServerCount {
+    server: String,
+    users: i64,
+}
+
+impl AccountBinding {
+    /// Creates a binding created now.
+    pub fn new(
+        telegram_id: i64,
+        server: impl Into<String>,
+        emby_user_id: impl Into<String>,
+        emby_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            telegram_id,
+            server: server.into(),
+            emby_user_id: emby_user_id.into(),
+            emby_name: emby_name.into(),
+            created_at: chrono::Utc::now().timestamp(),
+            expires_at: None,
+        }
+    }
+}
+
+impl Database {
+    /// Binds an account to a Telegram user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user already has an account on that server.
+    pub async fn bind_account(&self, binding: &AccountBinding) -> Result<(), DatabaseError> {
+        AccountBinding::insert(self.rbatis(), binding).await?;
+        Ok(())
+    }
+
+    /// Removes the binding of a Telegram user on a server.
+    ///
+    /// Returns whether a binding existed.
+    pub async fn unbind_account(&self, telegram_id: i64, server: &str) -> Result<bool, DatabaseError> {
+        let result = self
+            .rbatis()
+            .exec(
+                "DELETE FROM account_bindings WHERE telegram_id = ? AND server = ?",
+                vec![telegram_id.into(), server.to_string().into()],
+            )
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Returns all accounts of a Telegram user, ordered by server name.
+    pub async fn accounts_of(&self, telegram_id: i64) -> Result<Vec<AccountBinding>, DatabaseError> {
+        let mut accounts = AccountBinding::select_by_column(self.rbatis(), "telegram_id", telegram_id).await?;
+        accounts.sort_by(|a, b| a.server.cmp(&b.server));
+        Ok(accounts)
+    }
+
+    /// Returns the account of a Telegram user on a server.
+    pub async fn account_on(&self, telegram_id: i64, server: &str) -> Result<Option<AccountBinding>, DatabaseError> {
+        Ok(self
+            .accounts_of(telegram_id)
+            .await?
+            .into_iter()
+            .find(|account| account.server == server))
+    }
+
+    /// Returns the accounts with the given user name, ignoring case.
+    pub async fn accounts_named(&self, emby_name: &str) -> Result<Vec<AccountBinding>, DatabaseError> {
+        Ok(self
+            .rbatis()
+            .query_decode(
+                "SELECT * FROM account_bindings WHERE LOWER(emby_name) = LOWER(?) ORDER BY telegram_id, server",
+                vec![emby_name.to_string().into()],
+            )
+            .await?)
+    }
+
+    /// Sets when the account of a Telegram user on a server expires.
+    ///
+    /// Returns whether the account exists.
+    pub async fn set_account_expiry(
+        &self,
+        telegram_id: i64,
+        server: &str,
+        expires_at: Option<i64>,
+    ) -> Result<bool, DatabaseError> {
+        let result = self
+            .rbatis()
+            .exec(
+                "UPDATE account_bindings SET expires_at = ? WHERE telegram_id = ? AND server = ?",
+                vec![
+                    expires_at.map_or(rbs::Value::Null, Into::into),
+                    telegram_id.into(),
+                    server.to_string().into(),
+                ],
+            )
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Returns the number of bound accounts per server.
+    ///
+    /// Servers without accounts are missing from the map.
+    pub async fn account_counts(&self) -> Result<HashMap<String, u64>, DatabaseError> {
+        let counts: Vec<ServerCount> = self
+            .rbatis()
+            .query_decode(
+                "SELECT server, COUNT(*) AS users FROM account_bindings GROUP BY server",
+                vec![],
+            )
+            .await?;
+        Ok(counts
+            .into_iter()
+            .map(|count| (count.server, count.users.max(0) as u64))
+            .collect())
+    }
+}
@@ -0,0 +1,67 @@
+//! Records privileged actions.
+//!
+//! Every entry names who did what to whom, with which arguments and how it
+//! ended. Entries are only ever added.
+
+use serde::{Deserialize, Serialize};
+
+use super::connection::Database;
+use super::error::DatabaseError;
+
+/// A privileged action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the action was taken, as a Unix timestamp
+    pub created_at: i64,
+    /// The Telegram user who took the action
+    pub actor_id: i64,
+    /// What the action was applied to, e.g. a user id, if anything
+    pub target: Option<String>,
+    /// The action, usually the command name such as `grant`
+    pub action: String,
+    /// The arguments of the action
+    pub arguments: String,
+    /// How the action ended, e.g. `ok`, `denied` or the reason it failed
+    pub result: String,
+}
+
+rbatis::crud!(AuditEntry {}, "audit_log");
+
+impl AuditEntry {
+    /// Creates an entry for an action taken now.
+    pub fn new(
+        actor_id: i64,
+        target: Option<String>,
+        action: impl Into<String>,
+        arguments: impl Into<String>,
+        result: impl Into<String>,
+    ) -> Self {
+        Self {
+            created_at: chrono::Utc::now().timestamp(),
+            actor_id,
+            target,
+            action: action.into(),
+            arguments: arguments.into(),
+            result: result.into(),
+        }
+    }
+}
+
+impl Database {
+    /// Adds an entry to the audit log.
+    pub async fn record_audit(&self, entry: &AuditEntry) -> Result<(), DatabaseError> {
+        AuditEntry::insert(self.rbatis(), entry).await?;
+        Ok(())
+    }
+
+    /// Returns the latest `limit` entries, newest first.
+    pub async fn recent_audit(&self, limit: u64) -> Result<Vec<AuditEntry>, DatabaseError> {
+        Ok(self
+            .rbatis()
+            .query_decode(
+                "SELECT * FROM audit_log ORDER BY created_at DESC LIMIT ?",
+                vec![limit.into()],
+            )
+            .await?)
+    }
+}
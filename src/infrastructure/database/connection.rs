@@ -0,0 +1,133 @@
+//! Connects to the database configured in `[database]`.
+//!
+//! The driver is chosen by the URL scheme. SQLite is built in by default,
+//! MySQL and PostgreSQL are enabled with the `mysql` and `postgres` cargo
+//! features.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let database = Database::connect(&config.database).await?;
+//! database.rbatis().exec("DELETE FROM sessions", vec![]).await?;
+//! ```
+
+use std::path::Path;
+
+use rbatis::RBatis;
+
+use crate::info_log;
+use crate::infrastructure::config::DatabaseConfig;
+use super::error::DatabaseError;
+
+/// Domain identifier for database logs
+const DATABASE_LOGGER_DOMAIN: &str = "[DATABASE]";
+
+/// A pool of connections to the configured database.
+///
+/// Cloning is cheap, clones share the pool.
+#[derive(Debug, Clone)]
+pub struct Database {
+    rb: RBatis,
+}
+
+impl Database {
+    /// Connects to the database at `config.url`.
+    ///
+    /// Missing directories of a SQLite file are created first, and pending
+    /// migrations are applied, which also reports unreachable databases
+    /// right away.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the driver is not compiled in, the SQLite
+    /// directory cannot be created, the connection fails or a migration
+    /// fails.
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self, DatabaseError> {
+        let url = config.url.as_str();
+        let scheme = url.split(':').next().unwrap_or_default();
+        let rb = RBatis::new();
+
+        match scheme {
+            "sqlite" => {
+                create_sqlite_directory(url)?;
+                init_sqlite(&rb, url)?;
+            }
+            "mysql" => init_mysql(&rb, url)?,
+            "postgres" | "postgresql" => init_postgres(&rb, url)?,
+            _ => {
+                return Err(DatabaseError::UnsupportedDriver {
+                    scheme: scheme.to_owned(),
+                    reason: String::from("expected sqlite, mysql or postgres"),
+                });
+            }
+        }
+
+        let database = Self { rb };
+        database.migrate().await?;
+        info_log!(DATABASE_LOGGER_DOMAIN, "✅ Connected to {} database", scheme);
+        Ok(database)
+    }
+
+    /// Returns the rbatis instance for running queries.
+    pub fn rbatis(&self) -> &RBatis {
+        &self.rb
+    }
+}
+
+/// Creates the directory holding a SQLite database file.
+fn create_sqlite_directory(url: &str) -> Result<(), DatabaseError> {
+    let location = url.trim_start_matches("sqlite://").trim_start_matches("sqlite:");
+    let file = location.split('?').next().unwrap_or_default();
+    if file.is_empty() || file == ":memory:" {
+        return Ok(());
+    }
+
+    match Path::new(file).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => {
+            std::fs::create_dir_all(parent).map_err(|error| DatabaseError::Io {
+                path: parent.to_path_buf(),
+                error,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns the error for a driver whose cargo feature is disabled.
+#[allow(dead_code)]
+fn feature_disabled(scheme: &str, feature: &str) -> DatabaseError {
+    DatabaseError::UnsupportedDriver {
+        scheme: scheme.to_owned(),
+        reason: format!("rebuild with the `{}` feature", feature),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn init_sqlite(rb: &RBatis, url: &str) -> Result<(), DatabaseError> {
+    Ok(rb.init(rbdc_sqlite::SqliteDriver {}, url)?)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn init_sqlite(_rb: &RBatis, _url: &str) -> Result<(), DatabaseError> {
+    Err(feature_disabled("sqlite", "sqlite"))
+}
+
+#[cfg(feature = "mysql")]
+fn init_mysql(rb: &RBatis, url: &str) -> Result<(), DatabaseError> {
+    Ok(rb.init(rbdc_mysql::driver::MysqlDriver {}, url)?)
+}
+
+#[cfg(not(feature = "mysql"))]
+fn init_mysql(_rb: &RBatis, _url: &str) -> Result<(), DatabaseError> {
+    Err(feature_disabled("mysql", "mysql"))
+}
+
+#[cfg(feature = "postgres")]
+fn init_postgres(rb: &RBatis, url: &str) -> Result<(), DatabaseError> {
+    Ok(rb.init(rbdc_pg::driver::PgDriver {}, url)?)
+}
+
+#[cfg(not(feature = "postgres"))]
+fn init_postgres(_rb: &RBatis, _url: &str) -> Result<(), DatabaseError> {
+    Err(feature_disabled("postgres", "postgres"))
+}
@@ -0,0 +1,137 @@
+//! Stores the conversations the bot is having with users.
+//!
+//! A user has at most one conversation per chat. Its state is stored as
+//! JSON, whose shape is up to the flow that started the conversation, so it
+//! survives restarts of the bot.
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::connection::Database;
+use super::error::DatabaseError;
+
+/// A stored conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationRecord {
+    /// The chat the conversation takes place in
+    pub chat_id: i64,
+    /// The Telegram user the bot talks to
+    pub user_id: i64,
+    /// The flow that started the conversation, e.g. `bind`
+    pub flow: String,
+    /// The state of the flow as JSON
+    #[serde(deserialize_with = "json_text")]
+    pub state: String,
+    /// When the conversation expires, as a Unix timestamp
+    pub expires_at: i64,
+    /// When the state was last saved, as a Unix timestamp
+    pub updated_at: i64,
+    /// When the user was told the conversation timed out, as a Unix timestamp
+    pub expired_at: Option<i64>,
+}
+
+rbatis::crud!(ConversationRecord {}, "conversations");
+
+/// Reads a JSON column as text.
+///
+/// The SQLite driver hands out text that looks like a JSON object or array
+/// as a map, which is turned back into its JSON text here.
+fn json_text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(text) => Ok(text),
+        value => Ok(value.to_string()),
+    }
+}
+
+impl Database {
+    /// Returns the conversation of a user in a chat, expired or not.
+    pub async fn conversation(&self, chat_id: i64, user_id: i64) -> Result<Option<ConversationRecord>, DatabaseError> {
+        let records: Vec<ConversationRecord> = self
+            .rbatis()
+            .query_decode(
+                "SELECT * FROM conversations WHERE chat_id = ? AND user_id = ?",
+                vec![chat_id.into(), user_id.into()],
+            )
+            .await?;
+        Ok(records.into_iter().next())
+    }
+
+    /// Stores a conversation, replacing the user's previous one in the chat.
+    pub async fn save_conversation(&self, record: &ConversationRecord) -> Result<(), DatabaseError> {
+        let result = self
+            .rbatis()
+            .exec(
+                "UPDATE conversations SET flow = ?, state = ?, expires_at = ?, updated_at = ?, expired_at = ? \
+                 WHERE chat_id = ? AND user_id = ?",
+                vec![
+                    record.flow.clone().into(),
+                    record.state.clone().into(),
+                    record.expires_at.into(),
+                    record.updated_at.into(),
+                    record.expired_at.map_or(rbs::Value::Null, Into::into),
+                    record.chat_id.into(),
+                    record.user_id.into(),
+                ],
+            )
+            .await?;
+        if result.rows_affected == 0 {
+            ConversationRecord::insert(self.rbatis(), record).await?;
+        }
+        Ok(())
+    }
+
+    /// Removes the conversation of a user in a chat.
+    ///
+    /// Returns whether a conversation existed.
+    pub async fn delete_conversation(&self, chat_id: i64, user_id: i64) -> Result<bool, DatabaseError> {
+        let result = self
+            .rbatis()
+            .exec(
+                "DELETE FROM conversations WHERE chat_id = ? AND user_id = ?",
+                vec![chat_id.into(), user_id.into()],
+            )
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Marks the conversations that expired by `now` and returns those that
+    /// were not marked before.
+    ///
+    /// Marked conversations are kept until `delete_expired_conversations`
+    /// removes them. A conversation renewed while this runs is left alone.
+    pub async fn mark_expired_conversations(&self, now: i64) -> Result<Vec<ConversationRecord>, DatabaseError> {
+        let expired: Vec<ConversationRecord> = self
+            .rbatis()
+            .query_decode(
+                "SELECT * FROM conversations WHERE expires_at <= ? AND expired_at IS NULL",
+                vec![now.into()],
+            )
+            .await?;
+
+        let mut marked = Vec::with_capacity(expired.len());
+        for record in expired {
+            let result = self
+                .rbatis()
+                .exec(
+                    "UPDATE conversations SET expired_at = ? \
+                     WHERE chat_id = ? AND user_id = ? AND expires_at <= ? AND expired_at IS NULL",
+                    vec![now.into(), record.chat_id.into(), record.user_id.into(), now.into()],
+                )
+                .await?;
+            if result.rows_affected > 0 {
+                marked.push(ConversationRecord { expired_at: Some(now), ..record });
+            }
+        }
+        Ok(marked)
+    }
+
+    /// Removes the conversations marked as expired by `before`.
+    ///
+    /// Returns how many were removed.
+    pub async fn delete_expired_conversations(&self, before: i64) -> Result<u64, DatabaseError> {
+        let result = self
+            .rbatis()
+            .exec("DELETE FROM conversations WHERE expired_at <= ?", vec![before.into()])
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
@@ -0,0 +1,52 @@
+//! Stores messages the bot has to delete later, such as sent passwords.
+//!
+//! Keeping them in the database instead of a timer means a restart of the
+//! bot does not leave passwords visible in a chat.
+
+use serde::{Deserialize, Serialize};
+
+use super::connection::Database;
+use super::error::DatabaseError;
+
+/// A message waiting to be deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingDeletion {
+    /// The chat the message was sent to
+    pub chat_id: i64,
+    /// The id of the message in that chat
+    pub message_id: i64,
+    /// When the message is deleted, as a Unix timestamp
+    pub delete_at: i64,
+}
+
+rbatis::crud!(PendingDeletion {}, "pending_deletions");
+
+impl Database {
+    /// Remembers a message to delete at `delete_at`.
+    pub async fn schedule_deletion(&self, deletion: &PendingDeletion) -> Result<(), DatabaseError> {
+        PendingDeletion::insert(self.rbatis(), deletion).await?;
+        Ok(())
+    }
+
+    /// Returns the messages due for deletion by `now`, oldest first.
+    pub async fn due_deletions(&self, now: i64) -> Result<Vec<PendingDeletion>, DatabaseError> {
+        Ok(self
+            .rbatis()
+            .query_decode(
+                "SELECT * FROM pending_deletions WHERE delete_at <= ? ORDER BY delete_at",
+                vec![now.into()],
+            )
+            .await?)
+    }
+
+    /// Forgets a message, e.g. once it has been deleted.
+    pub async fn remove_deletion(&self, chat_id: i64, message_id: i64) -> Result<(), DatabaseError> {
+        self.rbatis()
+            .exec(
+                "DELETE FROM pending_deletions WHERE chat_id = ? AND message_id = ?",
+                vec![chat_id.into(), message_id.into()],
+            )
+            .await?;
+        Ok(())
+    }
+}
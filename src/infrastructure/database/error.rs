@@ -0,0 +1,53 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// Represents an error while connecting to or using the database.
+#[derive(Debug)]
+pub enum DatabaseError {
+    /// The URL names a database this build does not support
+    UnsupportedDriver {
+        /// The URL scheme, e.g. `mysql`
+        scheme: String,
+        /// Why the driver is unavailable
+        reason: String,
+    },
+    /// The directory of the SQLite file could not be created
+    Io {
+        /// The directory that could not be created
+        path: PathBuf,
+        /// The underlying error
+        error: std::io::Error,
+    },
+    /// The database rejected the connection or a statement
+    Rbatis(rbatis::Error),
+}
+
+impl fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatabaseError::UnsupportedDriver { scheme, reason } => {
+                write!(f, "unsupported database `{}`: {}", scheme, reason)
+            }
+            DatabaseError::Io { path, error } => {
+                write!(f, "failed to create {}: {}", path.display(), error)
+            }
+            DatabaseError::Rbatis(error) => write!(f, "database error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for DatabaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DatabaseError::UnsupportedDriver { .. } => None,
+            DatabaseError::Io { error, .. } => Some(error),
+            DatabaseError::Rbatis(error) => Some(error),
+        }
+    }
+}
+
+impl From<rbatis::Error> for DatabaseError {
+    fn from(error: rbatis::Error) -> Self {
+        DatabaseError::Rbatis(error)
+    }
+}
@@ -0,0 +1,102 @@
+//! Brings the database schema up to date.
+//!
+//! Migrations are plain SQL files in `migrations/`, named like
+//! `V<version>__<name>.sql` and embedded into the binary. Applied versions
+//! are recorded in `schema_migrations`, so each migration runs once. The SQL
+//! sticks to the subset shared by SQLite, MySQL and PostgreSQL.
+
+use crate::info_log;
+use super::connection::Database;
+use super::error::DatabaseError;
+
+/// Domain identifier for migration logs
+const MIGRATION_LOGGER_DOMAIN: &str = "[DATABASE]";
+
+/// A schema change applied once.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Increasing version, applied in order
+    pub version: i64,
+    /// A short description
+    pub name: &'static str,
+    /// One or more statements separated by `;`
+    pub sql: &'static str,
+}
+
+/// All migrations, ordered by version.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "account_bindings",
+        sql: include_str!("migrations/V1__account_bindings.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "conversations",
+        sql: include_str!("migrations/V2__conversations.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "roles",
+        sql: include_str!("migrations/V3__roles.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "user_profiles",
+        sql: include_str!("migrations/V4__user_profiles.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "pending_deletions",
+        sql: include_str!("migrations/V5__pending_deletions.sql"),
+    },
+];
+
+/// Records applied migrations.
+const CREATE_MIGRATIONS_TABLE: &str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    version BIGINT NOT NULL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    applied_at BIGINT NOT NULL
+)";
+
+impl Database {
+    /// Applies all migrations that have not been applied yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a statement fails. Migrations applied before the
+    /// failing one stay applied.
+    pub async fn migrate(&self) -> Result<(), DatabaseError> {
+        let rb = self.rbatis();
+        rb.exec(CREATE_MIGRATIONS_TABLE, vec![]).await?;
+        let applied: Vec<i64> = rb
+            .query_decode::<Vec<AppliedMigration>>("SELECT version FROM schema_migrations", vec![])
+            .await?
+            .into_iter()
+            .map(|migration| migration.version)
+            .collect();
+
+        for migration in MIGRATIONS.iter().filter(|migration| !applied.contains(&migration.version)) {
+            for statement in migration.sql.split(';').map(str::trim).filter(|statement| !statement.is_empty()) {
+                rb.exec(statement, vec![]).await?;
+            }
+            rb.exec(
+                "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?, ?, ?)",
+                vec![
+                    migration.version.into(),
+                    migration.name.to_string().into(),
+                    chrono::Utc::now().timestamp().into(),
+                ],
+            )
+                .await?;
+            info_log!(MIGRATION_LOGGER_DOMAIN, "Applied migration V{} {}", migration.version, migration.name);
+        }
+        Ok(())
+    }
+}
+
+/// A row of `schema_migrations`.
+#[derive(serde::Deserialize)]
+struct AppliedMigration {
+    version: i64,
+}
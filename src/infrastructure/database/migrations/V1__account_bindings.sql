@@ -0,0 +1,9 @@
+CREATE TABLE account_bindings (
+    telegram_id BIGINT NOT NULL,
+    server VARCHAR(64) NOT NULL,
+    emby_user_id VARCHAR(64) NOT NULL,
+    emby_name VARCHAR(255) NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (telegram_id, server)
+);
+CREATE INDEX idx_account_bindings_server ON account_bindings (server);
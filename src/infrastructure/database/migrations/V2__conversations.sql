@@ -0,0 +1,11 @@
+CREATE TABLE conversations (
+    chat_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    flow VARCHAR(64) NOT NULL,
+    state TEXT NOT NULL,
+    expires_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    expired_at BIGINT,
+    PRIMARY KEY (chat_id, user_id)
+);
+CREATE INDEX idx_conversations_expires_at ON conversations (expires_at);
@@ -0,0 +1,16 @@
+CREATE TABLE user_roles (
+    telegram_id BIGINT NOT NULL PRIMARY KEY,
+    role VARCHAR(16) NOT NULL,
+    granted_by BIGINT,
+    granted_at BIGINT NOT NULL
+);
+CREATE TABLE audit_log (
+    created_at BIGINT NOT NULL,
+    actor_id BIGINT NOT NULL,
+    target VARCHAR(255),
+    action VARCHAR(64) NOT NULL,
+    arguments TEXT NOT NULL,
+    result TEXT NOT NULL
+);
+CREATE INDEX idx_audit_log_created_at ON audit_log (created_at);
+CREATE INDEX idx_audit_log_actor_id ON audit_log (actor_id);
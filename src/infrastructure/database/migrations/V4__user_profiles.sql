@@ -0,0 +1,10 @@
+ALTER TABLE account_bindings ADD COLUMN expires_at BIGINT;
+CREATE TABLE telegram_users (
+    telegram_id BIGINT NOT NULL PRIMARY KEY,
+    username VARCHAR(64),
+    first_name VARCHAR(255) NOT NULL,
+    points BIGINT NOT NULL DEFAULT 0,
+    strikes BIGINT NOT NULL DEFAULT 0,
+    last_seen_at BIGINT NOT NULL
+);
+CREATE INDEX idx_telegram_users_username ON telegram_users (username);
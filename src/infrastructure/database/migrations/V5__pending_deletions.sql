@@ -0,0 +1,7 @@
+CREATE TABLE pending_deletions (
+    chat_id BIGINT NOT NULL,
+    message_id BIGINT NOT NULL,
+    delete_at BIGINT NOT NULL,
+    PRIMARY KEY (chat_id, message_id)
+);
+CREATE INDEX idx_pending_deletions_delete_at ON pending_deletions (delete_at);
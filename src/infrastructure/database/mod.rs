@@ -0,0 +1,19 @@
+pub mod connection;
+pub mod error;
+pub mod migration;
+pub mod accounts;
+pub mod conversations;
+pub mod roles;
+pub mod audit;
+pub mod telegram_users;
+pub mod deletions;
+
+pub use connection::Database;
+pub use error::DatabaseError;
+pub use migration::{Migration, MIGRATIONS};
+pub use accounts::AccountBinding;
+pub use conversations::ConversationRecord;
+pub use roles::RoleAssignment;
+pub use audit::AuditEntry;
+pub use telegram_users::TelegramUser;
+pub use deletions::PendingDeletion;
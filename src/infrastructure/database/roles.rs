@@ -0,0 +1,82 @@
+//! Stores the roles of Telegram users.
+//!
+//! Users without a stored role are plain users. The role is stored by name,
+//! see `services::permissions::Role`.
+
+use serde::{Deserialize, Serialize};
+
+use super::connection::Database;
+use super::error::DatabaseError;
+
+/// The role held by a Telegram user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoleAssignment {
+    /// The Telegram user id
+    pub telegram_id: i64,
+    /// The name of the role, e.g. `admin`
+    pub role: String,
+    /// The user who granted the role, `None` if it comes from the configuration
+    pub granted_by: Option<i64>,
+    /// When the role was granted, as a Unix timestamp
+    pub granted_at: i64,
+}
+
+rbatis::crud!(RoleAssignment {}, "user_roles");
+
+impl RoleAssignment {
+    /// Creates an assignment granted now.
+    pub fn new(telegram_id: i64, role: impl Into<String>, granted_by: Option<i64>) -> Self {
+        Self {
+            telegram_id,
+            role: role.into(),
+            granted_by,
+            granted_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+impl Database {
+    /// Returns the stored role of a Telegram user.
+    pub async fn role_assignment(&self, telegram_id: i64) -> Result<Option<RoleAssignment>, DatabaseError> {
+        let assignments = RoleAssignment::select_by_column(self.rbatis(), "telegram_id", telegram_id).await?;
+        Ok(assignments.into_iter().next())
+    }
+
+    /// Returns all stored roles, ordered by user id.
+    pub async fn role_assignments(&self) -> Result<Vec<RoleAssignment>, DatabaseError> {
+        let mut assignments = RoleAssignment::select_all(self.rbatis()).await?;
+        assignments.sort_by_key(|assignment| assignment.telegram_id);
+        Ok(assignments)
+    }
+
+    /// Stores a role, replacing the user's previous one.
+    pub async fn assign_role(&self, assignment: &RoleAssignment) -> Result<(), DatabaseError> {
+        let result = self
+            .rbatis()
+            .exec(
+                "UPDATE user_roles SET role = ?, granted_by = ?, granted_at = ? WHERE telegram_id = ?",
+                vec![
+                    assignment.role.clone().into(),
+                    assignment.granted_by.map_or(rbs::Value::Null, Into::into),
+                    assignment.granted_at.into(),
+                    assignment.telegram_id.into(),
+                ],
+            )
+            .await?;
+        if result.rows_affected == 0 {
+            RoleAssignment::insert(self.rbatis(), assignment).await?;
+        }
+        Ok(())
+    }
+
+    /// Removes the stored role of a user, making them a plain user.
+    ///
+    /// Returns whether a role was stored.
+    pub async fn remove_role(&self, telegram_id: i64) -> Result<bool, DatabaseError> {
+        let result = self
+            .rbatis()
+            .exec("DELETE FROM user_roles WHERE telegram_id = ?", vec![telegram_id.into()])
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+}
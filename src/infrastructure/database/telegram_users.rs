@@ -0,0 +1,83 @@
+//! Stores what the bot knows about the Telegram users it talks to.
+//!
+//! A user is recorded with their first message and updated with every
+//! later one, so admins can find them by `@username` and see when they were
+//! last active.
+
+use serde::{Deserialize, Serialize};
+
+use super::connection::Database;
+use super::error::DatabaseError;
+
+/// A Telegram user the bot has seen.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelegramUser {
+    /// The Telegram user id
+    pub telegram_id: i64,
+    /// The username without the leading `@`, if the user has one
+    pub username: Option<String>,
+    /// The user's first name
+    pub first_name: String,
+    /// Points earned by the user
+    pub points: i64,
+    /// Strikes given to the user for breaking the rules
+    pub strikes: i64,
+    /// When the user last talked to the bot, as a Unix timestamp
+    pub last_seen_at: i64,
+}
+
+rbatis::crud!(TelegramUser {}, "telegram_users");
+
+impl TelegramUser {
+    /// Creates a user seen now, without points or strikes.
+    pub fn new(telegram_id: i64, username: Option<String>, first_name: impl Into<String>) -> Self {
+        Self {
+            telegram_id,
+            username,
+            first_name: first_name.into(),
+            points: 0,
+            strikes: 0,
+            last_seen_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+impl Database {
+    /// Records that a user talked to the bot, keeping their points and strikes.
+    pub async fn touch_telegram_user(&self, user: &TelegramUser) -> Result<(), DatabaseError> {
+        let result = self
+            .rbatis()
+            .exec(
+                "UPDATE telegram_users SET username = ?, first_name = ?, last_seen_at = ? WHERE telegram_id = ?",
+                vec![
+                    user.username.clone().map_or(rbs::Value::Null, Into::into),
+                    user.first_name.clone().into(),
+                    user.last_seen_at.into(),
+                    user.telegram_id.into(),
+                ],
+            )
+            .await?;
+        if result.rows_affected == 0 {
+            TelegramUser::insert(self.rbatis(), user).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the user with the given id.
+    pub async fn telegram_user(&self, telegram_id: i64) -> Result<Option<TelegramUser>, DatabaseError> {
+        let users = TelegramUser::select_by_column(self.rbatis(), "telegram_id", telegram_id).await?;
+        Ok(users.into_iter().next())
+    }
+
+    /// Returns the user with the given username, ignoring case and a leading `@`.
+    pub async fn telegram_user_named(&self, username: &str) -> Result<Option<TelegramUser>, DatabaseError> {
+        let users: Vec<TelegramUser> = self
+            .rbatis()
+            .query_decode(
+                "SELECT * FROM telegram_users WHERE LOWER(username) = LOWER(?) ORDER BY last_seen_at DESC",
+                vec![username.trim_start_matches('@').to_string().into()],
+            )
+            .await?;
+        Ok(users.into_iter().next())
+    }
+}
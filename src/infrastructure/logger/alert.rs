@@ -0,0 +1,289 @@
+//! Forwards warnings and errors to an admin chat.
+//!
+//! `AlertLayer` is a tracing layer that copies WARN and ERROR events into a
+//! bounded channel without blocking the caller. `AlertForwarder` drains the
+//! channel on a background task: it waits a short batch window so a burst of
+//! events ends up in a single message, merges identical events into one line
+//! with a counter and keeps a minimum interval between two messages.
+//!
+//! Where the messages go is decided by an `AlertSink`, e.g. a Telegram chat.
+//! Events logged while a sink is sending are never forwarded, so a failing
+//! sink cannot trigger alerts about itself.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let (alert_layer, alerts) = AlertLayer::new(LogLevel::Warn);
+//! let _guard = LoggerBuilder::default().with_alerts(alert_layer).init();
+//!
+//! tokio::spawn(AlertForwarder::new(alerts, sink).run());
+//! ```
+
+use std::error::Error;
+use std::fmt::Write;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+use crate::warn_log;
+use super::format::DomainVisitor;
+use super::LogLevel;
+
+/// Domain identifier for alert forwarding logs
+const ALERT_LOGGER_DOMAIN: &str = "[ALERT]";
+
+/// The number of events buffered before new events are dropped
+const ALERT_CHANNEL_CAPACITY: usize = 1024;
+
+/// The number of distinct events listed in one message
+const MAX_ALERTS_PER_MESSAGE: usize = 20;
+
+/// The maximum message length accepted by the Telegram Bot API
+const MAX_MESSAGE_LENGTH: usize = 4096;
+
+tokio::task_local! {
+    /// Set while a sink is sending, so its own logs are not forwarded.
+    static SUPPRESS_ALERTS: ();
+}
+
+/// A single forwarded log event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    /// The level of the event
+    pub level: Level,
+    /// The log domain, e.g. `[EMBY]`
+    pub domain: String,
+    /// The message followed by the remaining fields
+    pub message: String,
+    /// The source location as `file:line`, if known
+    pub location: Option<String>,
+}
+
+impl Alert {
+    /// Creates an alert from a tracing event.
+    fn from_event(event: &Event<'_>) -> Self {
+        let mut visitor = DomainVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        let domain = visitor.bracketed_domain()
+            .unwrap_or_else(|| format!("[{}]", metadata.target()));
+        let message = visitor.message
+            .into_iter()
+            .chain(visitor.fields)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let location = metadata
+            .file()
+            .map(|file| match metadata.line() {
+                Some(line) => format!("{}:{}", file, line),
+                None => file.to_owned(),
+            });
+
+        Self {
+            level: *metadata.level(),
+            domain,
+            message,
+            location,
+        }
+    }
+}
+
+/// A tracing layer sending WARN and ERROR events to an `AlertForwarder`.
+#[derive(Debug, Clone)]
+pub struct AlertLayer {
+    /// The channel to the forwarder
+    sender: mpsc::Sender<Alert>,
+    /// The least severe level forwarded
+    min_level: Level,
+}
+
+impl AlertLayer {
+    /// Creates a layer forwarding events at `min_level` or more severe.
+    ///
+    /// Returns the layer and the receiver to pass to `AlertForwarder::new`.
+    /// Levels below `Warn` are raised to `Warn`, alerts are not meant for
+    /// regular progress logs.
+    pub fn new(min_level: LogLevel) -> (Self, mpsc::Receiver<Alert>) {
+        let (sender, receiver) = mpsc::channel(ALERT_CHANNEL_CAPACITY);
+        let min_level = match min_level {
+            LogLevel::Error => Level::ERROR,
+            _ => Level::WARN,
+        };
+        (Self { sender, min_level }, receiver)
+    }
+}
+
+impl<S: Subscriber> Layer<S> for AlertLayer {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        // More severe levels compare as smaller.
+        if *event.metadata().level() > self.min_level {
+            return;
+        }
+        if SUPPRESS_ALERTS.try_with(|_| ()).is_ok() {
+            return;
+        }
+
+        // Dropping alerts when the forwarder falls behind is preferable to
+        // blocking the code that logs.
+        let _ = self.sender.try_send(Alert::from_event(event));
+    }
+}
+
+/// Delivers alert messages, e.g. to a Telegram chat.
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    /// Sends one message summarizing a batch of alerts.
+    async fn send_alert(&self, text: String) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+/// Identical alerts merged into one line.
+#[derive(Debug)]
+struct AlertEntry {
+    alert: Alert,
+    count: usize,
+}
+
+/// Collects alerts until they are sent as one message.
+#[derive(Debug, Default)]
+pub struct AlertBatch {
+    /// Distinct alerts in the order they first occurred
+    entries: Vec<AlertEntry>,
+    /// The number of alerts that did not fit into the message
+    dropped: usize,
+}
+
+impl AlertBatch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an alert, merging it with an identical earlier alert.
+    pub fn push(&mut self, alert: Alert) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.alert == alert) {
+            entry.count += 1;
+        } else if self.entries.len() < MAX_ALERTS_PER_MESSAGE {
+            self.entries.push(AlertEntry { alert, count: 1 });
+        } else {
+            self.dropped += 1;
+        }
+    }
+
+    /// Returns whether the batch contains no alerts.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Renders the batch as a message and empties it.
+    pub fn take_message(&mut self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let total: usize = self.entries.iter().map(|entry| entry.count).sum::<usize>() + self.dropped;
+        let mut text = format!("⚠️ {} log alert{}\n", total, if total == 1 { "" } else { "s" });
+        for entry in self.entries.drain(..) {
+            let alert = entry.alert;
+            let _ = write!(text, "\n{} {} {}", alert.level, alert.domain, alert.message);
+            if let Some(location) = alert.location {
+                let _ = write!(text, " ({})", location);
+            }
+            if entry.count > 1 {
+                let _ = write!(text, " ×{}", entry.count);
+            }
+        }
+        if self.dropped > 0 {
+            let _ = write!(text, "\n… and {} more", self.dropped);
+            self.dropped = 0;
+        }
+
+        if text.chars().count() > MAX_MESSAGE_LENGTH {
+            text = text.chars().take(MAX_MESSAGE_LENGTH - 1).collect();
+            text.push('…');
+        }
+        Some(text)
+    }
+}
+
+/// Batches alerts from an `AlertLayer` and hands them to a sink.
+pub struct AlertForwarder<K: AlertSink> {
+    /// The receiving end of the layer's channel
+    receiver: mpsc::Receiver<Alert>,
+    /// Where messages are delivered
+    sink: K,
+    /// How long to wait for more alerts after the first one
+    batch_window: Duration,
+    /// The minimum time between two messages
+    min_interval: Duration,
+}
+
+impl<K: AlertSink> AlertForwarder<K> {
+    /// Creates a forwarder with a 10 second batch window and at most one
+    /// message per minute.
+    pub fn new(receiver: mpsc::Receiver<Alert>, sink: K) -> Self {
+        Self {
+            receiver,
+            sink,
+            batch_window: Duration::from_secs(10),
+            min_interval: Duration::from_secs(60),
+        }
+    }
+
+    /// Sets how long to wait for more alerts after the first one.
+    pub fn with_batch_window(mut self, batch_window: Duration) -> Self {
+        self.batch_window = batch_window;
+        self
+    }
+
+    /// Sets the minimum time between two messages.
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Forwards alerts until every `AlertLayer` sending to it is dropped.
+    pub async fn run(mut self) {
+        let mut batch = AlertBatch::new();
+        let mut last_sent: Option<Instant> = None;
+
+        while let Some(alert) = self.receiver.recv().await {
+            batch.push(alert);
+
+            let mut deadline = Instant::now() + self.batch_window;
+            if let Some(last_sent) = last_sent {
+                deadline = deadline.max(last_sent + self.min_interval);
+            }
+            let mut closed = false;
+            loop {
+                tokio::select! {
+                    alert = self.receiver.recv() => match alert {
+                        Some(alert) => batch.push(alert),
+                        None => {
+                            closed = true;
+                            break;
+                        }
+                    },
+                    _ = tokio::time::sleep_until(deadline) => break,
+                }
+            }
+
+            if let Some(text) = batch.take_message() {
+                last_sent = Some(Instant::now());
+                if let Err(error) = SUPPRESS_ALERTS.scope((), self.sink.send_alert(text)).await {
+                    SUPPRESS_ALERTS.sync_scope((), || {
+                        warn_log!(ALERT_LOGGER_DOMAIN, "Failed to send log alert: {}", error);
+                    });
+                }
+            }
+            if closed {
+                break;
+            }
+        }
+    }
+}
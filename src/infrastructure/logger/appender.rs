@@ -0,0 +1,453 @@
+//! Provides a file writer that rotates by time and size and cleans up old files.
+//!
+//! `tracing-appender` only rotates on fixed time intervals and keeps every
+//! file forever. `RollingFileWriter` additionally starts a new file once the
+//! active one grows beyond a size limit, compresses rotated files with gzip and
+//! deletes rotated files exceeding the configured count or age.
+//!
+//! File names follow the `tracing-appender` scheme: `<prefix>.<date>` for
+//! time-based rotation and `<prefix>` otherwise. Files rotated because of
+//! their size get an increasing index, e.g. `app.2025-03-01.1`, and a `.gz`
+//! extension once compressed. Indices keep growing even after older files
+//! were deleted, so a rotated file name is never reused.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+use chrono::Utc;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use tracing_subscriber::fmt::MakeWriter;
+
+use super::LogRotation;
+
+/// File name used when neither a prefix nor a date is available.
+const FALLBACK_FILE_NAME: &str = "log";
+
+/// Extension appended to compressed files.
+const GZIP_EXTENSION: &str = "gz";
+
+/// Describes when log files are rotated and how long rotated files are kept.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use pilipili_bot::infrastructure::logger::{LogRotation, RetentionPolicy};
+///
+/// // Daily files, split at 10 MiB, compressed, at most 14 rotated files
+/// // which are deleted after 30 days.
+/// let policy = RetentionPolicy::new(LogRotation::Daily)
+///     .with_max_file_size(10 * 1024 * 1024)
+///     .with_max_files(14)
+///     .with_max_age(Duration::from_secs(30 * 24 * 60 * 60))
+///     .with_compression(true);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// The time interval starting a new file
+    rotation: LogRotation,
+    /// The size in bytes after which a new file is started
+    max_file_size: Option<u64>,
+    /// The maximum number of rotated files kept, not counting the active file
+    max_files: Option<usize>,
+    /// The maximum age of rotated files
+    max_age: Option<Duration>,
+    /// Whether rotated files are compressed with gzip
+    compress: bool,
+}
+
+impl RetentionPolicy {
+    /// Creates a policy rotating on the given interval and keeping every file.
+    pub fn new(rotation: LogRotation) -> Self {
+        Self {
+            rotation,
+            max_file_size: None,
+            max_files: None,
+            max_age: None,
+            compress: false,
+        }
+    }
+
+    /// Starts a new file once the active file would exceed `bytes`.
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Keeps at most `count` rotated files, deleting the oldest ones.
+    pub fn with_max_files(mut self, count: usize) -> Self {
+        self.max_files = Some(count);
+        self
+    }
+
+    /// Deletes rotated files last modified longer than `age` ago.
+    pub fn with_max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Enables or disables gzip compression of rotated files.
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Returns the time interval starting a new file.
+    pub fn rotation(&self) -> LogRotation {
+        self.rotation
+    }
+
+    /// Returns whether rotated files have to be compressed or deleted.
+    fn needs_maintenance(&self) -> bool {
+        self.compress || self.max_files.is_some() || self.max_age.is_some()
+    }
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self::new(LogRotation::Daily)
+    }
+}
+
+/// The file currently written to.
+#[derive(Debug)]
+struct ActiveFile {
+    /// The open file handle
+    file: File,
+    /// The path of the file
+    path: PathBuf,
+    /// The date suffix of the time period the file belongs to
+    period: Option<String>,
+    /// The number of bytes in the file
+    size: u64,
+    /// The highest index used for a file rotated because of its size
+    last_index: u64,
+}
+
+/// Shared state of a writer, used by background maintenance.
+#[derive(Debug)]
+struct Directory {
+    /// The directory containing the log files
+    path: PathBuf,
+    /// The prefix of the log file names
+    prefix: String,
+    /// The rotation and retention settings
+    policy: RetentionPolicy,
+    /// Serializes compression and cleanup runs
+    maintenance: Mutex<()>,
+}
+
+/// A log file writer rotating by time and size.
+///
+/// Compression and cleanup of rotated files run on a background thread, so
+/// logging is not blocked while a large file is compressed. Existing files
+/// are cleaned up when the writer is created as well.
+///
+/// Maintenance picks the files to process while holding the lock of the
+/// active file, so it never touches the file being written to, even when a
+/// rotation happens while it runs.
+#[derive(Debug)]
+pub struct RollingFileWriter {
+    /// The directory and settings shared with maintenance threads
+    directory: Arc<Directory>,
+    /// The file currently written to, shared with maintenance threads
+    active: Arc<Mutex<ActiveFile>>,
+}
+
+impl RollingFileWriter {
+    /// Creates a writer appending to the current log file in `directory`.
+    ///
+    /// The directory is created if it does not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or the log file cannot be created.
+    pub fn new(
+        directory: impl AsRef<Path>,
+        file_prefix: &str,
+        policy: RetentionPolicy,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(directory.as_ref())?;
+
+        let directory = Arc::new(Directory {
+            path: directory.as_ref().to_path_buf(),
+            prefix: file_prefix.to_owned(),
+            policy,
+            maintenance: Mutex::new(()),
+        });
+        let active = directory.open(directory.current_period())?;
+
+        let writer = Self {
+            directory,
+            active: Arc::new(Mutex::new(active)),
+        };
+        writer.spawn_maintenance();
+        Ok(writer)
+    }
+
+    /// Returns the path of the file currently written to.
+    pub fn current_path(&self) -> PathBuf {
+        self.lock_active().path.clone()
+    }
+
+    /// Compresses and deletes rotated files according to the policy.
+    ///
+    /// This runs automatically after every rotation; calling it directly is
+    /// mostly useful in tests.
+    pub fn run_maintenance(&self) -> io::Result<()> {
+        self.directory.maintain(&self.active)
+    }
+
+    /// Writes `buf`, rotating the active file first if necessary.
+    fn write_rotating(&self, buf: &[u8]) -> io::Result<usize> {
+        let mut active = self.lock_active();
+
+        let period = self.directory.current_period();
+        let size_exceeded = self.directory.policy.max_file_size.is_some_and(|limit| {
+            active.size > 0 && active.size + buf.len() as u64 > limit
+        });
+
+        if period != active.period {
+            *active = self.directory.open(period)?;
+            drop(active);
+            self.spawn_maintenance();
+            active = self.lock_active();
+        } else if size_exceeded {
+            active.file.flush()?;
+            let index = active.last_index + 1;
+            fs::rename(&active.path, rotated_path(&active.path, index))?;
+            *active = self.directory.open(period)?;
+            active.last_index = active.last_index.max(index);
+            drop(active);
+            self.spawn_maintenance();
+            active = self.lock_active();
+        }
+
+        active.file.write_all(buf)?;
+        active.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    /// Locks the active file, recovering from a poisoned lock.
+    fn lock_active(&self) -> MutexGuard<'_, ActiveFile> {
+        lock(&self.active)
+    }
+
+    /// Runs compression and cleanup on a background thread.
+    fn spawn_maintenance(&self) {
+        if !self.directory.policy.needs_maintenance() {
+            return;
+        }
+
+        let directory = Arc::clone(&self.directory);
+        let active = Arc::clone(&self.active);
+        std::thread::spawn(move || {
+            if let Err(error) = directory.maintain(&active) {
+                // The logger cannot log its own failures.
+                eprintln!("Failed to clean up log files: {}", error);
+            }
+        });
+    }
+}
+
+impl Directory {
+    /// Returns the date suffix of the current time period.
+    fn current_period(&self) -> Option<String> {
+        let format = match self.policy.rotation {
+            LogRotation::Minutely => "%Y-%m-%d-%H-%M",
+            LogRotation::Hourly => "%Y-%m-%d-%H",
+            LogRotation::Daily => "%Y-%m-%d",
+            LogRotation::Never => return None,
+        };
+        Some(Utc::now().format(format).to_string())
+    }
+
+    /// Returns the name of the active file of a time period.
+    fn file_name(&self, period: Option<&str>) -> String {
+        match (self.prefix.is_empty(), period) {
+            (false, Some(period)) => format!("{}.{}", self.prefix, period),
+            (true, Some(period)) => period.to_owned(),
+            (false, None) => self.prefix.clone(),
+            (true, None) => FALLBACK_FILE_NAME.to_owned(),
+        }
+    }
+
+    /// Opens the active file of a time period in append mode.
+    fn open(&self, period: Option<String>) -> io::Result<ActiveFile> {
+        let name = self.file_name(period.as_deref());
+        let path = self.path.join(&name);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        let last_index = self.last_rotated_index(&name)?;
+        Ok(ActiveFile {
+            file,
+            path,
+            period,
+            size,
+            last_index,
+        })
+    }
+
+    /// Returns the highest index of the files rotated from `name`, or 0.
+    fn last_rotated_index(&self, name: &str) -> io::Result<u64> {
+        let mut last = 0;
+        for entry in fs::read_dir(&self.path)? {
+            let entry_name = entry?.file_name();
+            let index = entry_name
+                .to_string_lossy()
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .map(|rest| rest.strip_suffix(&format!(".{}", GZIP_EXTENSION)).unwrap_or(rest))
+                .and_then(|index| index.parse::<u64>().ok());
+            last = last.max(index.unwrap_or(0));
+        }
+        Ok(last)
+    }
+
+    /// Returns whether a file name belongs to this writer.
+    ///
+    /// Without a prefix, file names start with the date of their period.
+    fn is_log_file(&self, name: &str) -> bool {
+        let prefix = match (self.prefix.is_empty(), self.policy.rotation) {
+            (true, LogRotation::Never) => FALLBACK_FILE_NAME,
+            (true, _) => {
+                let bytes = name.as_bytes();
+                return bytes.len() >= 10
+                    && bytes[..4].iter().all(u8::is_ascii_digit)
+                    && bytes[4] == b'-';
+            }
+            (false, _) => self.prefix.as_str(),
+        };
+        name == prefix
+            || name
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('.'))
+    }
+
+    /// Returns the rotated log files, excluding the active one.
+    ///
+    /// The active file is locked while the directory is listed, so a
+    /// rotation cannot rename or replace it in between. Files returned here
+    /// never become active again: the active file name only changes to a
+    /// later period and rotated names are never reused.
+    fn rotated_files(&self, active: &Mutex<ActiveFile>) -> io::Result<Vec<PathBuf>> {
+        let active = lock(active);
+
+        let mut rotated = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name();
+            if path != active.path
+                && entry.file_type()?.is_file()
+                && self.is_log_file(&name.to_string_lossy())
+            {
+                rotated.push(path);
+            }
+        }
+        Ok(rotated)
+    }
+
+    /// Compresses rotated files and deletes those exceeding count or age.
+    fn maintain(&self, active: &Mutex<ActiveFile>) -> io::Result<()> {
+        let _guard = lock(&self.maintenance);
+
+        let mut rotated = Vec::new();
+        for path in self.rotated_files(active)? {
+            let is_compressed = path
+                .extension()
+                .is_some_and(|extension| extension == GZIP_EXTENSION);
+            let path = if self.policy.compress && !is_compressed {
+                compress(&path)?
+            } else {
+                path
+            };
+            let modified = fs::metadata(&path)?.modified()?;
+            rotated.push((modified, path));
+        }
+
+        // Newest first, so everything past `max_files` is the oldest.
+        rotated.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        let now = SystemTime::now();
+        for (index, (modified, path)) in rotated.iter().enumerate() {
+            let too_many = self.policy.max_files.is_some_and(|max| index >= max);
+            let too_old = self.policy.max_age.is_some_and(|max_age| {
+                now.duration_since(*modified).is_ok_and(|age| age > max_age)
+            });
+            if too_many || too_old {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Locks a mutex, recovering from a poisoned lock.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Returns the path a file is renamed to when rotated because of its size.
+fn rotated_path(active: &Path, index: u64) -> PathBuf {
+    let mut name = active.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Returns `path` with the gzip extension appended.
+fn gzip_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(GZIP_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Compresses a file with gzip, removes the original and returns the new path.
+///
+/// The modification time is kept, so age-based cleanup still refers to the
+/// time the file was last written.
+fn compress(path: &Path) -> io::Result<PathBuf> {
+    let target = gzip_path(path);
+    let modified = fs::metadata(path)?.modified()?;
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut encoder = GzEncoder::new(BufWriter::new(File::create(&target)?), Compression::default());
+    io::copy(&mut reader, &mut encoder)?;
+    let file = encoder.finish()?.into_inner().map_err(|error| error.into_error())?;
+    file.set_modified(modified)?;
+
+    fs::remove_file(path)?;
+    Ok(target)
+}
+
+impl Write for &RollingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_rotating(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.lock_active().file.flush()
+    }
+}
+
+impl Write for RollingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_rotating(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.lock_active().file.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RollingFileWriter {
+    type Writer = &'a RollingFileWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
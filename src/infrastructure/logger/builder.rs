@@ -1,15 +1,45 @@
 use std::fmt::Debug;
+use std::time::Duration;
+use time::format_description::BorrowedFormatItem;
+use time::macros::format_description;
 use time::UtcOffset;
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+use tracing::Subscriber;
+use tracing_appender::non_blocking::NonBlockingBuilder;
+use tracing_subscriber::fmt::time::OffsetTime;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer, Registry};
 
-use super::{LogLevel, LogRotation};
+use crate::infrastructure::config::LoggerConfig;
+use super::format::DomainFields;
+use super::handle::{filter_string, parse_filter};
+use super::{
+    AlertLayer, LogFormat, LogLevel, LogRotation, LoggerError, LoggerGuard, LoggerHandle,
+    RetentionPolicy, RollingFileWriter,
+};
+
+type Timer = OffsetTime<&'static [BorrowedFormatItem<'static>]>;
+
+/// Timestamp format of all log layers, e.g. `2025-03-01 12:30:00.123456`
+const TIME_FORMAT: &[BorrowedFormatItem<'static>] = format_description!(
+    "[year]-[month padding:zero]-[day padding:zero] [hour]:[minute]:[second].[subsecond digits:6]"
+);
 
 #[derive(Debug, Clone)]
 pub struct LoggerBuilder {
     max_level: LogLevel,
+    directives: Vec<String>,
     directory: String,
     file_name_prefix: String,
-    rolling: LogRotation
+    rolling: LogRotation,
+    max_file_size: Option<u64>,
+    max_files: Option<usize>,
+    max_age: Option<Duration>,
+    compress: bool,
+    file_format: LogFormat,
+    console_format: LogFormat,
+    console: bool,
+    alerts: Option<AlertLayer>,
 }
 
 impl Default for LoggerBuilder {
@@ -17,20 +47,62 @@ impl Default for LoggerBuilder {
     fn default() -> Self {
         Self {
             max_level: LogLevel::Info,
+            directives: Vec::new(),
             directory: "logs".to_owned(),
             file_name_prefix: "".to_owned(),
             rolling: LogRotation::Daily,
+            max_file_size: None,
+            max_files: None,
+            max_age: None,
+            compress: false,
+            file_format: LogFormat::Compact,
+            console_format: LogFormat::Compact,
+            console: true,
+            alerts: None,
         }
     }
 }
 
 impl LoggerBuilder {
 
+    /// Creates a builder from the `[logger]` section of the configuration.
+    pub fn from_config(config: &LoggerConfig) -> Self {
+        let mut builder = Self::default()
+            .with_level(config.level)
+            .with_directory(&config.directory)
+            .with_file_prefix(&config.prefix)
+            .with_rolling(config.rotation)
+            .with_compression(config.compress)
+            .with_file_format(config.file_format)
+            .with_console_format(config.console_format)
+            .with_console(config.console);
+        for directive in &config.directives {
+            builder = builder.with_directive(directive);
+        }
+        if let Some(megabytes) = config.max_file_size_mb {
+            builder = builder.with_max_file_size(megabytes * 1024 * 1024);
+        }
+        if let Some(count) = config.max_files {
+            builder = builder.with_max_files(count);
+        }
+        if let Some(days) = config.max_age_days {
+            builder = builder.with_max_age(Duration::from_secs(days * 24 * 60 * 60));
+        }
+        builder
+    }
+
     pub fn with_level(mut self, level: LogLevel) -> Self {
         self.max_level = level;
         self
     }
 
+    /// Adds a filter directive overriding the level for a log domain or
+    /// module, e.g. `NETWORK=debug` or `pilipili_bot::bot=trace`.
+    pub fn with_directive(mut self, directive: &str) -> Self {
+        self.directives.push(directive.to_owned());
+        self
+    }
+
     pub fn with_directory(mut self, directory: &str) -> Self {
         self.directory = directory.to_owned();
         self
@@ -46,48 +118,193 @@ impl LoggerBuilder {
         self
     }
 
-    pub fn init(self) {
-        let timer_fmt = time::format_description::parse(
-            "[year]-[month padding:zero]-[day padding:zero] [hour]:[minute]:[second].[subsecond digits:6]",
-        )
-            .expect("Failed to parse time format");
+    /// Starts a new log file once the active one would exceed `bytes`.
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Keeps at most `count` rotated log files, deleting the oldest ones.
+    pub fn with_max_files(mut self, count: usize) -> Self {
+        self.max_files = Some(count);
+        self
+    }
+
+    /// Deletes rotated log files older than `age`.
+    pub fn with_max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Compresses rotated log files with gzip.
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    pub fn with_file_format(mut self, format: LogFormat) -> Self {
+        self.file_format = format;
+        self
+    }
+
+    pub fn with_console_format(mut self, format: LogFormat) -> Self {
+        self.console_format = format;
+        self
+    }
+
+    /// Enables or disables the console layer.
+    pub fn with_console(mut self, console: bool) -> Self {
+        self.console = console;
+        self
+    }
+
+    /// Forwards warnings and errors through the given alert layer.
+    pub fn with_alerts(mut self, alerts: AlertLayer) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    /// Installs the logger as the global subscriber.
+    ///
+    /// Unlike `try_init` this never panics or fails: if a logger is already
+    /// installed, a guard for it is returned, and any other error is printed
+    /// to stderr. Keep the returned guard alive until the program exits, it
+    /// flushes buffered log lines when dropped.
+    pub fn init(self) -> LoggerGuard {
+        match self.try_init() {
+            Ok(guard) => guard,
+            Err(LoggerError::AlreadyInitialized) => LoggerGuard::new(LoggerHandle::global(), None),
+            Err(error) => {
+                eprintln!("Failed to initialize logger: {}", error);
+                LoggerGuard::new(None, None)
+            }
+        }
+    }
+
+    /// Installs the logger as the global subscriber.
+    ///
+    /// The `RUST_LOG` environment variable takes precedence over the
+    /// configured level and directives. File output is written on a
+    /// background thread; the returned guard flushes it when dropped and
+    /// gives access to the `LoggerHandle` changing the filter at runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LoggerError::AlreadyInitialized` if a global subscriber is
+    /// already installed, `LoggerError::InvalidFilter` if a directive cannot
+    /// be parsed and `LoggerError::Io` if the log file cannot be created.
+    pub fn try_init(self) -> Result<LoggerGuard, LoggerError> {
+        if LoggerHandle::global().is_some() {
+            return Err(LoggerError::AlreadyInitialized);
+        }
+
         let time_offset = UtcOffset::current_local_offset()
-            .unwrap_or_else(|_| time::UtcOffset::UTC);
-        let timer = fmt::time::OffsetTime::new(time_offset, timer_fmt);
+            .unwrap_or(time::UtcOffset::UTC);
+        let timer = fmt::time::OffsetTime::new(time_offset, TIME_FORMAT);
 
-        let env_filter = EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| EnvFilter::new(self.max_level.to_string()));
+        let filter = match std::env::var(EnvFilter::DEFAULT_ENV).map(|filter| parse_filter(&filter)) {
+            Ok(Ok(filter)) => filter,
+            _ => parse_filter(&filter_string(self.max_level, &self.directives))?,
+        };
+        let (filter, reload_handle) = reload::Layer::new(filter);
 
-        let file_appender = self.rolling
-            .create_file_appender(self.directory, self.file_name_prefix);
+        let file_appender = RollingFileWriter::new(
+            &self.directory,
+            &self.file_name_prefix,
+            self.retention_policy(),
+        )?;
+        // Block instead of dropping lines when the writer thread falls behind,
+        // e.g. while rotating or under a burst of debug output.
+        let (file_writer, worker_guard) = NonBlockingBuilder::default()
+            .lossy(false)
+            .finish(file_appender);
 
-        let file_layer = fmt::Layer::new()
-            .compact()
-            .with_ansi(false)
-            .with_timer(timer.clone())
-            .with_level(true)
-            .with_target(false)
-            .with_file(true)
-            .with_line_number(true)
-            .with_thread_names(false)
-            .with_thread_ids(false)
-            .with_writer(file_appender);
+        let file_layer = Self::build_layer(
+            self.file_format,
+            file_writer,
+            timer.clone(),
+            false,
+        );
+
+        let console_layer = self.console.then(|| {
+            Self::build_layer(
+                self.console_format,
+                std::io::stdout,
+                timer,
+                true,
+            )
+        });
 
-        let console_layer = fmt::Layer::new()
-            .compact()
-            .with_ansi(true)
+        Registry::default()
+            .with(filter)
+            .with(file_layer)
+            .with(console_layer)
+            .with(self.alerts)
+            .try_init()
+            .map_err(|_| LoggerError::AlreadyInitialized)?;
+
+        let handle = LoggerHandle::install_global(reload_handle);
+        Ok(LoggerGuard::new(Some(handle), Some(worker_guard)))
+    }
+
+    /// Returns the rotation and retention settings of the file layer.
+    fn retention_policy(&self) -> RetentionPolicy {
+        let mut policy = RetentionPolicy::new(self.rolling)
+            .with_compression(self.compress);
+        if let Some(bytes) = self.max_file_size {
+            policy = policy.with_max_file_size(bytes);
+        }
+        if let Some(count) = self.max_files {
+            policy = policy.with_max_files(count);
+        }
+        if let Some(age) = self.max_age {
+            policy = policy.with_max_age(age);
+        }
+        policy
+    }
+
+    /// Builds a formatting layer writing to `writer` in the given format.
+    ///
+    /// Console layers use ANSI colors and include thread names; file layers
+    /// are plain. Both text formats print the log domain as a `[DOMAIN]`
+    /// prefix through `DomainFields`, JSON output carries it as a top-level
+    /// `domain` field.
+    fn build_layer<S, W>(
+        format: LogFormat,
+        writer: W,
+        timer: Timer,
+        console: bool,
+    ) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+        W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+    {
+        let layer = fmt::Layer::new()
+            .with_ansi(console)
             .with_timer(timer)
             .with_level(true)
             .with_target(false)
             .with_file(true)
             .with_line_number(true)
-            .with_thread_names(true)
-            .with_thread_ids(false);
+            .with_thread_names(console)
+            .with_thread_ids(false)
+            .with_writer(writer);
 
-        Registry::default()
-            .with(env_filter)
-            .with(file_layer)
-            .with(console_layer)
-            .init();
+        match format {
+            LogFormat::Compact => layer
+                .compact()
+                .fmt_fields(DomainFields)
+                .boxed(),
+            LogFormat::Pretty => layer
+                .pretty()
+                .fmt_fields(DomainFields)
+                .boxed(),
+            LogFormat::Json => layer
+                .json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .with_span_list(true)
+                .boxed(),
+        }
     }
-}
\ No newline at end of file
+}
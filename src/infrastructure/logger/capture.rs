@@ -0,0 +1,130 @@
+//! Captures log lines in memory for assertions in tests.
+//!
+//! The global logger can only be installed once per process, which makes it
+//! unsuitable for checking log output in tests. `LogCapture` instead
+//! installs a subscriber for the current thread only, so every test can
+//! capture its own lines.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use pilipili_bot::info_log;
+//! use pilipili_bot::infrastructure::logger::LogCapture;
+//!
+//! let capture = LogCapture::new();
+//! capture.run(|| {
+//!     info_log!("[CONFIG]", "Config loaded");
+//! });
+//!
+//! assert_eq!(capture.lines(), vec!["INFO [CONFIG] Config loaded"]);
+//! ```
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use tracing::Subscriber;
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{fmt, Registry};
+
+use super::format::DomainFields;
+use super::handle::parse_filter;
+use super::LoggerError;
+
+/// Collects log lines written by the subscribers it creates.
+///
+/// Lines have the form `LEVEL [DOMAIN] message field=value`, without
+/// timestamps or source locations, so they can be compared verbatim.
+#[derive(Debug, Clone, Default)]
+pub struct LogCapture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    filter: Option<String>,
+}
+
+impl LogCapture {
+    /// Creates an empty capture.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only captures events enabled by the given filter directives.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LoggerError::InvalidFilter` if the filter cannot be parsed.
+    pub fn with_filter(mut self, filter: &str) -> Result<Self, LoggerError> {
+        parse_filter(filter)?;
+        self.filter = Some(filter.to_owned());
+        Ok(self)
+    }
+
+    /// Returns a subscriber writing events into the capture.
+    ///
+    /// Without a filter, events of all levels are captured.
+    pub fn subscriber(&self) -> impl Subscriber + Send + Sync + 'static {
+        let filter = self
+            .filter
+            .as_deref()
+            .map(|filter| parse_filter(filter).expect("filter was validated in with_filter"));
+        let layer = fmt::Layer::new()
+            .with_ansi(false)
+            .without_time()
+            .with_level(true)
+            .with_target(false)
+            .compact()
+            .fmt_fields(DomainFields)
+            .with_writer(self.clone());
+        Registry::default().with(filter).with(layer)
+    }
+
+    /// Captures the logs of the current thread until the guard is dropped.
+    pub fn set_default(&self) -> DefaultGuard {
+        tracing::subscriber::set_default(self.subscriber())
+    }
+
+    /// Runs `f`, capturing the logs it writes on the current thread.
+    pub fn run<T>(&self, f: impl FnOnce() -> T) -> T {
+        tracing::subscriber::with_default(self.subscriber(), f)
+    }
+
+    /// Returns the captured lines.
+    ///
+    /// The padding aligning the level names is removed.
+    pub fn lines(&self) -> Vec<String> {
+        let buffer = self.buffer.lock().unwrap();
+        String::from_utf8_lossy(&buffer)
+            .lines()
+            .map(|line| line.trim_start().to_owned())
+            .collect()
+    }
+
+    /// Returns whether any captured line contains `text`.
+    pub fn contains(&self, text: &str) -> bool {
+        self.lines().iter().any(|line| line.contains(text))
+    }
+
+    /// Discards the captured lines.
+    pub fn clear(&self) {
+        self.buffer.lock().unwrap().clear();
+    }
+}
+
+impl Write for LogCapture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogCapture {
+    type Writer = LogCapture;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
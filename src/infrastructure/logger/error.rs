@@ -0,0 +1,50 @@
+//! Defines the error type returned when configuring the logger.
+
+use std::fmt::{self, Display};
+use std::io;
+
+/// Represents an error that occurred while configuring the logger.
+#[derive(Debug)]
+pub enum LoggerError {
+    /// A filter directive could not be parsed
+    InvalidFilter {
+        /// The rejected filter
+        filter: String,
+        /// Why the filter was rejected
+        reason: String,
+    },
+    /// The filter of the running subscriber could not be replaced
+    Reload(String),
+    /// A global subscriber is already installed
+    AlreadyInitialized,
+    /// The log directory or file could not be created
+    Io(io::Error),
+}
+
+impl Display for LoggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoggerError::InvalidFilter { filter, reason } => {
+                write!(f, "invalid log filter `{}`: {}", filter, reason)
+            }
+            LoggerError::Reload(reason) => write!(f, "failed to reload log filter: {}", reason),
+            LoggerError::AlreadyInitialized => write!(f, "a global logger is already installed"),
+            LoggerError::Io(error) => write!(f, "failed to open log file: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for LoggerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoggerError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoggerError {
+    fn from(error: io::Error) -> Self {
+        LoggerError::Io(error)
+    }
+}
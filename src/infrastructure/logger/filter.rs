@@ -0,0 +1,189 @@
+//! Filters log events by module path and by log domain.
+//!
+//! The logging macros keep the module path as the event target, so regular
+//! `EnvFilter` directives such as `pilipili_bot::infrastructure::network=debug`
+//! work as usual. The domain is only recorded as the `domain` field, which
+//! `EnvFilter` cannot match on. `LogFilter` therefore takes directives whose
+//! target is an upper-case domain name, e.g. `EMBY=debug`, out of the filter
+//! string and checks them against the `domain` field of each event. All other
+//! directives are handled by an `EnvFilter`.
+//!
+//! ```rust
+//! use pilipili_bot::infrastructure::logger::LogFilter;
+//!
+//! let filter = LogFilter::parse("warn,EMBY=debug,pilipili_bot::bot=info").unwrap();
+//! assert_eq!(filter.to_string(), "pilipili_bot::bot=info,warn,EMBY=debug");
+//! ```
+
+use std::fmt;
+
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::subscriber::Interest;
+use tracing::{span, Event, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::EnvFilter;
+
+use super::LoggerError;
+
+/// The field the logging macros record the domain in.
+const DOMAIN_FIELD: &str = "domain";
+
+/// A filter combining module directives with per-domain levels.
+#[derive(Debug)]
+pub struct LogFilter {
+    /// The filter for all directives not naming a domain
+    modules: EnvFilter,
+    /// The maximum level per domain, e.g. `EMBY` and `DEBUG`
+    domains: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    /// Parses a filter string in `EnvFilter` syntax with domain directives.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LoggerError::InvalidFilter` if a directive cannot be parsed.
+    pub fn parse(filter: &str) -> Result<Self, LoggerError> {
+        let invalid = |reason: String| LoggerError::InvalidFilter {
+            filter: filter.to_owned(),
+            reason,
+        };
+
+        let mut modules = Vec::new();
+        let mut domains = Vec::new();
+        for directive in filter.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+            let (target, level) = match directive.split_once('=') {
+                Some((target, level)) => (target, Some(level)),
+                None => (directive, None),
+            };
+            if !is_domain(target) {
+                modules.push(directive);
+                continue;
+            }
+
+            let level = match level {
+                Some(level) => level
+                    .parse::<LevelFilter>()
+                    .map_err(|error| invalid(format!("{}: {}", directive, error)))?,
+                None => LevelFilter::TRACE,
+            };
+            domains.push((target.to_owned(), level));
+        }
+
+        let modules = EnvFilter::builder()
+            .parse(modules.join(","))
+            .map_err(|error| invalid(error.to_string()))?;
+        Ok(Self { modules, domains })
+    }
+
+    /// Returns the level configured for a domain, if any.
+    fn domain_level(&self, domain: &str) -> Option<LevelFilter> {
+        self.domains
+            .iter()
+            .rev()
+            .find(|(name, _)| name == domain)
+            .map(|(_, level)| *level)
+    }
+
+    /// Returns whether domain directives have to be checked for a callsite.
+    fn filters_domain(&self, metadata: &Metadata<'_>) -> bool {
+        !self.domains.is_empty()
+            && metadata.is_event()
+            && metadata.fields().field(DOMAIN_FIELD).is_some()
+    }
+}
+
+/// Returns whether a directive target names a log domain such as `EMBY`.
+///
+/// Module paths are lower case, and bare level names such as `WARN` are
+/// left to `EnvFilter`.
+fn is_domain(target: &str) -> bool {
+    target.starts_with(|c: char| c.is_ascii_uppercase())
+        && target.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        && target.parse::<LevelFilter>().is_err()
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.modules)?;
+        for (domain, level) in &self.domains {
+            write!(f, ",{}={}", domain, level)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogFilter {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if self.filters_domain(metadata) {
+            // Decided per event, as the domain is a field value.
+            Interest::sometimes()
+        } else {
+            <EnvFilter as Layer<S>>::register_callsite(&self.modules, metadata)
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        let domains = self.domains.iter().map(|(_, level)| *level).max();
+        self.modules
+            .max_level_hint()
+            .map(|modules| domains.map_or(modules, |domains| modules.max(domains)))
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.filters_domain(metadata) || self.modules.enabled(metadata, ctx)
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        let metadata = event.metadata();
+        if !self.filters_domain(metadata) {
+            return true;
+        }
+
+        let mut visitor = DomainVisitor::default();
+        event.record(&mut visitor);
+        match visitor.0.as_deref().and_then(|domain| self.domain_level(domain)) {
+            Some(level) => *metadata.level() <= level,
+            None => self.modules.enabled(metadata, ctx),
+        }
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        self.modules.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        self.modules.on_record(id, values, ctx);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.modules.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.modules.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.modules.on_close(id, ctx);
+    }
+}
+
+/// Reads the `domain` field of an event.
+#[derive(Default)]
+struct DomainVisitor(Option<String>);
+
+impl Visit for DomainVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == DOMAIN_FIELD {
+            self.0 = Some(value.to_owned());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == DOMAIN_FIELD {
+            self.0 = Some(format!("{:?}", value).trim_matches('"').to_owned());
+        }
+    }
+}
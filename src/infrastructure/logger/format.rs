@@ -0,0 +1,112 @@
+//! Defines the output formats available for log layers.
+//!
+//! The file and console layers can use different formats, e.g. JSON lines in
+//! files for log shippers like Loki or Elasticsearch and compact text on the
+//! console for humans.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use tracing::field::{Field, Visit};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::{FormatFields, Writer};
+
+/// Represents the output format of a log layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Single-line text output, e.g. `INFO file.rs:12: [CONFIG] loaded`
+    #[default]
+    Compact,
+    /// Multi-line, human-friendly text output
+    Pretty,
+    /// One JSON object per line, with the domain as a top-level field such
+    /// as `"domain":"CONFIG"`
+    Json,
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format_str = match *self {
+            LogFormat::Compact => "compact",
+            LogFormat::Pretty => "pretty",
+            LogFormat::Json => "json",
+        };
+        write!(f, "{}", format_str)
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "compact" => Ok(LogFormat::Compact),
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format: {}", s)),
+        }
+    }
+}
+
+/// Formats event and span fields for the compact and pretty text formats.
+///
+/// The `domain` field recorded by the logging macros is printed in brackets
+/// before the message, so text logs keep the familiar `[DOMAIN] message`
+/// shape while JSON logs carry the bare domain as a separate field.
+/// Remaining fields follow as `name=value`.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct DomainFields;
+
+impl<'writer> FormatFields<'writer> for DomainFields {
+    fn format_fields<R: RecordFields>(&self, mut writer: Writer<'writer>, fields: R) -> fmt::Result {
+        let mut visitor = DomainVisitor::default();
+        fields.record(&mut visitor);
+
+        let parts = visitor.bracketed_domain()
+            .into_iter()
+            .chain(visitor.message)
+            .chain(visitor.fields);
+        for (index, part) in parts.enumerate() {
+            if index > 0 {
+                writer.write_char(' ')?;
+            }
+            writer.write_str(&part)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the fields of an event, keeping the domain and message apart.
+#[derive(Default)]
+pub(crate) struct DomainVisitor {
+    pub(crate) domain: Option<String>,
+    pub(crate) message: Option<String>,
+    pub(crate) fields: Vec<String>,
+}
+
+impl DomainVisitor {
+    /// Returns the domain as written in text logs, e.g. `[EMBY]`.
+    pub(crate) fn bracketed_domain(&self) -> Option<String> {
+        self.domain.as_ref().map(|domain| format!("[{}]", domain))
+    }
+}
+
+impl Visit for DomainVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "domain" => self.domain = Some(value.to_owned()),
+            "message" => self.message = Some(value.to_owned()),
+            _ => self.record_debug(field, &value),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "domain" => self.domain = Some(format!("{:?}", value)),
+            "message" => self.message = Some(format!("{:?}", value)),
+            name => self.fields.push(format!("{}={:?}", name, value)),
+        }
+    }
+}
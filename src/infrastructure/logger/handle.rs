@@ -0,0 +1,116 @@
+//! Changes the log filter of the running subscriber.
+//!
+//! `LoggerBuilder::init` installs the `LogFilter` behind a reload layer and
+//! returns a guard holding a `LoggerHandle` to it. The handle can be cloned
+//! and passed to whatever needs to change the verbosity at runtime, such as
+//! an admin bot command or a configuration reload. It is also available
+//! through `LoggerHandle::global` once the logger is installed.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let _guard = LoggerBuilder::default().init();
+//! let logger = LoggerHandle::global().unwrap();
+//!
+//! // Debug logs for the network domain, info for everything else
+//! logger.set_filter("info,NETWORK=debug")?;
+//! ```
+
+use once_cell::sync::OnceCell;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{reload, Registry};
+
+use super::{LogFilter, LogLevel, LoggerError};
+
+/// The handle of the installed logger, set once by `LoggerBuilder::try_init`.
+static GLOBAL_HANDLE: OnceCell<LoggerHandle> = OnceCell::new();
+
+/// A handle to the filter of the installed subscriber.
+#[derive(Debug, Clone)]
+pub struct LoggerHandle {
+    handle: reload::Handle<LogFilter, Registry>,
+}
+
+impl LoggerHandle {
+    /// Stores the reload handle of the installed filter as the global handle.
+    pub(crate) fn install_global(handle: reload::Handle<LogFilter, Registry>) -> Self {
+        GLOBAL_HANDLE.get_or_init(|| Self { handle }).clone()
+    }
+
+    /// Returns the handle of the installed logger, if any.
+    pub fn global() -> Option<Self> {
+        GLOBAL_HANDLE.get().cloned()
+    }
+
+    /// Returns the active filter in `EnvFilter` directive syntax, followed by
+    /// the domain directives.
+    pub fn current_filter(&self) -> Result<String, LoggerError> {
+        self.handle
+            .with_current(|filter| filter.to_string())
+            .map_err(|error| LoggerError::Reload(error.to_string()))
+    }
+
+    /// Replaces the active filter.
+    ///
+    /// The filter uses the `EnvFilter` syntax, e.g. `debug` or
+    /// `info,pilipili_bot::bot=trace`, and accepts domain directives such as
+    /// `NETWORK=trace`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LoggerError::InvalidFilter` if the filter cannot be parsed,
+    /// in which case the active filter is kept.
+    pub fn set_filter(&self, filter: &str) -> Result<(), LoggerError> {
+        let filter = parse_filter(filter)?;
+        self.handle
+            .reload(filter)
+            .map_err(|error| LoggerError::Reload(error.to_string()))
+    }
+
+    /// Replaces the active filter by a default level and additional directives.
+    pub fn set_level(&self, level: LogLevel, directives: &[String]) -> Result<(), LoggerError> {
+        self.set_filter(&filter_string(level, directives))
+    }
+}
+
+/// Keeps the logger alive and flushes buffered log lines when dropped.
+///
+/// Returned by `LoggerBuilder::init`; bind it to a variable in `main`, e.g.
+/// `let _guard = ...`, as `let _ = ...` would drop it immediately.
+#[must_use = "buffered log lines are flushed when the guard is dropped"]
+#[derive(Debug)]
+pub struct LoggerGuard {
+    /// The handle to the filter, if the logger is installed
+    handle: Option<LoggerHandle>,
+    /// Flushes the background file writer on drop
+    _worker: Option<WorkerGuard>,
+}
+
+impl LoggerGuard {
+    /// Creates a guard for an installed logger.
+    pub(crate) fn new(handle: Option<LoggerHandle>, worker: Option<WorkerGuard>) -> Self {
+        Self {
+            handle,
+            _worker: worker,
+        }
+    }
+
+    /// Returns the handle changing the filter, or `None` if installing failed.
+    pub fn handle(&self) -> Option<&LoggerHandle> {
+        self.handle.as_ref()
+    }
+}
+
+/// Joins a default level and per-module directives into a filter string.
+pub(crate) fn filter_string(level: LogLevel, directives: &[String]) -> String {
+    std::iter::once(level.to_string().to_ascii_lowercase())
+        .chain(directives.iter().map(|directive| directive.trim().to_owned()))
+        .filter(|directive| !directive.is_empty())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parses a filter string, reporting invalid directives instead of ignoring them.
+pub(crate) fn parse_filter(filter: &str) -> Result<LogFilter, LoggerError> {
+    LogFilter::parse(filter)
+}
@@ -4,6 +4,9 @@
 //! Each level represents a different severity of log message.
 
 use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
 
 /// Represents the severity level of a log message.
 /// 
@@ -14,7 +17,8 @@ use std::fmt;
 /// - `Info`: General information about program execution
 /// - `Debug`: Detailed information useful for debugging
 /// - `Trace`: Very detailed information for tracing program flow
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LogLevel {
     /// Critical errors that require immediate attention
     Error,
@@ -40,4 +44,19 @@ impl fmt::Display for LogLevel {
         };
         write!(f, "{}", level_str)
     }
-}
\ No newline at end of file
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(format!("unknown log level: {}", s)),
+        }
+    }
+}
@@ -2,128 +2,278 @@
 //! 
 //! This module exports macros that make it easy to log messages with different severity levels.
 //! Each macro supports both a simple form (with just a message) and a form that includes a domain.
+//! The domain is recorded without its brackets as the structured `domain`
+//! field of the event, while the event target stays the module path. Filter
+//! directives can therefore select both domains and modules, e.g.
+//! `info,EMBY=debug,pilipili_bot::infrastructure::network=warn`; see
+//! `LogFilter` for how domain directives are matched.
+//!
+//! After the domain, the macros accept either a single message expression, a
+//! format string with arguments, or structured fields followed by a format
+//! string. Fields use the `tracing` syntax: `key = value`, `key = %value`
+//! for `Display` and `key = ?value` for `Debug`, or the `%key` and `?key`
+//! shorthands.
+//!
+//! ```rust
+//! # use pilipili_bot::info_log;
+//! # let (id, name) = (42, "alice");
+//! info_log!("[EMBY]", "Created user {}", name);
+//! info_log!("[EMBY]", user_id = %id, "Created user {}", name);
+//! ```
+
+/// Returns the name of a log domain, e.g. `EMBY` for `[EMBY]`.
+pub const fn domain_name(domain: &str) -> &str {
+    let bytes = domain.as_bytes();
+    if bytes.len() < 2 || bytes[0] != b'[' || bytes[bytes.len() - 1] != b']' {
+        return domain;
+    }
+
+    let (_, inner) = bytes.split_at(1);
+    let (inner, _) = inner.split_at(inner.len() - 1);
+    match std::str::from_utf8(inner) {
+        Ok(name) => name,
+        Err(_) => domain,
+    }
+}
+
+/// Emits an event at the given `tracing` level with a domain field.
+///
+/// Shared implementation of the level macros; use those instead.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __domain_log {
+    ($level:ident, $domain:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {
+        tracing::$level!(
+            domain = $crate::infrastructure::logger::macros::domain_name($domain),
+            $fmt $(, $arg)*
+        );
+    };
+    ($level:ident, $domain:expr, $($key:ident).+ = $($rest:tt)+) => {
+        tracing::$level!(
+            domain = $crate::infrastructure::logger::macros::domain_name($domain),
+            $($key).+ = $($rest)+
+        );
+    };
+    ($level:ident, $domain:expr, % $($rest:tt)+) => {
+        tracing::$level!(
+            domain = $crate::infrastructure::logger::macros::domain_name($domain),
+            % $($rest)+
+        );
+    };
+    ($level:ident, $domain:expr, ? $($rest:tt)+) => {
+        tracing::$level!(
+            domain = $crate::infrastructure::logger::macros::domain_name($domain),
+            ? $($rest)+
+        );
+    };
+    ($level:ident, $domain:expr, $msg:expr) => {
+        tracing::$level!(
+            domain = $crate::infrastructure::logger::macros::domain_name($domain),
+            "{}",
+            $msg
+        );
+    };
+    ($level:ident, $msg:expr) => {
+        $crate::__domain_log!($level, "[APP]", $msg);
+    };
+}
 
 /// Log a message at the trace level.
 /// 
-/// This macro supports two forms:
+/// This macro supports four forms:
 /// 
 /// 1. Simple form with just a message:
 /// ```rust
+/// # use pilipili_bot::trace_log;
+/// # let (id, user) = (42, "alice");
 /// trace_log!("This is a trace message");
 /// ```
 /// 
 /// 2. Form with domain and message:
 /// ```rust
+/// # use pilipili_bot::trace_log;
+/// # let (id, user) = (42, "alice");
 /// trace_log!("[MyDomain]", "This is a trace message");
 /// ```
+///
+/// 3. Form with domain and format arguments:
+/// ```rust
+/// # use pilipili_bot::trace_log;
+/// # let (id, user) = (42, "alice");
+/// trace_log!("[MyDomain]", "This is a trace message for {}", user);
+/// ```
+///
+/// 4. Form with domain, fields and format arguments:
+/// ```rust
+/// # use pilipili_bot::trace_log;
+/// # let (id, user) = (42, "alice");
+/// trace_log!("[MyDomain]", user_id = %id, "This is a trace message for {}", user);
+/// ```
 /// 
 /// If no domain is specified, "[APP]" will be used as the default domain.
 #[macro_export]
 macro_rules! trace_log {
-    ($msg:expr) => {
-        trace_log!("[APP]", $msg);
-    };
-    ($domain:expr, $msg:expr) => {
-        tracing::trace!("{} {}", $domain, $msg);
+    ($($args:tt)+) => {
+        $crate::__domain_log!(trace, $($args)+);
     };
 }
 
 /// Log a message at the debug level.
 /// 
-/// This macro supports two forms:
+/// This macro supports four forms:
 /// 
 /// 1. Simple form with just a message:
 /// ```rust
+/// # use pilipili_bot::debug_log;
+/// # let (id, user) = (42, "alice");
 /// debug_log!("This is a debug message");
 /// ```
 /// 
 /// 2. Form with domain and message:
 /// ```rust
+/// # use pilipili_bot::debug_log;
+/// # let (id, user) = (42, "alice");
 /// debug_log!("[MyDomain]", "This is a debug message");
 /// ```
+///
+/// 3. Form with domain and format arguments:
+/// ```rust
+/// # use pilipili_bot::debug_log;
+/// # let (id, user) = (42, "alice");
+/// debug_log!("[MyDomain]", "This is a debug message for {}", user);
+/// ```
+///
+/// 4. Form with domain, fields and format arguments:
+/// ```rust
+/// # use pilipili_bot::debug_log;
+/// # let (id, user) = (42, "alice");
+/// debug_log!("[MyDomain]", user_id = %id, "This is a debug message for {}", user);
+/// ```
 /// 
 /// If no domain is specified, "[APP]" will be used as the default domain.
 #[macro_export]
 macro_rules! debug_log {
-    ($msg:expr) => {
-        debug_log!("[APP]", $msg);
-    };
-    ($domain:expr, $msg:expr) => {
-        tracing::debug!("{} {}", $domain, $msg);
+    ($($args:tt)+) => {
+        $crate::__domain_log!(debug, $($args)+);
     };
 }
 
 /// Log a message at the info level.
 /// 
-/// This macro supports two forms:
+/// This macro supports four forms:
 /// 
 /// 1. Simple form with just a message:
 /// ```rust
+/// # use pilipili_bot::info_log;
+/// # let (id, user) = (42, "alice");
 /// info_log!("This is an info message");
 /// ```
 /// 
 /// 2. Form with domain and message:
 /// ```rust
+/// # use pilipili_bot::info_log;
+/// # let (id, user) = (42, "alice");
 /// info_log!("[MyDomain]", "This is an info message");
 /// ```
+///
+/// 3. Form with domain and format arguments:
+/// ```rust
+/// # use pilipili_bot::info_log;
+/// # let (id, user) = (42, "alice");
+/// info_log!("[MyDomain]", "This is an info message for {}", user);
+/// ```
+///
+/// 4. Form with domain, fields and format arguments:
+/// ```rust
+/// # use pilipili_bot::info_log;
+/// # let (id, user) = (42, "alice");
+/// info_log!("[MyDomain]", user_id = %id, "This is an info message for {}", user);
+/// ```
 /// 
 /// If no domain is specified, "[APP]" will be used as the default domain.
 #[macro_export]
 macro_rules! info_log {
-    ($msg:expr) => {
-        info_log!("[APP]", $msg);
-    };
-    ($domain:expr, $msg:expr) => {
-        tracing::info!("{} {}", $domain, $msg);
+    ($($args:tt)+) => {
+        $crate::__domain_log!(info, $($args)+);
     };
 }
 
 /// Log a message at the warn level.
 /// 
-/// This macro supports two forms:
+/// This macro supports four forms:
 /// 
 /// 1. Simple form with just a message:
 /// ```rust
+/// # use pilipili_bot::warn_log;
+/// # let (id, user) = (42, "alice");
 /// warn_log!("This is a warning message");
 /// ```
 /// 
 /// 2. Form with domain and message:
 /// ```rust
+/// # use pilipili_bot::warn_log;
+/// # let (id, user) = (42, "alice");
 /// warn_log!("[MyDomain]", "This is a warning message");
 /// ```
+///
+/// 3. Form with domain and format arguments:
+/// ```rust
+/// # use pilipili_bot::warn_log;
+/// # let (id, user) = (42, "alice");
+/// warn_log!("[MyDomain]", "This is a warning message for {}", user);
+/// ```
+///
+/// 4. Form with domain, fields and format arguments:
+/// ```rust
+/// # use pilipili_bot::warn_log;
+/// # let (id, user) = (42, "alice");
+/// warn_log!("[MyDomain]", user_id = %id, "This is a warning message for {}", user);
+/// ```
 /// 
 /// If no domain is specified, "[APP]" will be used as the default domain.
 #[macro_export]
 macro_rules! warn_log {
-    ($msg:expr) => {
-        warn_log!("[APP]", $msg);
-    };
-    ($domain:expr, $msg:expr) => {
-        tracing::warn!("{} {}", $domain, $msg);
+    ($($args:tt)+) => {
+        $crate::__domain_log!(warn, $($args)+);
     };
 }
 
 /// Log a message at the error level.
 /// 
-/// This macro supports two forms:
+/// This macro supports four forms:
 /// 
 /// 1. Simple form with just a message:
 /// ```rust
+/// # use pilipili_bot::error_log;
+/// # let (id, user) = (42, "alice");
 /// error_log!("This is an error message");
 /// ```
 /// 
 /// 2. Form with domain and message:
 /// ```rust
+/// # use pilipili_bot::error_log;
+/// # let (id, user) = (42, "alice");
 /// error_log!("[MyDomain]", "This is an error message");
 /// ```
+///
+/// 3. Form with domain and format arguments:
+/// ```rust
+/// # use pilipili_bot::error_log;
+/// # let (id, user) = (42, "alice");
+/// error_log!("[MyDomain]", "This is an error message for {}", user);
+/// ```
+///
+/// 4. Form with domain, fields and format arguments:
+/// ```rust
+/// # use pilipili_bot::error_log;
+/// # let (id, user) = (42, "alice");
+/// error_log!("[MyDomain]", user_id = %id, "This is an error message for {}", user);
+/// ```
 /// 
 /// If no domain is specified, "[APP]" will be used as the default domain.
 #[macro_export]
 macro_rules! error_log {
-    ($msg:expr) => {
-        error_log!("[APP]", $msg);
-    };
-    ($domain:expr, $msg:expr) => {
-        tracing::error!("{} {}", $domain, $msg);
+    ($($args:tt)+) => {
+        $crate::__domain_log!(error, $($args)+);
     };
 }
\ No newline at end of file
@@ -1,34 +1,62 @@
 //! A flexible and configurable logging system for Rust applications.
 //! 
 //! This module provides a comprehensive logging solution with the following features:
-//! - Configurable log levels
-//! - Log rotation support
+//! - Configurable log levels, changeable at runtime through a `LoggerHandle`
+//! - Log rotation by time and size, with retention and gzip compression
+//! - Compact, pretty or JSON output per layer
 //! - Builder pattern for easy configuration
 //! - Convenient macros for logging
+//! - In-memory log capture for tests
+//! - Batched, rate-limited alerts about warnings and errors
 //! 
 //! # Examples
 //! 
-//! ```rust
-//! use infrastructure::logger::{LogLevel, LogRotation, LoggerBuilder};
-//! 
+//! ```rust,no_run
+//! use pilipili_bot::{debug_log, info_log};
+//! use pilipili_bot::infrastructure::logger::{LogFormat, LogLevel, LogRotation, LoggerBuilder};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
 //! // Configure and initialize the logger
-//! LoggerBuilder::default()
+//! let guard = LoggerBuilder::default()
 //!     .with_level(LogLevel::Info)
 //!     .with_rolling(LogRotation::Daily)
+//!     .with_max_file_size(10 * 1024 * 1024)
+//!     .with_max_files(14)
+//!     .with_compression(true)
 //!     .with_directory("logs")
 //!     .with_file_prefix("app")
-//!     .init();
+//!     .with_file_format(LogFormat::Json)
+//!     .try_init()?;
+//!
+//! // Raise the verbosity later without restarting
+//! guard.handle().unwrap().set_filter("debug")?;
 //! 
 //! // Use the logging macros
 //! info_log!("Application started");
 //! debug_log!("[Database]", "Connected to database");
+//! # Ok(())
+//! # }
 //! ```
 
 pub mod builder;
 pub mod rotation;
 pub mod level;
 pub mod macros;
+pub mod format;
+pub mod appender;
+pub mod handle;
+pub mod filter;
+pub mod error;
+pub mod capture;
+pub mod alert;
 
 pub use builder::LoggerBuilder;
 pub use rotation::LogRotation;
-pub use level::LogLevel;
\ No newline at end of file
+pub use level::LogLevel;
+pub use format::LogFormat;
+pub use appender::{RetentionPolicy, RollingFileWriter};
+pub use handle::{LoggerGuard, LoggerHandle};
+pub use filter::LogFilter;
+pub use error::LoggerError;
+pub use capture::LogCapture;
+pub use alert::{Alert, AlertBatch, AlertForwarder, AlertLayer, AlertSink};
\ No newline at end of file
@@ -3,14 +3,15 @@
 //! This module provides different rotation strategies for log files,
 //! allowing for automatic file management based on time intervals.
 
-use tracing_appender::rolling::{self, RollingFileAppender};
+use serde::Deserialize;
 
 /// Defines how often log files should be rotated.
 /// 
 /// The rotation strategy determines when a new log file is created
 /// and the old one is archived. This helps manage log file sizes
 /// and organize logs by time periods.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LogRotation {
     /// Rotate log files every minute
     Minutely,
@@ -21,28 +22,3 @@ pub enum LogRotation {
     /// Never rotate log files
     Never,
 }
-
-impl LogRotation {
-    /// Creates a new file appender with the specified rotation strategy.
-    /// 
-    /// # Arguments
-    /// 
-    /// * `directory` - The directory where log files will be stored
-    /// * `file_prefix` - The prefix to use for log file names
-    /// 
-    /// # Returns
-    /// 
-    /// A `RollingFileAppender` configured with the specified rotation strategy
-    pub fn create_file_appender(
-        self, 
-        directory: String, 
-        file_prefix: String
-    ) -> RollingFileAppender {
-        match self {
-            LogRotation::Minutely => rolling::minutely(directory, file_prefix),
-            LogRotation::Hourly => rolling::hourly(directory, file_prefix),
-            LogRotation::Daily => rolling::daily(directory, file_prefix),
-            LogRotation::Never => rolling::never(directory, file_prefix),
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,62 @@
+//! Declares the bot-level counters.
+//!
+//! Network metrics are recorded automatically by `MetricsPlugin`. Bot events
+//! have no such hook, so command handlers report them through `BotMetrics`.
+
+use std::sync::Arc;
+
+use super::registry::{Metric, MetricsRegistry};
+
+/// Number of bot commands handled, by command and outcome.
+pub const COMMANDS_HANDLED: Metric = Metric::counter(
+    "pilipili_bot_commands_total",
+    "Number of bot commands handled, by command and outcome.",
+);
+
+/// Number of media server accounts registered with the bot through `/bind`.
+pub const REGISTRATIONS: Metric = Metric::counter(
+    "pilipili_bot_registrations_total",
+    "Number of media server accounts registered with the bot.",
+);
+
+/// Number of media server accounts disabled by the bot, by reason.
+pub const DISABLES: Metric = Metric::counter(
+    "pilipili_bot_disables_total",
+    "Number of media server accounts disabled by the bot, by reason.",
+);
+
+/// Records bot events into a metrics registry.
+#[derive(Debug, Clone)]
+pub struct BotMetrics {
+    registry: Arc<MetricsRegistry>,
+}
+
+impl Default for BotMetrics {
+    fn default() -> Self {
+        Self::new(MetricsRegistry::global())
+    }
+}
+
+impl BotMetrics {
+    /// Creates a recorder writing into `registry`.
+    pub fn new(registry: Arc<MetricsRegistry>) -> Self {
+        Self { registry }
+    }
+
+    /// Records a handled command, e.g. `command_handled("register", true)`.
+    pub fn command_handled(&self, command: &str, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.registry
+            .increment(&COMMANDS_HANDLED, &[("command", command), ("outcome", outcome)]);
+    }
+
+    /// Records a newly registered account, e.g. after a successful `/bind`.
+    pub fn registration(&self) {
+        self.registry.increment(&REGISTRATIONS, &[]);
+    }
+
+    /// Records a disabled account, e.g. `account_disabled("expired")`.
+    pub fn account_disabled(&self, reason: &str) {
+        self.registry.increment(&DISABLES, &[("reason", reason)]);
+    }
+}
@@ -0,0 +1,110 @@
+//! Serves the metrics registry on a `/metrics` HTTP endpoint.
+//!
+//! The exporter only needs to answer Prometheus scrapes, so it implements the
+//! bare minimum of HTTP/1.1 on top of a `TcpListener` instead of pulling in a
+//! web framework.
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::{debug_log, info_log};
+use super::registry::MetricsRegistry;
+
+/// Domain identifier for exporter logs
+const METRICS_LOGGER_DOMAIN: &str = "[METRICS]";
+
+/// Maximum size of a request head that is accepted
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// The content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// An HTTP server exposing a registry in the Prometheus text format.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use pilipili_bot::infrastructure::metrics::{MetricsExporter, MetricsRegistry};
+///
+/// # async fn example() -> std::io::Result<()> {
+/// let exporter = MetricsExporter::bind("0.0.0.0:9898", MetricsRegistry::global()).await?;
+/// tokio::spawn(exporter.serve());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The bot starts the exporter when `[metrics] enabled` is set.
+pub struct MetricsExporter {
+    listener: TcpListener,
+    registry: Arc<MetricsRegistry>,
+}
+
+impl MetricsExporter {
+    /// Binds the exporter to `address`.
+    pub async fn bind(
+        address: impl ToSocketAddrs,
+        registry: Arc<MetricsRegistry>,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(address).await?;
+        Ok(Self { listener, registry })
+    }
+
+    /// Returns the address the exporter is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts scrapes until the listener fails.
+    pub async fn serve(self) -> io::Result<()> {
+        info_log!(METRICS_LOGGER_DOMAIN, "Serving metrics on http://{}/metrics", self.local_addr()?);
+
+        loop {
+            let (socket, peer) = self.listener.accept().await?;
+            let registry = self.registry.clone();
+            tokio::spawn(async move {
+                if let Err(error) = Self::handle(socket, &registry).await {
+                    debug_log!(METRICS_LOGGER_DOMAIN, "Failed to answer scrape from {}: {}", peer, error);
+                }
+            });
+        }
+    }
+
+    /// Answers a single request.
+    async fn handle(mut socket: TcpStream, registry: &MetricsRegistry) -> io::Result<()> {
+        let mut buffer = Vec::with_capacity(1024);
+        let mut chunk = [0; 1024];
+        while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+            let read = socket.read(&mut chunk).await?;
+            if read == 0 || buffer.len() + read > MAX_REQUEST_SIZE {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        }
+
+        let head = String::from_utf8_lossy(&buffer);
+        let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+        let method = request_line.next().unwrap_or_default();
+        let path = request_line.next().unwrap_or_default();
+        let path = path.split('?').next().unwrap_or_default();
+
+        let (status, body) = match (method, path) {
+            ("GET", "/metrics") => ("200 OK", registry.render()),
+            (_, "/metrics") => ("405 Method Not Allowed", String::new()),
+            _ => ("404 Not Found", String::new()),
+        };
+
+        let response = format!(
+            "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            CONTENT_TYPE,
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await?;
+        socket.shutdown().await
+    }
+}
@@ -0,0 +1,30 @@
+//! Collects runtime metrics and exposes them to Prometheus.
+//!
+//! This module provides:
+//! - A registry for counters and histograms
+//! - Bot-level counters for commands, registrations and disabled accounts
+//! - An HTTP exporter serving `/metrics` in the Prometheus text format
+//!
+//! Network metrics are recorded by `network::MetricsPlugin`.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use pilipili_bot::infrastructure::metrics::{BotMetrics, MetricsExporter, MetricsRegistry};
+//!
+//! # async fn example() -> std::io::Result<()> {
+//! BotMetrics::default().command_handled("start", true);
+//!
+//! let exporter = MetricsExporter::bind("127.0.0.1:9898", MetricsRegistry::global()).await?;
+//! tokio::spawn(exporter.serve());
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod registry;
+pub mod bot;
+pub mod exporter;
+
+pub use registry::{Metric, MetricKind, MetricsRegistry, LATENCY_BUCKETS};
+pub use bot::BotMetrics;
+pub use exporter::MetricsExporter;
@@ -0,0 +1,248 @@
+//! Stores metric values and renders them in the Prometheus text format.
+//!
+//! Metrics are declared as constants of type `Metric` and recorded with a set
+//! of labels. Every distinct label set becomes its own time series, so labels
+//! must have a bounded number of values (path templates, never raw URLs).
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+/// The process-wide registry used when no registry is passed explicitly.
+static GLOBAL_REGISTRY: Lazy<Arc<MetricsRegistry>> = Lazy::new(|| Arc::new(MetricsRegistry::new()));
+
+/// Default histogram buckets for latencies in seconds.
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// The type of a metric.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricKind {
+    /// A value that only ever increases
+    Counter,
+    /// A distribution of observed values, with the given upper bucket bounds
+    Histogram(&'static [f64]),
+}
+
+/// Describes a metric.
+///
+/// # Examples
+///
+/// ```rust
+/// use pilipili_bot::infrastructure::metrics::{Metric, MetricsRegistry};
+///
+/// const JOBS: Metric = Metric::counter("jobs_total", "Number of jobs run.");
+///
+/// let registry = MetricsRegistry::new();
+/// registry.increment(&JOBS, &[("job", "cleanup")]);
+/// assert!(registry.render().contains(r#"jobs_total{job="cleanup"} 1"#));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metric {
+    /// The metric name, e.g. `pilipili_http_requests_total`
+    pub name: &'static str,
+    /// A human-readable description
+    pub help: &'static str,
+    /// The metric type
+    pub kind: MetricKind,
+}
+
+impl Metric {
+    /// Declares a counter.
+    pub const fn counter(name: &'static str, help: &'static str) -> Self {
+        Self { name, help, kind: MetricKind::Counter }
+    }
+
+    /// Declares a histogram with the given bucket upper bounds.
+    pub const fn histogram(name: &'static str, help: &'static str, buckets: &'static [f64]) -> Self {
+        Self { name, help, kind: MetricKind::Histogram(buckets) }
+    }
+}
+
+/// A sorted list of label names and values identifying one time series.
+type Labels = Vec<(String, String)>;
+
+/// The current value of one time series.
+#[derive(Debug, Clone)]
+enum Series {
+    Counter(u64),
+    Histogram {
+        /// Number of observations per bucket, not cumulative
+        buckets: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+/// All time series of one metric.
+#[derive(Debug)]
+struct Family {
+    metric: Metric,
+    series: BTreeMap<Labels, Series>,
+}
+
+/// A collection of metrics.
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    families: Mutex<BTreeMap<&'static str, Family>>,
+}
+
+impl MetricsRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the process-wide registry.
+    pub fn global() -> Arc<MetricsRegistry> {
+        GLOBAL_REGISTRY.clone()
+    }
+
+    /// Increases a counter by one.
+    pub fn increment(&self, metric: &Metric, labels: &[(&str, &str)]) {
+        self.add(metric, labels, 1);
+    }
+
+    /// Increases a counter by `value`.
+    pub fn add(&self, metric: &Metric, labels: &[(&str, &str)], value: u64) {
+        self.update(metric, labels, |series| {
+            if let Series::Counter(counter) = series {
+                *counter += value;
+            }
+        });
+    }
+
+    /// Records an observation in a histogram.
+    pub fn observe(&self, metric: &Metric, labels: &[(&str, &str)], value: f64) {
+        let MetricKind::Histogram(bounds) = metric.kind else {
+            return;
+        };
+        self.update(metric, labels, |series| {
+            if let Series::Histogram { buckets, sum, count } = series {
+                if let Some(index) = bounds.iter().position(|bound| value <= *bound) {
+                    buckets[index] += 1;
+                }
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+
+    /// Returns the current value of a counter, or 0 if it was never recorded.
+    pub fn counter_value(&self, metric: &Metric, labels: &[(&str, &str)]) -> u64 {
+        let families = self.families.lock().unwrap();
+        let series = families
+            .get(metric.name)
+            .and_then(|family| family.series.get(&Self::labels(labels)));
+        match series {
+            Some(Series::Counter(value)) => *value,
+            _ => 0,
+        }
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap();
+        let mut output = String::new();
+
+        for family in families.values() {
+            let metric = &family.metric;
+            let kind = match metric.kind {
+                MetricKind::Counter => "counter",
+                MetricKind::Histogram(_) => "histogram",
+            };
+            let _ = writeln!(output, "# HELP {} {}", metric.name, metric.help);
+            let _ = writeln!(output, "# TYPE {} {}", metric.name, kind);
+
+            for (labels, series) in &family.series {
+                match (series, metric.kind) {
+                    (Series::Counter(value), _) => {
+                        let _ = writeln!(output, "{}{} {}", metric.name, Self::format_labels(labels, None), value);
+                    }
+                    (Series::Histogram { buckets, sum, count }, MetricKind::Histogram(bounds)) => {
+                        let mut cumulative = 0;
+                        for (bound, observations) in bounds.iter().zip(buckets) {
+                            cumulative += observations;
+                            let le = bound.to_string();
+                            let _ = writeln!(
+                                output,
+                                "{}_bucket{} {}",
+                                metric.name,
+                                Self::format_labels(labels, Some(&le)),
+                                cumulative
+                            );
+                        }
+                        let _ = writeln!(
+                            output,
+                            "{}_bucket{} {}",
+                            metric.name,
+                            Self::format_labels(labels, Some("+Inf")),
+                            count
+                        );
+                        let _ = writeln!(output, "{}_sum{} {}", metric.name, Self::format_labels(labels, None), sum);
+                        let _ = writeln!(output, "{}_count{} {}", metric.name, Self::format_labels(labels, None), count);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        output
+    }
+
+    /// Applies `update` to the series identified by `metric` and `labels`,
+    /// creating it first if necessary.
+    fn update(&self, metric: &Metric, labels: &[(&str, &str)], update: impl FnOnce(&mut Series)) {
+        let mut families = self.families.lock().unwrap();
+        let family = families.entry(metric.name).or_insert_with(|| Family {
+            metric: *metric,
+            series: BTreeMap::new(),
+        });
+        let series = family
+            .series
+            .entry(Self::labels(labels))
+            .or_insert_with(|| match metric.kind {
+                MetricKind::Counter => Series::Counter(0),
+                MetricKind::Histogram(bounds) => Series::Histogram {
+                    buckets: vec![0; bounds.len()],
+                    sum: 0.0,
+                    count: 0,
+                },
+            });
+        update(series);
+    }
+
+    /// Converts borrowed labels into a sorted, owned label set.
+    fn labels(labels: &[(&str, &str)]) -> Labels {
+        let mut labels: Labels = labels
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        labels.sort();
+        labels
+    }
+
+    /// Formats a label set as `{name="value",...}`, optionally with an `le` label.
+    fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+        let mut pairs: Vec<String> = labels
+            .iter()
+            .map(|(name, value)| format!("{}=\"{}\"", name, Self::escape(value)))
+            .collect();
+        if let Some(le) = le {
+            pairs.push(format!("le=\"{}\"", le));
+        }
+        if pairs.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", pairs.join(","))
+        }
+    }
+
+    /// Escapes a label value as required by the text format.
+    fn escape(value: &str) -> String {
+        value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+    }
+}
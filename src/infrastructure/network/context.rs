@@ -0,0 +1,97 @@
+//! Describes the request a plugin hook is called for.
+//!
+//! Plugins only see the raw `reqwest` types, which know nothing about the
+//! `NetworkTarget` that produced them. The context carries the target's
+//! method and path template as well as timing information, so plugins can
+//! aggregate requests per endpoint instead of per concrete URL. The request id
+//! ties the request to the interaction that caused it.
+
+use std::time::{Duration, Instant};
+
+use super::http_method::HttpMethod;
+use super::request_id::RequestId;
+use super::target::NetworkTarget;
+
+/// Information about the request currently being processed.
+#[derive(Debug, Clone)]
+pub struct NetworkContext {
+    /// The HTTP method of the request
+    method: HttpMethod,
+    /// The path template of the target, e.g. `emby/Users/{user_id}`
+    path_template: String,
+    /// The id correlating the request with the current interaction
+    request_id: RequestId,
+    /// The attempt number, starting at 1 and increased by every retry
+    attempt: u32,
+    /// When the current attempt was started
+    started_at: Instant,
+    /// Whether the request body must not be logged
+    sensitive_body: bool,
+}
+
+impl NetworkContext {
+    /// Creates a context for the first attempt of a request.
+    /// 
+    /// The request id of the current interaction is used if there is one,
+    /// otherwise a new id is generated.
+    pub fn new(method: HttpMethod, path_template: impl Into<String>) -> Self {
+        Self {
+            method,
+            path_template: path_template.into(),
+            request_id: RequestId::current_or_generate(),
+            attempt: 1,
+            started_at: Instant::now(),
+            sensitive_body: false,
+        }
+    }
+
+    /// Creates a context for the first attempt of a request to `target`.
+    pub fn for_target<T: NetworkTarget>(target: &T) -> Self {
+        Self::new(target.method(), target.path_template()).with_sensitive_body(target.is_body_sensitive())
+    }
+
+    /// Marks the request body as secret, so plugins do not log it.
+    pub fn with_sensitive_body(mut self, sensitive: bool) -> Self {
+        self.sensitive_body = sensitive;
+        self
+    }
+
+    /// Returns a context for the next attempt of the same request.
+    pub fn next_attempt(&self) -> Self {
+        Self {
+            attempt: self.attempt + 1,
+            started_at: Instant::now(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the HTTP method of the request.
+    pub fn method(&self) -> HttpMethod {
+        self.method
+    }
+
+    /// Returns the path template of the target.
+    pub fn path_template(&self) -> &str {
+        &self.path_template
+    }
+
+    /// Returns the id correlating the request with the current interaction.
+    pub fn request_id(&self) -> &RequestId {
+        &self.request_id
+    }
+
+    /// Returns the attempt number, starting at 1.
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Returns whether the request body must not be logged.
+    pub fn has_sensitive_body(&self) -> bool {
+        self.sensitive_body
+    }
+
+    /// Returns the time elapsed since the current attempt was started.
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+}
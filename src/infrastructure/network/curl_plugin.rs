@@ -1,101 +1,414 @@
 //! Provides a curl-based logging plugin for network requests.
-//! 
+//!
 //! This module implements a plugin that logs network requests in curl command format,
 //! making it easy to reproduce requests for debugging or testing purposes.
+//! Secrets such as API keys, tokens and passwords are masked according to
+//! `RedactionRules` before anything is logged. Bodies of targets marked with
+//! `NetworkTarget::is_body_sensitive` are replaced as a whole.
+//!
+//! The generated commands are quoted for POSIX shells and can be pasted as-is.
+//! Optionally every request is also written to its own `.sh` or `.http` file,
+//! which is handy for attaching a reproducible request to a bug report. Dumps
+//! are written on tokio's blocking pool, so they never stall the request.
 
-use reqwest::{Request, Response, Error};
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::{debug_log, error_log};
+use chrono::Local;
+use reqwest::{Method, Request, Response, Error};
+
+use crate::{debug_log, error_log, warn_log};
+use super::context::NetworkContext;
 use super::plugin::NetworkPlugin;
+use super::redaction::{RedactionRules, REDACTED};
+
+/// Domain identifier for curl plugin logs
+const CURL_LOGGER_DOMAIN: &str = "[NETWORK]";
+
+/// The `-w` format printing curl's timing breakdown after the response
+const TIMING_FORMAT: &str = "\\nnamelookup: %{time_namelookup}s\\nconnect: %{time_connect}s\\n\
+    tls: %{time_appconnect}s\\nfirst byte: %{time_starttransfer}s\\ntotal: %{time_total}s\\n";
+
+/// The file format used when dumping requests to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurlDumpFormat {
+    /// An executable shell script containing the curl command
+    Shell,
+    /// A `.http` file as understood by the REST clients of common editors
+    Http,
+}
+
+impl CurlDumpFormat {
+    /// Returns the file extension of the format.
+    fn extension(&self) -> &'static str {
+        match self {
+            CurlDumpFormat::Shell => "sh",
+            CurlDumpFormat::Http => "http",
+        }
+    }
+}
+
+/// The `--data-binary` argument of binary bodies that were not dumped.
+///
+/// curl fails on the missing file instead of waiting for stdin.
+const BODY_NOT_CAPTURED: &str = "'@<body-not-captured>'";
+
+/// Where and how requests are dumped to disk.
+#[derive(Debug)]
+struct CurlDump {
+    /// Directory receiving the request files
+    directory: PathBuf,
+    /// Format of the request files
+    format: CurlDumpFormat,
+    /// Sequence number making file names unique within one process
+    sequence: AtomicU64,
+}
 
 /// A plugin that logs network requests in curl command format.
-/// 
+///
 /// This plugin implements the `Plugin` trait and provides detailed logging of:
 /// - Request details in curl command format
 /// - Response status codes
 /// - Error messages
-pub struct CurlPlugin;
+///
+/// By default the globally installed `RedactionRules` are applied; use
+/// `with_redaction` to configure the plugin separately. Note that dumped
+/// request files are redacted as well, so secrets have to be filled in
+/// before replaying them.
+#[derive(Debug)]
+pub struct CurlPlugin {
+    /// Rules overriding the global redaction rules
+    redaction: Option<RedactionRules>,
+    /// Whether `--compressed` is added to the command
+    compressed: bool,
+    /// Whether `-w` is added to print timing information
+    timing: bool,
+    /// Optional dump of every request into its own file
+    dump: Option<CurlDump>,
+}
 
-/// Domain identifier for curl plugin logs
-const CURL_LOGGER_DOMAIN: &str = "[NETWORK]";
+impl Default for CurlPlugin {
+    fn default() -> Self {
+        Self {
+            redaction: None,
+            compressed: true,
+            timing: false,
+            dump: None,
+        }
+    }
+}
 
 impl CurlPlugin {
+    /// Creates a plugin that applies the global redaction rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses the given redaction rules instead of the global ones.
+    pub fn with_redaction(mut self, rules: RedactionRules) -> Self {
+        self.redaction = Some(rules);
+        self
+    }
+
+    /// Enables or disables `--compressed`.
+    ///
+    /// Enabled by default, because the HTTP client accepts gzip responses and
+    /// curl would otherwise print them undecoded.
+    pub fn with_compressed(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
+    /// Enables or disables printing timing information via `-w`.
+    pub fn with_timing(mut self, timing: bool) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    /// Writes every request into its own file inside `directory`.
+    ///
+    /// Binary request bodies are written next to the request file and
+    /// referenced from it, so the request can be replayed exactly.
+    pub fn with_dump(mut self, directory: impl Into<PathBuf>, format: CurlDumpFormat) -> Self {
+        self.dump = Some(CurlDump {
+            directory: directory.into(),
+            format,
+            sequence: AtomicU64::new(0),
+        });
+        self
+    }
+
+    /// Returns the redaction rules in effect.
+    fn rules(&self) -> Cow<'_, RedactionRules> {
+        match &self.redaction {
+            Some(rules) => Cow::Borrowed(rules),
+            None => Cow::Owned(RedactionRules::global()),
+        }
+    }
+
     /// Logs the request details in curl command format.
-    fn on_request_impl(&self, request: &Request) {
-        let curl_command = CurlPlugin::request_to_curl(request);
-        let message = format!("Sending request: {}", curl_command);
-        debug_log!(CURL_LOGGER_DOMAIN, message);
+    fn on_request_impl(&self, context: &NetworkContext, request: &Request) {
+        let curl_command = match &self.dump {
+            Some(dump) => self.dump_request(dump, request, context.has_sensitive_body()),
+            None => self.request_to_curl_with_context(context, request),
+        };
+        debug_log!(CURL_LOGGER_DOMAIN, "Sending request: {}", curl_command);
     }
 
-    /// Logs the response status code.
-    fn on_response_impl(&self, response: &Response) {
-        let message = format!("Received response: {}", response.status());
-        debug_log!(CURL_LOGGER_DOMAIN, message);
+    /// Logs the response status code and latency.
+    fn on_response_impl(&self, context: &NetworkContext, response: &Response) {
+        debug_log!(
+            CURL_LOGGER_DOMAIN,
+            "Received response: {} from {} in {:?}",
+            response.status(),
+            self.rules().redact_url(response.url()),
+            context.elapsed()
+        );
     }
 
     /// Logs any errors that occur during the request.
-    fn on_error_impl(&self, error: &Error) {
-        let message = format!("Request occurred Error: {}", error);
-        error_log!(CURL_LOGGER_DOMAIN, message);
+    fn on_error_impl(&self, context: &NetworkContext, error: &Error) {
+        error_log!(
+            CURL_LOGGER_DOMAIN,
+            "Request occurred Error after {:?}: {}",
+            context.elapsed(),
+            self.rules().redact_error(error)
+        );
     }
 
     /// Converts a request into a curl command string.
-    /// 
+    ///
     /// This method generates a curl command that can be used to reproduce the request,
     /// including:
-    /// - HTTP method
+    /// - HTTP method (`-I` for HEAD requests)
     /// - URL
     /// - Headers
     /// - Request body (if present)
-    fn request_to_curl(request: &Request) -> String {
-        let mut curl_command = String::new();
-        curl_command.push_str("curl -X ");
-        curl_command.push_str(request.method().as_str());
-        curl_command.push_str(&format!(" '{}' ", request.url()));
-
-        for (name, value) in request.headers() {
-            let escaped_value = value
-                .to_str()
-                .unwrap()
-                .replace('"', "\\\"")
-                .replace("'", "\\'");
-            curl_command.push_str(&format!("-H \"{}: {}\" ", name, escaped_value));
+    ///
+    /// Secret query parameters, headers marked as sensitive or matched by the
+    /// redaction rules and secret JSON body fields are masked. Binary bodies
+    /// can only be replayed from a dump, see `with_dump`.
+    pub fn request_to_curl(&self, request: &Request) -> String {
+        self.curl_arguments(request, None, false).join(" ")
+    }
+
+    /// Converts a request into a curl command string, masking the whole body
+    /// if the context marks it as sensitive.
+    pub fn request_to_curl_with_context(&self, context: &NetworkContext, request: &Request) -> String {
+        self.curl_arguments(request, None, context.has_sensitive_body()).join(" ")
+    }
+
+    /// Builds the shell-quoted arguments of the curl command.
+    ///
+    /// Options are kept together with their values, so the arguments can be
+    /// joined with spaces or with line continuations. `body_file` is the file
+    /// a binary body was dumped to, if any. A `sensitive_body` is replaced by
+    /// the redaction placeholder.
+    fn curl_arguments(&self, request: &Request, body_file: Option<&Path>, sensitive_body: bool) -> Vec<String> {
+        let rules = self.rules();
+        let mut arguments = vec![String::from("curl")];
+        let mut comment = None;
+
+        // `-X HEAD` makes curl wait for a body that never arrives, `-I` does not.
+        if request.method() == Method::HEAD {
+            arguments.push(String::from("-I"));
+        } else {
+            arguments.push(format!("-X {}", request.method().as_str()));
+        }
+        arguments.push(shell_quote(&rules.redact_url(request.url())));
+
+        for (name, value) in Self::redacted_headers(&rules, request) {
+            arguments.push(format!("-H {}", shell_quote(&format!("{}: {}", name, value))));
         }
 
         if let Some(body) = request.body() {
-            let body_str = if let Some(text) = body.as_bytes().and_then(|bytes| {
-                std::str::from_utf8(bytes).ok()
-            }) {
-                text.replace('\'', "\\'").replace('"', "\\\"")
-            } else if let Some(chunk) = body.as_bytes() {
-                format!("Binary Data ({:?})", chunk.iter().take(50).map(|&b|
-                    format!("{:02X}", b)).collect::<Vec<_>>().join(" "))
-            } else {
-                String::from("Unknown Content")
-            };
-
-            if !body_str.is_empty() {
-                curl_command.push_str(&format!(" -d '{}'", body_str));
+            match body.as_bytes() {
+                Some([]) => {}
+                Some(_) if sensitive_body => arguments.push(format!("--data-raw {}", shell_quote(REDACTED))),
+                Some(bytes) => match std::str::from_utf8(bytes) {
+                    Ok(text) => {
+                        arguments.push(format!("--data-raw {}", shell_quote(&rules.redact_body(text))));
+                    }
+                    Err(_) => {
+                        match body_file {
+                            Some(path) => {
+                                let file = shell_quote(&format!("@{}", path.display()));
+                                arguments.push(format!("--data-binary {}", file));
+                            }
+                            None => {
+                                arguments.push(format!("--data-binary {}", BODY_NOT_CAPTURED));
+                                comment = Some(format!(
+                                    "# {} bytes of binary data, enable dumps to replay",
+                                    bytes.len()
+                                ));
+                            }
+                        }
+                    }
+                },
+                None => comment = Some(String::from("# streamed body omitted")),
             }
         }
 
-        curl_command
+        if self.compressed {
+            arguments.push(String::from("--compressed"));
+        }
+        if self.timing {
+            arguments.push(format!("-w {}", shell_quote(TIMING_FORMAT)));
+        }
+        arguments.extend(comment);
+
+        arguments
+    }
+
+    /// Returns the request headers with secret values masked.
+    fn redacted_headers<'a>(
+        rules: &RedactionRules,
+        request: &'a Request
+    ) -> Vec<(&'a str, Cow<'a, str>)> {
+        request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = if value.is_sensitive() || rules.is_secret_header(name.as_str()) {
+                    Cow::Borrowed(REDACTED)
+                } else {
+                    String::from_utf8_lossy(value.as_bytes())
+                };
+                (name.as_str(), value)
+            })
+            .collect()
     }
+
+    /// Writes the request into a new file and returns its curl command.
+    ///
+    /// Inside a tokio runtime the files are written on the blocking pool,
+    /// otherwise right away. Failures are logged.
+    fn dump_request(&self, dump: &CurlDump, request: &Request, sensitive_body: bool) -> String {
+        let sequence = dump.sequence.fetch_add(1, Ordering::Relaxed);
+        let stem = format!(
+            "{}-{:04}-{}",
+            Local::now().format("%Y%m%d-%H%M%S"),
+            sequence,
+            request.method().as_str().to_ascii_lowercase()
+        );
+
+        let binary_body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .filter(|bytes| !sensitive_body && std::str::from_utf8(bytes).is_err());
+        let body = binary_body.map(|bytes| (dump.directory.join(format!("{}.bin", stem)), bytes.to_vec()));
+        let body_file = body.as_ref().map(|(path, _)| path.as_path());
+
+        let arguments = self.curl_arguments(request, body_file, sensitive_body);
+        let content = match dump.format {
+            CurlDumpFormat::Shell => format!("#!/bin/sh\n{}\n", arguments.join(" \\\n  ")),
+            CurlDumpFormat::Http => self.request_to_http(request, body_file, sensitive_body),
+        };
+
+        let files = DumpFiles {
+            directory: dump.directory.clone(),
+            body,
+            request: (dump.directory.join(format!("{}.{}", stem, dump.format.extension())), content),
+            executable: dump.format == CurlDumpFormat::Shell,
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(move || files.write_or_warn());
+            }
+            Err(_) => files.write_or_warn(),
+        }
+
+        arguments.join(" ")
+    }
+
+    /// Converts a request into the `.http` file format.
+    fn request_to_http(&self, request: &Request, body_file: Option<&Path>, sensitive_body: bool) -> String {
+        let rules = self.rules();
+        let mut content = format!(
+            "{} {}\n",
+            request.method().as_str(),
+            rules.redact_url(request.url())
+        );
+
+        for (name, value) in Self::redacted_headers(&rules, request) {
+            content.push_str(&format!("{}: {}\n", name, value));
+        }
+
+        if let Some(bytes) = request.body().and_then(|body| body.as_bytes()) {
+            content.push('\n');
+            match (std::str::from_utf8(bytes), body_file) {
+                _ if sensitive_body => content.push_str(REDACTED),
+                (Ok(text), _) => content.push_str(&rules.redact_body(text)),
+                (Err(_), Some(path)) => content.push_str(&format!("< {}", path.display())),
+                (Err(_), None) => {}
+            }
+            content.push('\n');
+        }
+
+        content
+    }
+}
+
+/// The files of one dumped request.
+struct DumpFiles {
+    /// Directory receiving the files
+    directory: PathBuf,
+    /// The binary body and its path, if any
+    body: Option<(PathBuf, Vec<u8>)>,
+    /// The request file and its content
+    request: (PathBuf, String),
+    /// Whether the request file is a shell script
+    executable: bool,
+}
+
+impl DumpFiles {
+    /// Writes the files, logging a failure.
+    fn write_or_warn(self) {
+        if let Err(error) = self.write() {
+            warn_log!(CURL_LOGGER_DOMAIN, "Failed to dump request: {}", error);
+        }
+    }
+
+    /// Writes the files.
+    fn write(self) -> std::io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        if let Some((path, bytes)) = &self.body {
+            fs::write(path, bytes)?;
+        }
+        let (path, content) = &self.request;
+        fs::write(path, content)?;
+        #[cfg(unix)]
+        if self.executable {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(())
+    }
+}
+
+/// Quotes a string for POSIX shells.
+///
+/// Inside single quotes nothing is special except the single quote itself,
+/// which has to be written as `'\''` (close, escaped quote, reopen).
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
 }
 
 impl NetworkPlugin for CurlPlugin {
     /// Logs the request details before sending.
-    fn on_request(&self, request: &Request) {
-        self.on_request_impl(request);
+    fn on_request(&self, context: &NetworkContext, request: &Request) {
+        self.on_request_impl(context, request);
     }
 
     /// Logs the response details after receiving.
-    fn on_response(&self, response: &Response) {
-        self.on_response_impl(response);
+    fn on_response(&self, context: &NetworkContext, response: &Response) {
+        self.on_response_impl(context, response);
     }
 
     /// Logs any errors that occur.
-    fn on_error(&self, error: &Error) {
-        self.on_error_impl(error);
+    fn on_error(&self, context: &NetworkContext, error: &Error) {
+        self.on_error_impl(context, error);
     }
-}
\ No newline at end of file
+}
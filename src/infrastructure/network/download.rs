@@ -0,0 +1,86 @@
+//! Defines the options used when downloading a response body to a file.
+//!
+//! Downloads are performed by `NetworkProvider::download`. This module only
+//! describes how a download should behave: the size limit, whether partially
+//! downloaded files are resumed and who gets notified about progress.
+
+use std::fmt;
+
+/// Describes the progress of a running download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Number of bytes stored in the destination file so far,
+    /// including bytes from a resumed earlier download
+    pub downloaded: u64,
+    /// Total size of the file, if the server announced it
+    pub total: Option<u64>,
+}
+
+/// Callback invoked whenever a chunk has been written to disk.
+pub type ProgressCallback = Box<dyn Fn(DownloadProgress) + Send + Sync>;
+
+/// Configures how `NetworkProvider::download` behaves.
+///
+/// # Examples
+///
+/// ```rust
+/// use pilipili_bot::infrastructure::network::DownloadOptions;
+///
+/// let options = DownloadOptions::default()
+///     .with_max_size(10 * 1024 * 1024)
+///     .with_resume(true)
+///     .with_progress(|progress| println!("{} bytes", progress.downloaded));
+/// ```
+#[derive(Default)]
+pub struct DownloadOptions {
+    /// Maximum size of the complete file in bytes
+    pub(crate) max_size: Option<u64>,
+    /// Whether an existing partial file should be resumed via `Range`
+    pub(crate) resume: bool,
+    /// Optional progress callback
+    pub(crate) progress: Option<ProgressCallback>,
+}
+
+impl DownloadOptions {
+    /// Limits the size of the downloaded file to `max_size` bytes.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Enables or disables resuming a partially downloaded file.
+    ///
+    /// When enabled and the destination file already exists, only the missing
+    /// bytes are requested with a `Range` header. Servers that ignore the
+    /// header cause the file to be downloaded again from the start.
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Registers a callback that is invoked after every written chunk.
+    pub fn with_progress<F>(mut self, progress: F) -> Self
+    where
+        F: Fn(DownloadProgress) + Send + Sync + 'static,
+    {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Reports progress to the registered callback, if any.
+    pub(crate) fn report(&self, progress: DownloadProgress) {
+        if let Some(callback) = &self.progress {
+            callback(progress);
+        }
+    }
+}
+
+impl fmt::Debug for DownloadOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DownloadOptions")
+            .field("max_size", &self.max_size)
+            .field("resume", &self.resume)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
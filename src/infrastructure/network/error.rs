@@ -0,0 +1,81 @@
+//! Defines the error type returned by the network layer.
+//!
+//! `reqwest::Error` only covers transport failures. Requests can additionally
+//! fail because a target supplies an invalid header, and streaming and
+//! downloading can fail on the local file system or because a response is
+//! larger than the caller allows, so those cases are gathered here.
+
+use std::fmt::{self, Display};
+use std::io;
+
+use reqwest::StatusCode;
+
+use super::redaction::RedactionRules;
+
+/// Represents an error that occurred while performing a network operation.
+/// 
+/// The `Display` output masks secrets in request URLs according to the
+/// globally installed `RedactionRules`, so errors can be logged safely.
+#[derive(Debug)]
+pub enum NetworkError {
+    /// The request could not be sent or the response could not be read
+    Request(reqwest::Error),
+    /// Reading or writing a local file failed
+    Io(io::Error),
+    /// A request header has an invalid name or value
+    InvalidHeader {
+        /// The name of the rejected header
+        name: String,
+        /// Why the header was rejected
+        reason: String,
+    },
+    /// The server answered with a status code that cannot be handled
+    UnexpectedStatus(StatusCode),
+    /// The response body is larger than the configured limit
+    SizeLimitExceeded {
+        /// The maximum number of bytes allowed
+        limit: u64,
+    },
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Request(error) => {
+                write!(f, "request failed: {}", RedactionRules::global().redact_error(error))
+            }
+            NetworkError::Io(error) => write!(f, "io error: {}", error),
+            NetworkError::InvalidHeader { name, reason } => {
+                write!(f, "invalid header `{}`: {}", name, reason)
+            }
+            NetworkError::UnexpectedStatus(status) => {
+                write!(f, "unexpected response status: {}", status)
+            }
+            NetworkError::SizeLimitExceeded { limit } => {
+                write!(f, "response body exceeds the limit of {} bytes", limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NetworkError::Request(error) => Some(error),
+            NetworkError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for NetworkError {
+    fn from(error: reqwest::Error) -> Self {
+        NetworkError::Request(error)
+    }
+}
+
+impl From<io::Error> for NetworkError {
+    fn from(error: io::Error) -> Self {
+        NetworkError::Io(error)
+    }
+}
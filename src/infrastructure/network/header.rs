@@ -0,0 +1,192 @@
+//! Defines the headers a network target attaches to its requests.
+//!
+//! Header names and values often come from configuration or user input, so
+//! they are validated when the request is built instead of being unwrapped.
+//! Headers can be repeated and can be marked as sensitive, which lets logging
+//! plugins redact them.
+
+use std::borrow::Cow;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+use super::error::NetworkError;
+
+/// A single request header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkHeader {
+    /// The header name, either static or owned
+    name: Cow<'static, str>,
+    /// The header value
+    value: String,
+    /// Whether the value must not appear in logs
+    sensitive: bool,
+}
+
+impl NetworkHeader {
+    /// Creates a regular header.
+    pub fn new(name: impl Into<Cow<'static, str>>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            sensitive: false,
+        }
+    }
+
+    /// Creates a header whose value is redacted in logs, e.g. tokens or passwords.
+    pub fn sensitive(name: impl Into<Cow<'static, str>>, value: impl Into<String>) -> Self {
+        Self {
+            sensitive: true,
+            ..Self::new(name, value)
+        }
+    }
+
+    /// Returns the header name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the header value.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns whether the header value is sensitive.
+    pub fn is_sensitive(&self) -> bool {
+        self.sensitive
+    }
+}
+
+/// An ordered list of request headers.
+///
+/// Unlike a map, the same header name may appear several times; every
+/// occurrence is sent.
+///
+/// # Examples
+///
+/// ```rust
+/// use pilipili_bot::infrastructure::network::NetworkHeaders;
+///
+/// let headers = NetworkHeaders::new()
+///     .with("accept", "application/json")
+///     .with("accept-language", "en")
+///     .with("accept-language", "zh")
+///     .with_sensitive("X-Emby-Token", "secret");
+///
+/// assert_eq!(headers.len(), 4);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkHeaders {
+    headers: Vec<NetworkHeader>,
+}
+
+impl NetworkHeaders {
+    /// Creates an empty header list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a regular header.
+    pub fn with(mut self, name: impl Into<Cow<'static, str>>, value: impl Into<String>) -> Self {
+        self.push(NetworkHeader::new(name, value));
+        self
+    }
+
+    /// Appends a sensitive header.
+    pub fn with_sensitive(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.push(NetworkHeader::sensitive(name, value));
+        self
+    }
+
+    /// Appends a header.
+    pub fn push(&mut self, header: NetworkHeader) {
+        self.headers.push(header);
+    }
+
+    /// Returns the number of headers.
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Returns whether the list contains no headers.
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Returns an iterator over the headers.
+    pub fn iter(&self) -> impl Iterator<Item = &NetworkHeader> {
+        self.headers.iter()
+    }
+
+    /// Converts the headers into a `HeaderMap`.
+    ///
+    /// Repeated headers are appended rather than overwritten, and sensitive
+    /// headers are flagged with `HeaderValue::set_sensitive`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NetworkError::InvalidHeader` if a name is not a valid header
+    /// name or a value contains characters such as newlines.
+    pub fn to_header_map(&self) -> Result<HeaderMap, NetworkError> {
+        let mut header_map = HeaderMap::with_capacity(self.headers.len());
+        for header in &self.headers {
+            let name = HeaderName::from_bytes(header.name.as_bytes()).map_err(|error| {
+                NetworkError::InvalidHeader {
+                    name: header.name.to_string(),
+                    reason: error.to_string(),
+                }
+            })?;
+            let mut value = HeaderValue::from_str(&header.value).map_err(|error| {
+                NetworkError::InvalidHeader {
+                    name: header.name.to_string(),
+                    reason: error.to_string(),
+                }
+            })?;
+            value.set_sensitive(header.sensitive);
+            header_map.append(name, value);
+        }
+        Ok(header_map)
+    }
+}
+
+impl From<Vec<NetworkHeader>> for NetworkHeaders {
+    fn from(headers: Vec<NetworkHeader>) -> Self {
+        Self { headers }
+    }
+}
+
+impl<N, V> FromIterator<(N, V)> for NetworkHeaders
+where
+    N: Into<Cow<'static, str>>,
+    V: Into<String>,
+{
+    fn from_iter<I: IntoIterator<Item = (N, V)>>(iter: I) -> Self {
+        Self {
+            headers: iter
+                .into_iter()
+                .map(|(name, value)| NetworkHeader::new(name, value))
+                .collect(),
+        }
+    }
+}
+
+impl IntoIterator for NetworkHeaders {
+    type Item = NetworkHeader;
+    type IntoIter = std::vec::IntoIter<NetworkHeader>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.headers.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a NetworkHeaders {
+    type Item = &'a NetworkHeader;
+    type IntoIter = std::slice::Iter<'a, NetworkHeader>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.headers.iter()
+    }
+}
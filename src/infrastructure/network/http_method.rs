@@ -1,18 +1,27 @@
 //! Defines the supported HTTP methods for network requests.
-//! 
+//!
 //! This module provides an enum representing the standard HTTP methods
-//! supported by the network system.
+//! supported by the network system, together with conversions to and from
+//! `reqwest::Method` and plain strings.
 
 use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use reqwest::Method;
 
 /// Represents the HTTP method to be used in a network request.
-/// 
+///
 /// This enum provides the standard HTTP methods supported by the system:
 /// - `Get`: Retrieve a resource
 /// - `Post`: Create a new resource
-/// - `Put`: Update an existing resource
+/// - `Put`: Replace an existing resource
 /// - `Delete`: Remove a resource
-#[derive(Debug, Clone, Copy)]
+/// - `Patch`: Partially update an existing resource
+/// - `Head`: Retrieve the headers of a resource without its body
+/// - `Options`: Describe the communication options of a resource
+/// - `Trace`: Perform a message loop-back test
+/// - `Connect`: Establish a tunnel to the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum HttpMethod {
     /// HTTP GET method
     Get,
@@ -22,19 +31,138 @@ pub enum HttpMethod {
     Put,
     /// HTTP DELETE method
     Delete,
+    /// HTTP PATCH method
+    Patch,
+    /// HTTP HEAD method
+    Head,
+    /// HTTP OPTIONS method
+    Options,
+    /// HTTP TRACE method
+    Trace,
+    /// HTTP CONNECT method
+    Connect,
 }
 
-impl Display for HttpMethod {
-    /// Formats the HTTP method as a string.
-    /// 
+impl HttpMethod {
     /// Returns the HTTP method name in uppercase, as per HTTP specification.
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let str = match self {
+    pub fn as_str(&self) -> &'static str {
+        match self {
             HttpMethod::Get => "GET",
             HttpMethod::Post => "POST",
             HttpMethod::Put => "PUT",
             HttpMethod::Delete => "DELETE",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Options => "OPTIONS",
+            HttpMethod::Trace => "TRACE",
+            HttpMethod::Connect => "CONNECT",
+        }
+    }
+
+    /// Returns whether the method is safe, i.e. read-only (RFC 9110, 9.2.1).
+    pub fn is_safe(&self) -> bool {
+        matches!(
+            self,
+            HttpMethod::Get | HttpMethod::Head | HttpMethod::Options | HttpMethod::Trace
+        )
+    }
+
+    /// Returns whether sending the request several times has the same effect
+    /// as sending it once (RFC 9110, 9.2.2).
+    ///
+    /// Only idempotent requests may be retried after the server could have
+    /// received them.
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, HttpMethod::Put | HttpMethod::Delete)
+    }
+}
+
+impl Display for HttpMethod {
+    /// Formats the HTTP method as a string.
+    ///
+    /// Returns the HTTP method name in uppercase, as per HTTP specification.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The error returned when a string or `reqwest::Method` is not a known HTTP method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseHttpMethodError {
+    /// The rejected method name
+    method: String,
+}
+
+impl Display for ParseHttpMethodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported HTTP method: {}", self.method)
+    }
+}
+
+impl std::error::Error for ParseHttpMethodError {}
+
+impl FromStr for HttpMethod {
+    type Err = ParseHttpMethodError;
+
+    /// Parses a method name case-insensitively, e.g. `"patch"` or `"PATCH"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let method = match s.to_ascii_uppercase().as_str() {
+            "GET" => HttpMethod::Get,
+            "POST" => HttpMethod::Post,
+            "PUT" => HttpMethod::Put,
+            "DELETE" => HttpMethod::Delete,
+            "PATCH" => HttpMethod::Patch,
+            "HEAD" => HttpMethod::Head,
+            "OPTIONS" => HttpMethod::Options,
+            "TRACE" => HttpMethod::Trace,
+            "CONNECT" => HttpMethod::Connect,
+            _ => return Err(ParseHttpMethodError { method: s.to_owned() }),
         };
-        write!(f, "{}", str)
+        Ok(method)
+    }
+}
+
+impl From<HttpMethod> for Method {
+    fn from(method: HttpMethod) -> Self {
+        match method {
+            HttpMethod::Get => Method::GET,
+            HttpMethod::Post => Method::POST,
+            HttpMethod::Put => Method::PUT,
+            HttpMethod::Delete => Method::DELETE,
+            HttpMethod::Patch => Method::PATCH,
+            HttpMethod::Head => Method::HEAD,
+            HttpMethod::Options => Method::OPTIONS,
+            HttpMethod::Trace => Method::TRACE,
+            HttpMethod::Connect => Method::CONNECT,
+        }
+    }
+}
+
+impl TryFrom<&Method> for HttpMethod {
+    type Error = ParseHttpMethodError;
+
+    /// Converts a `reqwest::Method`, rejecting extension methods.
+    fn try_from(method: &Method) -> Result<Self, Self::Error> {
+        let method = match *method {
+            Method::GET => HttpMethod::Get,
+            Method::POST => HttpMethod::Post,
+            Method::PUT => HttpMethod::Put,
+            Method::DELETE => HttpMethod::Delete,
+            Method::PATCH => HttpMethod::Patch,
+            Method::HEAD => HttpMethod::Head,
+            Method::OPTIONS => HttpMethod::Options,
+            Method::TRACE => HttpMethod::Trace,
+            Method::CONNECT => HttpMethod::Connect,
+            _ => return Err(ParseHttpMethodError { method: method.to_string() }),
+        };
+        Ok(method)
+    }
+}
+
+impl TryFrom<Method> for HttpMethod {
+    type Error = ParseHttpMethodError;
+
+    fn try_from(method: Method) -> Result<Self, Self::Error> {
+        HttpMethod::try_from(&method)
     }
 }
@@ -0,0 +1,101 @@
+//! Provides a plugin recording request metrics.
+//!
+//! Requests are labelled by method, host and the target's path template, so
+//! `emby/Users/{user_id}` is one time series no matter how many users exist.
+
+use std::sync::Arc;
+
+use reqwest::{Request, Response, Error, StatusCode};
+
+use crate::infrastructure::metrics::{Metric, MetricsRegistry, LATENCY_BUCKETS};
+use super::context::NetworkContext;
+use super::plugin::NetworkPlugin;
+
+/// Number of completed requests, by method, host, target and status class.
+pub const HTTP_REQUESTS: Metric = Metric::counter(
+    "pilipili_http_requests_total",
+    "Number of completed HTTP requests, by method, host, target and status class.",
+);
+
+/// Request latency, by method, host and target.
+pub const HTTP_REQUEST_DURATION: Metric = Metric::histogram(
+    "pilipili_http_request_duration_seconds",
+    "HTTP request latency in seconds, by method, host and target.",
+    LATENCY_BUCKETS,
+);
+
+/// Number of `429 Too Many Requests` responses, by host and target.
+pub const HTTP_RATE_LIMITED: Metric = Metric::counter(
+    "pilipili_http_rate_limited_total",
+    "Number of HTTP requests rejected with 429 Too Many Requests, by host and target.",
+);
+
+/// A plugin that records request counts, latencies and status classes.
+#[derive(Debug, Clone)]
+pub struct MetricsPlugin {
+    registry: Arc<MetricsRegistry>,
+}
+
+impl Default for MetricsPlugin {
+    fn default() -> Self {
+        Self::new(MetricsRegistry::global())
+    }
+}
+
+impl MetricsPlugin {
+    /// Creates a plugin recording into `registry`.
+    pub fn new(registry: Arc<MetricsRegistry>) -> Self {
+        Self { registry }
+    }
+
+    /// Records one finished attempt.
+    fn record(&self, context: &NetworkContext, host: &str, status_class: &str) {
+        let method = context.method().as_str();
+        let target = context.path_template();
+
+        self.registry.increment(
+            &HTTP_REQUESTS,
+            &[("method", method), ("host", host), ("target", target), ("status", status_class)],
+        );
+        self.registry.observe(
+            &HTTP_REQUEST_DURATION,
+            &[("method", method), ("host", host), ("target", target)],
+            context.elapsed().as_secs_f64(),
+        );
+    }
+
+    /// Returns the status class label, e.g. `2xx`.
+    fn status_class(status: StatusCode) -> &'static str {
+        match status.as_u16() {
+            100..=199 => "1xx",
+            200..=299 => "2xx",
+            300..=399 => "3xx",
+            400..=499 => "4xx",
+            _ => "5xx",
+        }
+    }
+}
+
+impl NetworkPlugin for MetricsPlugin {
+    /// Requests are recorded once they completed.
+    fn on_request(&self, _context: &NetworkContext, _request: &Request) {}
+
+    /// Records the status class and latency of the response.
+    fn on_response(&self, context: &NetworkContext, response: &Response) {
+        let host = response.url().host_str().unwrap_or_default();
+        self.record(context, host, Self::status_class(response.status()));
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            self.registry.increment(
+                &HTTP_RATE_LIMITED,
+                &[("host", host), ("target", context.path_template())],
+            );
+        }
+    }
+
+    /// Records the failed request with the status class `error`.
+    fn on_error(&self, context: &NetworkContext, error: &Error) {
+        let host = error.url().and_then(|url| url.host_str()).unwrap_or_default();
+        self.record(context, host, "error");
+    }
+}
@@ -1,25 +1,47 @@
 //! A flexible and extensible network request handling system.
 //! 
 //! This module provides a plugin-based architecture for making HTTP requests with the following features:
-//! - Support for different HTTP methods
+//! - Support for all standard HTTP methods
+//! - Idempotency-aware retries
 //! - Plugin system for request/response processing
 //! - Curl-based implementation
+//! - Redaction of secrets in network logs
+//! - Request metrics per target
+//! - Request correlation ids and tracing spans
 //! - Task-based request handling
+//! - Streaming responses and file downloads
 //! 
 //! # Examples
 //! 
-//! ```rust
-//! use infrastructure::network::{HttpMethod, NetworkProvider, NetworkTarget};
+//! ```rust,no_run
+//! use pilipili_bot::infrastructure::network::{HttpMethod, NetworkProvider, NetworkTarget, NetworkTask};
 //! 
-//! // Create a network target
-//! let target = NetworkTarget::new("https://api.example.com");
+//! // Describe a network target
+//! struct Status;
+//! 
+//! impl NetworkTarget for Status {
+//!     fn base_url(&self) -> String {
+//!         "https://api.example.com".to_string()
+//!     }
+//! 
+//!     fn path(&self) -> String {
+//!         "status".to_string()
+//!     }
+//! 
+//!     fn method(&self) -> HttpMethod {
+//!         HttpMethod::Get
+//!     }
+//! 
+//!     fn task(&self) -> NetworkTask {
+//!         NetworkTask::RequestPlain
+//!     }
+//! }
 //! 
 //! // Make a request
-//! let response = NetworkProvider::new()
-//!     .method(HttpMethod::Get)
-//!     .target(target)
-//!     .execute()
-//!     .await?;
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let response = NetworkProvider::new(vec![]).send_request(&Status).await?;
+//! # Ok(())
+//! # }
 //! ```
 
 pub mod http_method;
@@ -28,11 +50,29 @@ pub mod target;
 pub mod provider;
 pub mod plugin;
 pub mod curl_plugin;
+pub mod error;
+pub mod stream;
+pub mod download;
+pub mod retry;
+pub mod header;
+pub mod redaction;
+pub mod context;
+pub mod metrics_plugin;
+pub mod request_id;
 
 // Re-export commonly used types
-pub use http_method::HttpMethod;
+pub use http_method::{HttpMethod, ParseHttpMethodError};
 pub use task::NetworkTask;
 pub use target::NetworkTarget;
 pub use provider::NetworkProvider;
 pub use plugin::NetworkPlugin;
-pub use curl_plugin::CurlPlugin;
\ No newline at end of file
+pub use curl_plugin::{CurlDumpFormat, CurlPlugin};
+pub use error::NetworkError;
+pub use stream::NetworkStream;
+pub use download::{DownloadOptions, DownloadProgress};
+pub use retry::RetryPolicy;
+pub use header::{NetworkHeader, NetworkHeaders};
+pub use redaction::RedactionRules;
+pub use context::NetworkContext;
+pub use metrics_plugin::MetricsPlugin;
+pub use request_id::RequestId;
\ No newline at end of file
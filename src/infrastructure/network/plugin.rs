@@ -5,25 +5,31 @@
 
 use reqwest::{Request, Response, Error};
 
+use super::context::NetworkContext;
+
 /// Defines the interface for network request/response plugins.
 /// 
 /// This trait provides methods that are called at different stages of a network request:
 /// - Before the request is sent
 /// - After a response is received
 /// - When an error occurs
-pub trait NetworkPlugin {
+/// 
+/// Every hook receives the `NetworkContext` of the request, which identifies
+/// the target it was built from and measures the time spent on it. Plugins
+/// are shared between tasks and therefore have to be `Send + Sync`.
+pub trait NetworkPlugin: Send + Sync {
     /// Called before a request is sent.
     /// 
     /// This method allows plugins to inspect or modify the request before it is sent.
-    fn on_request(&self, request: &Request);
+    fn on_request(&self, context: &NetworkContext, request: &Request);
 
     /// Called after a response is received.
     /// 
     /// This method allows plugins to inspect or process the response.
-    fn on_response(&self, response: &Response);
+    fn on_response(&self, context: &NetworkContext, response: &Response);
 
     /// Called when an error occurs during the request.
     /// 
     /// This method allows plugins to handle or log errors.
-    fn on_error(&self, error: &Error);
+    fn on_error(&self, context: &NetworkContext, error: &Error);
 }
\ No newline at end of file
@@ -5,8 +5,8 @@
 //! 
 //! # Basic Usage
 //! 
-//! ```rust
-//! use infrastructure::network::{Provider, HttpMethod, Task, TargetType};
+//! ```rust,no_run
+//! use pilipili_bot::infrastructure::network::{HttpMethod, NetworkProvider, NetworkTarget, NetworkTask};
 //! 
 //! // 1. Create a simple target struct
 //! struct SimpleTarget {
@@ -15,8 +15,8 @@
 //!     method: HttpMethod,
 //! }
 //! 
-//! // 2. Implement the TargetType trait
-//! impl TargetType for SimpleTarget {
+//! // 2. Implement the NetworkTarget trait
+//! impl NetworkTarget for SimpleTarget {
 //!     fn base_url(&self) -> String {
 //!         self.base_url.clone()
 //!     }
@@ -29,14 +29,14 @@
 //!         self.method.clone()
 //!     }
 //! 
-//!     fn task(&self) -> Task {
-//!         Task::RequestPlain  // Simple request with URL only
+//!     fn task(&self) -> NetworkTask {
+//!         NetworkTask::RequestPlain  // Simple request with URL only
 //!     }
 //! }
 //! 
-//! // 3. Create a Provider instance and send the request
+//! // 3. Create a NetworkProvider instance and send the request
 //! async fn example() -> Result<(), Box<dyn std::error::Error>> {
-//!     let provider = Provider::new(vec![]);  // Can add plugins here
+//!     let provider = NetworkProvider::new(vec![]);  // Can add plugins here
 //!     
 //!     let target = SimpleTarget {
 //!         base_url: "https://api.example.com".to_string(),
@@ -64,6 +64,44 @@
 //!    - Sends URL query parameters
 //!    - Suitable for GET requests with URL parameters
 //! 
+//! # Streaming and Downloading
+//! 
+//! Large binaries (images, subtitle files, exported logs) should not be buffered
+//! in memory. `send_request_stream` returns the body chunk by chunk, and
+//! `download` writes it straight into a file:
+//! 
+//! ```rust,no_run
+//! use pilipili_bot::infrastructure::network::{DownloadOptions, NetworkProvider, NetworkTarget};
+//! 
+//! async fn poster(provider: &NetworkProvider, target: &impl NetworkTarget) -> Result<u64, Box<dyn std::error::Error>> {
+//!     let options = DownloadOptions::default()
+//!         .with_max_size(20 * 1024 * 1024)
+//!         .with_resume(true)
+//!         .with_progress(|progress| println!("{:?}", progress));
+//! 
+//!     Ok(provider.download(target, "poster.jpg", options).await?)
+//! }
+//! ```
+//! 
+//! # Retries
+//! 
+//! Failed requests can be retried with a `RetryPolicy`. Connection failures are
+//! retried for every method; timeouts, rate limiting and gateway errors only for
+//! idempotent methods (GET, HEAD, OPTIONS, TRACE, PUT, DELETE).
+//! 
+//! ```rust
+//! use pilipili_bot::infrastructure::network::{NetworkProvider, RetryPolicy};
+//! 
+//! let provider = NetworkProvider::new(vec![]).with_retry_policy(RetryPolicy::new(3));
+//! ```
+//! 
+//! # Tracing
+//! 
+//! Every request runs in an `http_request` span with the method, the target's
+//! path template, the request id, the final status and the latency. Inside an
+//! interaction scoped with `RequestId::scope`, all requests share the id of
+//! the interaction and send it as `X-Request-Id`.
+//! 
 //! # Plugin System
 //! 
 //! Provider supports a plugin system that allows custom processing of:
@@ -72,35 +110,53 @@
 //! - Error handling
 //! 
 //! ```rust
-//! use infrastructure::network::plugin::Plugin;
+//! use pilipili_bot::infrastructure::network::{NetworkContext, NetworkPlugin, NetworkProvider};
+//! use reqwest::{Error, Request, Response};
 //! 
 //! struct LoggingPlugin;
 //! 
-//! impl Plugin for LoggingPlugin {
-//!     fn on_request(&self, request: &Request) {
+//! impl NetworkPlugin for LoggingPlugin {
+//!     fn on_request(&self, context: &NetworkContext, request: &Request) {
 //!         println!("Sending request: {:?}", request);
 //!     }
 //! 
-//!     fn on_response(&self, response: &Response) {
-//!         println!("Received response: {:?}", response);
+//!     fn on_response(&self, context: &NetworkContext, response: &Response) {
+//!         println!("Received response after {:?}: {:?}", context.elapsed(), response);
 //!     }
 //! 
-//!     fn on_error(&self, error: &Error) {
+//!     fn on_error(&self, context: &NetworkContext, error: &Error) {
 //!         println!("Error occurred: {:?}", error);
 //!     }
 //! }
 //! 
-//! let provider = Provider::new(vec![Box::new(LoggingPlugin)]);
+//! let provider = NetworkProvider::new(vec![Box::new(LoggingPlugin)]);
 //! ```
 
-use reqwest::{Client, Method};
+use std::path::Path;
+use std::time::Instant;
+
+use reqwest::header::RANGE;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
 use once_cell::sync::Lazy;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tracing::Instrument;
+
+use crate::warn_log;
 
-use super::http_method::HttpMethod;
+use super::context::NetworkContext;
+use super::download::{DownloadOptions, DownloadProgress};
+use super::error::NetworkError;
+use super::request_id::REQUEST_ID_HEADER;
+use super::stream::NetworkStream;
 use super::plugin::NetworkPlugin;
+use super::retry::RetryPolicy;
 use super::task::NetworkTask;
 use super::target::NetworkTarget;
 
+/// Domain identifier for provider logs
+const PROVIDER_LOGGER_DOMAIN: &str = "[NETWORK]";
+
 /// A static HTTP client instance configured with default settings.
 /// 
 /// The client is configured to:
@@ -128,6 +184,8 @@ static CLIENT: Lazy<Client> = Lazy::new(|| {
 pub struct NetworkProvider {
     /// List of plugins to be executed during request lifecycle
     plugins: Vec<Box<dyn NetworkPlugin>>,
+    /// Policy deciding whether failed requests are sent again
+    retry_policy: RetryPolicy,
 }
 
 impl NetworkProvider {
@@ -137,7 +195,19 @@ impl NetworkProvider {
     /// 
     /// * `plugins` - Vector of plugins to be used for request processing
     pub fn new(plugins: Vec<Box<dyn NetworkPlugin>>) -> Self {
-        Self { plugins }
+        Self {
+            plugins,
+            retry_policy: RetryPolicy::none(),
+        }
+    }
+
+    /// Sets the policy used to retry failed requests.
+    /// 
+    /// Requests are only retried when this is safe for their HTTP method,
+    /// see `RetryPolicy` for details.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
     }
 
     /// Sends a network request to the specified target.
@@ -154,30 +224,158 @@ impl NetworkProvider {
     /// 
     /// # Returns
     /// 
-    /// A `Result` containing either the response or an error
+    /// A `Result` containing either the response or an error. Invalid target
+    /// headers are reported as `NetworkError::InvalidHeader` without sending
+    /// anything.
     pub async fn send_request<T: NetworkTarget>(
         &self, 
         target: &T
-    ) -> Result<reqwest::Response, reqwest::Error> {
+    ) -> Result<reqwest::Response, NetworkError> {
+        let context = NetworkContext::for_target(target);
+        let request = self.build_request(target, &context)?;
+        Ok(self.execute(context, request).await?)
+    }
+
+    /// Sends a network request and returns the response body as a stream.
+    /// 
+    /// The body is not buffered; chunks are read on demand through
+    /// `NetworkStream::next_chunk`. Use `NetworkStream::with_max_size` to
+    /// protect against unexpectedly large responses.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The target to send the request to
+    /// 
+    /// # Returns
+    /// 
+    /// A `Result` containing either the response stream or an error
+    pub async fn send_request_stream<T: NetworkTarget>(
+        &self,
+        target: &T
+    ) -> Result<NetworkStream, NetworkError> {
+        let response = self.send_request(target).await?;
+        Ok(NetworkStream::new(response))
+    }
+
+    /// Downloads the response body of the target into a file.
+    /// 
+    /// The body is written chunk by chunk, so arbitrarily large files can be
+    /// downloaded without buffering them in memory. Depending on `options`
+    /// the download:
+    /// - resumes an existing partial file with a `Range` request
+    /// - aborts once the file would grow beyond the size limit
+    /// - reports progress after every written chunk
+    /// 
+    /// A file that exceeds the size limit is removed again. A resumed
+    /// download is instead truncated back to the part that was already on
+    /// disk, so data the caller meant to keep is never lost.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target` - The target to download from
+    /// * `path` - The destination file
+    /// * `options` - Size limit, resume and progress settings
+    /// 
+    /// # Returns
+    /// 
+    /// A `Result` containing the final size of the file or an error
+    pub async fn download<T: NetworkTarget>(
+        &self,
+        target: &T,
+        path: impl AsRef<Path>,
+        options: DownloadOptions,
+    ) -> Result<u64, NetworkError> {
+        let path = path.as_ref();
+
+        let mut offset = 0;
+        if options.resume
+            && let Ok(metadata) = fs::metadata(path).await
+        {
+            offset = metadata.len();
+        }
+
+        let context = NetworkContext::for_target(target);
+        let mut request = self.build_request(target, &context)?;
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+
+        let response = self.execute(context, request).await?;
+        let status = response.status();
+
+        let mut file = match status {
+            StatusCode::PARTIAL_CONTENT if offset > 0 => {
+                OpenOptions::new().append(true).open(path).await?
+            }
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                // The file on disk is already complete.
+                return Ok(offset);
+            }
+            status if status.is_success() => {
+                offset = 0;
+                File::create(path).await?
+            }
+            status => return Err(NetworkError::UnexpectedStatus(status)),
+        };
+
+        let total = response.content_length().map(|length| length + offset);
+        let mut stream = NetworkStream::new(response);
+        if let Some(max_size) = options.max_size {
+            let remaining = max_size.saturating_sub(offset);
+            stream = match stream.with_max_size(remaining) {
+                Ok(stream) => stream,
+                Err(_) => {
+                    discard(file, path, offset).await?;
+                    return Err(NetworkError::SizeLimitExceeded { limit: max_size });
+                }
+            };
+        }
+
+        loop {
+            let chunk = match stream.next_chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(NetworkError::SizeLimitExceeded { .. }) => {
+                    discard(file, path, offset).await?;
+                    let limit = options.max_size.unwrap_or_default();
+                    return Err(NetworkError::SizeLimitExceeded { limit });
+                }
+                Err(error) => return Err(error),
+            };
+
+            file.write_all(&chunk).await?;
+            options.report(DownloadProgress {
+                downloaded: offset + stream.received(),
+                total,
+            });
+        }
+
+        file.flush().await?;
+        Ok(offset + stream.received())
+    }
+
+    /// Builds a request for the specified target.
+    /// 
+    /// The URL is assembled from the target's base URL and path, and the
+    /// target's headers and task are applied to the request. The request id
+    /// of the context is sent as `X-Request-Id`.
+    fn build_request<T: NetworkTarget>(
+        &self,
+        target: &T,
+        context: &NetworkContext
+    ) -> Result<RequestBuilder, NetworkError> {
         let url = format!(
             "{}/{}",
             target.base_url().trim_end_matches('/'),
             target.path().trim_start_matches('/')
         );
 
-        let mut request = CLIENT.request(match target.method() {
-            HttpMethod::Get => Method::GET,
-            HttpMethod::Post => Method::POST,
-            HttpMethod::Put => Method::PUT,
-            HttpMethod::Delete => Method::DELETE,
-        }, &url);
+        let mut request = CLIENT
+            .request(Method::from(target.method()), &url)
+            .header(REQUEST_ID_HEADER, context.request_id().as_str());
 
         if let Some(headers) = target.headers() {
-            let mut header_map = reqwest::header::HeaderMap::new();
-            for (key, value) in headers {
-                header_map.insert(key, value.parse().unwrap());
-            }
-            request = request.headers(header_map);
+            request = request.headers(headers.to_header_map()?);
         }
 
         match target.task() {
@@ -193,11 +391,101 @@ impl NetworkProvider {
             }
         }
 
-        for plugin in &self.plugins {
-            if let Some(cloned_request) = request.try_clone() {
-                if let Ok(built_request) = cloned_request.build() {
-                    plugin.on_request(&built_request);
+        Ok(request)
+    }
+
+    /// Sends a built request and retries it according to the retry policy.
+    /// 
+    /// The request runs inside an `http_request` span, a child of the span of
+    /// the interaction that caused it, which records the method, target,
+    /// request id and, once finished, the status and total latency.
+    async fn execute(
+        &self,
+        context: NetworkContext,
+        request: RequestBuilder
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let span = tracing::debug_span!(
+            "http_request",
+            method = %context.method(),
+            target = %context.path_template(),
+            request_id = %context.request_id(),
+            status = tracing::field::Empty,
+            attempts = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+        );
+        let started_at = Instant::now();
+
+        let (response, attempts) = self
+            .execute_with_retries(context, request)
+            .instrument(span.clone())
+            .await;
+
+        span.record("attempts", attempts);
+        span.record("latency_ms", started_at.elapsed().as_millis() as u64);
+        match &response {
+            Ok(res) => span.record("status", res.status().as_u16()),
+            Err(_) => span.record("status", "error"),
+        };
+
+        response
+    }
+
+    /// Sends a built request and retries it according to the retry policy.
+    /// 
+    /// Requests whose body cannot be cloned (e.g. streams) are never retried.
+    /// Returns the final result together with the number of attempts.
+    async fn execute_with_retries(
+        &self,
+        mut context: NetworkContext,
+        mut request: RequestBuilder
+    ) -> (Result<reqwest::Response, reqwest::Error>, u32) {
+        let method = context.method();
+        let mut retry = 0;
+        loop {
+            let next_request = if retry < self.retry_policy.max_retries() {
+                request.try_clone()
+            } else {
+                None
+            };
+
+            let response = self.execute_once(&context, request).await;
+            let should_retry = match &response {
+                Ok(res) => self.retry_policy.should_retry_status(method, res.status()),
+                Err(err) => self.retry_policy.should_retry_error(method, err),
+            };
+
+            match next_request {
+                Some(next_request) if should_retry => {
+                    retry += 1;
+                    let delay = self.retry_policy.delay(retry);
+                    warn_log!(
+                        PROVIDER_LOGGER_DOMAIN,
+                        "Retrying {} request ({}/{}) in {:?}",
+                        method,
+                        retry,
+                        self.retry_policy.max_retries(),
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    request = next_request;
+                    context = context.next_attempt();
                 }
+                _ => return (response, context.attempt()),
+            }
+        }
+    }
+
+    /// Sends a built request once and runs the plugins around it.
+    async fn execute_once(
+        &self,
+        context: &NetworkContext,
+        request: RequestBuilder
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        for plugin in &self.plugins {
+            if let Some(cloned_request) = request.try_clone()
+                && let Ok(built_request) = cloned_request.build()
+            {
+                plugin.on_request(context, &built_request);
             }
         }
 
@@ -205,16 +493,30 @@ impl NetworkProvider {
         match &response {
             Ok(res) => {
                 for plugin in &self.plugins {
-                    plugin.on_response(res);
+                    plugin.on_response(context, res);
                 }
             }
             Err(err) => {
                 for plugin in &self.plugins {
-                    plugin.on_error(err);
+                    plugin.on_error(context, err);
                 }
             }
         }
 
         response
     }
-}
\ No newline at end of file
+}
+
+/// Discards what a download wrote to `file`.
+///
+/// A fresh download (`offset` 0) is removed, a resumed one is truncated back
+/// to the `offset` bytes that were on disk before.
+async fn discard(file: File, path: &Path, offset: u64) -> Result<(), NetworkError> {
+    if offset > 0 {
+        file.set_len(offset).await?;
+        return Ok(());
+    }
+    drop(file);
+    fs::remove_file(path).await?;
+    Ok(())
+}
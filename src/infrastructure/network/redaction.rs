@@ -0,0 +1,239 @@
+//! Masks secrets before network requests end up in log files.
+//!
+//! URLs, headers and bodies of requests to Emby or Telegram regularly carry
+//! credentials: the Emby `api_key` query parameter, the `X-Emby-Token` header
+//! or the Telegram bot token in the URL path. `RedactionRules` describes which
+//! parts of a request are secret and replaces them with a placeholder.
+//! More names can be masked with the `[logger.redaction]` configuration
+//! section, which the bot installs as the global rules.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use pilipili_bot::infrastructure::network::RedactionRules;
+//!
+//! let rules = RedactionRules::default().with_query_param("session");
+//! let url = "https://emby.example.com/emby/Users?api_key=secret&session=abc".parse().unwrap();
+//!
+//! assert_eq!(
+//!     rules.redact_url(&url),
+//!     "https://emby.example.com/emby/Users?api_key=[REDACTED]&session=[REDACTED]"
+//! );
+//! ```
+
+use std::borrow::Cow;
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use reqwest::Url;
+use serde_json::Value;
+
+/// Placeholder that replaces redacted values.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// The rules installed for all network logs that are not configured explicitly.
+static GLOBAL_RULES: Lazy<RwLock<RedactionRules>> =
+    Lazy::new(|| RwLock::new(RedactionRules::default()));
+
+/// Describes which parts of a request contain secrets.
+///
+/// All names are matched case-insensitively. The default rules mask the
+/// credentials used by Emby, Jellyfin and Telegram.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionRules {
+    /// Query parameters whose values are masked
+    query_params: Vec<String>,
+    /// Headers whose values are masked
+    headers: Vec<String>,
+    /// JSON fields whose values are masked, at any nesting depth
+    json_fields: Vec<String>,
+    /// Whether `/bot<token>/` path segments of the Telegram Bot API are masked
+    telegram_bot_token: bool,
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        Self::empty()
+            .with_query_param("api_key")
+            .with_query_param("X-Emby-Token")
+            .with_query_param("access_token")
+            .with_header("authorization")
+            .with_header("proxy-authorization")
+            .with_header("cookie")
+            .with_header("set-cookie")
+            .with_header("X-Emby-Token")
+            .with_header("X-Emby-Authorization")
+            .with_header("X-MediaBrowser-Token")
+            .with_json_field("password")
+            .with_json_field("Pw")
+            .with_json_field("CurrentPw")
+            .with_json_field("NewPw")
+            .with_json_field("AccessToken")
+            .with_json_field("api_key")
+            .with_telegram_bot_token(true)
+    }
+}
+
+impl RedactionRules {
+    /// Creates rules that mask nothing.
+    pub fn empty() -> Self {
+        Self {
+            query_params: Vec::new(),
+            headers: Vec::new(),
+            json_fields: Vec::new(),
+            telegram_bot_token: false,
+        }
+    }
+
+    /// Returns a copy of the globally installed rules.
+    pub fn global() -> Self {
+        GLOBAL_RULES.read().unwrap().clone()
+    }
+
+    /// Installs the rules used by every network log that has no explicit rules.
+    pub fn install_global(rules: RedactionRules) {
+        *GLOBAL_RULES.write().unwrap() = rules;
+    }
+
+    /// Masks the value of the given query parameter.
+    pub fn with_query_param(mut self, name: &str) -> Self {
+        self.query_params.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Masks the value of the given header.
+    pub fn with_header(mut self, name: &str) -> Self {
+        self.headers.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Masks the value of the given JSON field.
+    pub fn with_json_field(mut self, name: &str) -> Self {
+        self.json_fields.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Enables or disables masking of Telegram bot tokens in URL paths.
+    pub fn with_telegram_bot_token(mut self, enabled: bool) -> Self {
+        self.telegram_bot_token = enabled;
+        self
+    }
+
+    /// Returns whether the value of the given header must be masked.
+    pub fn is_secret_header(&self, name: &str) -> bool {
+        Self::contains(&self.headers, name)
+    }
+
+    /// Returns the URL as a string with secrets masked.
+    ///
+    /// User credentials embedded in the URL are always dropped.
+    pub fn redact_url(&self, url: &Url) -> String {
+        let mut redacted = url.origin().ascii_serialization();
+
+        for segment in url.path().split('/').skip(1) {
+            redacted.push('/');
+            if self.telegram_bot_token && Self::is_telegram_bot_token(segment) {
+                redacted.push_str("bot");
+                redacted.push_str(REDACTED);
+            } else {
+                redacted.push_str(segment);
+            }
+        }
+
+        if let Some(query) = url.query() {
+            let pairs: Vec<String> = query
+                .split('&')
+                .map(|pair| match pair.split_once('=') {
+                    Some((name, _)) if Self::contains(&self.query_params, name) => {
+                        format!("{}={}", name, REDACTED)
+                    }
+                    _ => pair.to_owned(),
+                })
+                .collect();
+            redacted.push('?');
+            redacted.push_str(&pairs.join("&"));
+        }
+
+        redacted
+    }
+
+    /// Returns the header value, or the placeholder if the header is secret.
+    pub fn redact_header<'a>(&self, name: &str, value: &'a str) -> Cow<'a, str> {
+        if self.is_secret_header(name) {
+            Cow::Borrowed(REDACTED)
+        } else {
+            Cow::Borrowed(value)
+        }
+    }
+
+    /// Returns the body with secret JSON fields masked.
+    ///
+    /// Bodies that are not valid JSON are returned unchanged.
+    pub fn redact_body<'a>(&self, body: &'a str) -> Cow<'a, str> {
+        if self.json_fields.is_empty() {
+            return Cow::Borrowed(body);
+        }
+
+        match serde_json::from_str::<Value>(body) {
+            Ok(mut value) => {
+                if self.redact_json(&mut value) {
+                    Cow::Owned(value.to_string())
+                } else {
+                    Cow::Borrowed(body)
+                }
+            }
+            Err(_) => Cow::Borrowed(body),
+        }
+    }
+
+    /// Returns the error message with secrets in the request URL masked.
+    pub fn redact_error(&self, error: &reqwest::Error) -> String {
+        let message = error.to_string();
+        match error.url() {
+            Some(url) => message.replace(url.as_str(), &self.redact_url(url)),
+            None => message,
+        }
+    }
+
+    /// Masks secret fields in place and returns whether anything changed.
+    fn redact_json(&self, value: &mut Value) -> bool {
+        match value {
+            Value::Object(map) => {
+                let mut changed = false;
+                for (key, field) in map.iter_mut() {
+                    if Self::contains(&self.json_fields, key) {
+                        *field = Value::String(REDACTED.to_owned());
+                        changed = true;
+                    } else {
+                        changed |= self.redact_json(field);
+                    }
+                }
+                changed
+            }
+            Value::Array(items) => {
+                let mut changed = false;
+                for item in items.iter_mut() {
+                    changed |= self.redact_json(item);
+                }
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether a path segment looks like `bot<id>:<secret>`.
+    fn is_telegram_bot_token(segment: &str) -> bool {
+        segment
+            .strip_prefix("bot")
+            .and_then(|token| token.split_once(':'))
+            .is_some_and(|(id, secret)| {
+                !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) && !secret.is_empty()
+            })
+    }
+
+    /// Returns whether `name` is contained in the lowercase `names`.
+    fn contains(names: &[String], name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        names.contains(&name)
+    }
+}
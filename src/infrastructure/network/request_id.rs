@@ -0,0 +1,74 @@
+//! Correlates network requests with the interaction that caused them.
+//!
+//! A `RequestId` is attached to the current task while an incoming Telegram
+//! update is handled. Every request sent by `NetworkProvider` during that time
+//! carries the same id in its span and in the `X-Request-Id` header, so the
+//! logs of one user interaction can be filtered by it.
+
+use std::collections::hash_map::RandomState;
+use std::fmt::{self, Display};
+use std::future::Future;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The header carrying the request id.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+tokio::task_local! {
+    /// The request id of the interaction handled by the current task.
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+/// Makes ids generated within the same nanosecond unique.
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// An opaque id shared by all requests of one interaction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Generates a new random id of 16 hexadecimal characters.
+    pub fn generate() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default();
+        let mut hasher = RandomState::new().build_hasher();
+        nanos.hash(&mut hasher);
+        SEQUENCE.fetch_add(1, Ordering::Relaxed).hash(&mut hasher);
+        Self(format!("{:016x}", hasher.finish()))
+    }
+
+    /// Returns the id of the interaction handled by the current task, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+    }
+
+    /// Returns the current id, or a new one outside of an interaction.
+    pub fn current_or_generate() -> Self {
+        Self::current().unwrap_or_else(Self::generate)
+    }
+
+    /// Runs `future` with this id as the current request id.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT_REQUEST_ID.scope(self, future).await
+    }
+
+    /// Returns the id as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for RequestId {
+    fn from(id: String) -> Self {
+        Self(id)
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -0,0 +1,97 @@
+//! Defines when and how failed network requests are retried.
+//!
+//! Retries are only safe when repeating a request cannot cause a side effect
+//! twice. A request that failed while connecting never reached the server and
+//! can always be retried; anything else is only retried for idempotent methods.
+
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use super::http_method::HttpMethod;
+
+/// Describes how many times and how fast failed requests are retried.
+///
+/// The delay between attempts grows exponentially from `base_delay` and is
+/// capped at `max_delay`. The default policy performs no retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt
+    max_retries: u32,
+    /// Delay before the first retry
+    base_delay: Duration,
+    /// Upper bound for the delay between two attempts
+    max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy that never retries.
+    pub fn none() -> Self {
+        Self::new(0)
+    }
+
+    /// Creates a policy that retries up to `max_retries` times.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the upper bound for the delay between two attempts.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Returns the maximum number of retries.
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Returns the delay to wait before the given retry (starting at 1).
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Returns whether a request that failed with `error` may be retried.
+    ///
+    /// Connection failures are retried for every method because the server
+    /// never saw the request. Timeouts and other transport errors are only
+    /// retried for idempotent methods.
+    pub fn should_retry_error(&self, method: HttpMethod, error: &reqwest::Error) -> bool {
+        if error.is_connect() {
+            return true;
+        }
+        method.is_idempotent() && (error.is_timeout() || error.is_request())
+    }
+
+    /// Returns whether a request that received `status` may be retried.
+    ///
+    /// Rate limiting and temporary gateway failures are retried for idempotent
+    /// methods only.
+    pub fn should_retry_status(&self, method: HttpMethod, status: StatusCode) -> bool {
+        method.is_idempotent()
+            && matches!(
+                status,
+                StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            )
+    }
+}
@@ -0,0 +1,94 @@
+//! Provides chunked access to response bodies.
+//!
+//! Large binaries such as posters, subtitle files or exported logs should not be
+//! buffered in memory. A `NetworkStream` hands out the body chunk by chunk while
+//! keeping track of how many bytes have been received so far.
+
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::{Response, StatusCode};
+
+use super::error::NetworkError;
+
+/// A streaming view over a response body.
+///
+/// The stream optionally enforces a maximum body size. The limit is checked
+/// against the `Content-Length` header up front and against the number of
+/// received bytes while reading, so servers that omit or lie about the length
+/// are still caught.
+pub struct NetworkStream {
+    /// The underlying response
+    response: Response,
+    /// Maximum number of bytes that may be read from the body
+    max_size: Option<u64>,
+    /// Number of bytes read so far
+    received: u64,
+}
+
+impl NetworkStream {
+    /// Wraps a response into a stream without a size limit.
+    pub fn new(response: Response) -> Self {
+        Self {
+            response,
+            max_size: None,
+            received: 0,
+        }
+    }
+
+    /// Limits the body to `max_size` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NetworkError::SizeLimitExceeded` right away if the announced
+    /// `Content-Length` is already larger than the limit.
+    pub fn with_max_size(mut self, max_size: u64) -> Result<Self, NetworkError> {
+        if self.content_length().is_some_and(|length| length > max_size) {
+            return Err(NetworkError::SizeLimitExceeded { limit: max_size });
+        }
+        self.max_size = Some(max_size);
+        Ok(self)
+    }
+
+    /// Returns the status code of the response.
+    pub fn status(&self) -> StatusCode {
+        self.response.status()
+    }
+
+    /// Returns the headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        self.response.headers()
+    }
+
+    /// Returns the announced length of the body, if known.
+    pub fn content_length(&self) -> Option<u64> {
+        self.response.content_length()
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Reads the next chunk of the body.
+    ///
+    /// Returns `Ok(None)` once the body has been read completely.
+    pub async fn next_chunk(&mut self) -> Result<Option<Bytes>, NetworkError> {
+        let Some(chunk) = self.response.chunk().await? else {
+            return Ok(None);
+        };
+
+        self.received += chunk.len() as u64;
+        if let Some(limit) = self.max_size
+            && self.received > limit
+        {
+            return Err(NetworkError::SizeLimitExceeded { limit });
+        }
+
+        Ok(Some(chunk))
+    }
+
+    /// Consumes the stream and returns the underlying response.
+    pub fn into_response(self) -> Response {
+        self.response
+    }
+}
@@ -3,6 +3,7 @@
 //! This module provides a trait that defines the structure of a network request target,
 //! including the base URL, path, HTTP method, and request task.
 
+use super::header::NetworkHeaders;
 use super::http_method::HttpMethod;
 use super::task::NetworkTask;
 
@@ -21,6 +22,15 @@ pub trait NetworkTarget {
     /// Returns the request path.
     fn path(&self) -> String;
 
+    /// Returns the request path with its variable parts replaced by placeholders.
+    /// 
+    /// The template identifies the endpoint independent of concrete IDs, e.g.
+    /// `emby/Users/{user_id}`, and is used to label metrics. By default the
+    /// path itself is returned, which is only appropriate for static paths.
+    fn path_template(&self) -> String {
+        self.path()
+    }
+
     /// Returns the HTTP method to use.
     fn method(&self) -> HttpMethod;
 
@@ -30,8 +40,18 @@ pub trait NetworkTarget {
     /// Returns optional request headers.
     /// 
     /// By default, returns `None`. Implementors can override this method
-    /// to provide custom headers.
-    fn headers(&self) -> Option<Vec<(&'static str, String)>> {
+    /// to provide custom headers. Headers may be repeated, and secrets should
+    /// be added with `NetworkHeaders::with_sensitive` so logs redact them.
+    fn headers(&self) -> Option<NetworkHeaders> {
         None
     }
+
+    /// Returns whether the whole request body is secret.
+    ///
+    /// Logs show the body of such requests as `[REDACTED]` instead of only
+    /// masking known JSON fields, e.g. for a message carrying a new password.
+    /// By default, returns `false`.
+    fn is_body_sensitive(&self) -> bool {
+        false
+    }
 }
@@ -4,4 +4,9 @@ pub mod infrastructure {
     pub mod api;
     pub mod config;
     pub mod database;
-}
\ No newline at end of file
+    pub mod metrics;
+}
+
+pub mod bot;
+pub mod context;
+pub mod services;
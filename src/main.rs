@@ -1,6 +1,184 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::broadcast::error::RecvError;
+
+use pilipili_bot::{error_log, warn_log};
+use pilipili_bot::bot::{
+    ActivityHandler, BindHandler, CallbackRouter, CancelHandler, ConversationStore, DeletionQueue, Dispatcher,
+    LogLevelHandler, ResetPasswordHandler, RolesHandler, ServersHandler, TelegramAlertSink, UserAdminHandler,
+};
+use pilipili_bot::context::AppContext;
+use pilipili_bot::infrastructure::config::{
+    cli, CliArgs, CliCommand, ConfigLoader, ConfigReloader, ConfigSection,
+};
 use pilipili_bot::infrastructure::logger::builder::LoggerBuilder;
+use pilipili_bot::infrastructure::logger::{AlertForwarder, AlertLayer};
+use pilipili_bot::infrastructure::metrics::{MetricsExporter, MetricsRegistry};
+use pilipili_bot::infrastructure::network::{CurlPlugin, MetricsPlugin, NetworkProvider, RedactionRules, RetryPolicy};
+use pilipili_bot::services::permissions::seed_roles;
+
+/// How often expired conversations are removed
+const CONVERSATION_EXPIRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often messages due for deletion are deleted
+const MESSAGE_DELETION_INTERVAL: Duration = Duration::from_secs(5);
+
+#[tokio::main]
+async fn main() {
+    let args = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    let loader = ConfigLoader::from_env().with_cli_args(&args);
+    let loaded = loader
+        .load()
+        .and_then(|config| config.validate().map(|_| config));
+    if args.command == Some(CliCommand::CheckConfig) {
+        match loaded {
+            Ok(_) => println!("Configuration is valid"),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let config = match loaded {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Failed to load configuration: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let mut logger_builder = LoggerBuilder::from_config(&config.logger);
+    let mut alert_receiver = None;
+    if config.alerts.chat_id.is_some() {
+        let (alert_layer, receiver) = AlertLayer::new(config.alerts.level);
+        logger_builder = logger_builder.with_alerts(alert_layer);
+        alert_receiver = Some(receiver);
+    }
+    let logger = match logger_builder.try_init() {
+        Ok(logger) => logger,
+        Err(error) => {
+            eprintln!("Failed to initialize logger: {}", error);
+            return;
+        }
+    };
+    let logger_handle = logger
+        .handle()
+        .cloned()
+        .expect("an initialized logger has a handle");
+
+    RedactionRules::install_global(config.logger.redaction.rules());
+    let provider = NetworkProvider::new(vec![
+        Box::new(CurlPlugin::default()),
+        Box::new(MetricsPlugin::default()),
+    ])
+        .with_retry_policy(RetryPolicy::new(3));
+    let context = match AppContext::connect(config, provider).await {
+        Ok(context) => Arc::new(context),
+        Err(error) => {
+            error_log!("[DATABASE]", "Failed to connect to the database: {}", error);
+            return;
+        }
+    };
+
+    if let Err(error) = seed_roles(context.database(), &context.config()).await {
+        error_log!("[DATABASE]", "Failed to seed roles: {}", error);
+        return;
+    }
+
+    let mut config_changes = context.subscribe();
+    let reload_logger = logger_handle.clone();
+    let reload_context = context.clone();
+    tokio::spawn(async move {
+        loop {
+            let change = match config_changes.recv().await {
+                Ok(change) => change,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn_log!("[CONFIG]", "Missed {} configuration changes", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if (change.contains(ConfigSection::Roles) || change.contains(ConfigSection::Telegram))
+                && let Err(error) = seed_roles(reload_context.database(), &change.config).await
+            {
+                error_log!("[CONFIG]", "Failed to apply the new roles: {}", error);
+            }
+            if change.contains(ConfigSection::Logger) {
+                let logger = &change.config.logger;
+                if let Err(error) = reload_logger.set_level(logger.level, &logger.directives) {
+                    error_log!("[CONFIG]", "Failed to apply the new log level: {}", error);
+                }
+                RedactionRules::install_global(logger.redaction.rules());
+            }
+            for section in [ConfigSection::Alerts, ConfigSection::Metrics, ConfigSection::Database] {
+                if change.contains(section) {
+                    warn_log!("[CONFIG]", "Changes to [{}] take effect after a restart", section);
+                }
+            }
+        }
+    });
+    tokio::spawn(ConfigReloader::new(loader).run(context.clone()));
+
+    let config = context.config();
+    let alerts = &config.alerts;
+    if let (Some(chat_id), Some(receiver)) = (alerts.chat_id, alert_receiver) {
+        let sink = TelegramAlertSink::new(context.telegram().clone(), chat_id);
+        let forwarder = AlertForwarder::new(receiver, sink)
+            .with_batch_window(Duration::from_secs(alerts.batch_window))
+            .with_min_interval(Duration::from_secs(alerts.min_interval));
+        tokio::spawn(forwarder.run());
+    }
+
+    if config.metrics.enabled {
+        match MetricsExporter::bind(config.metrics.address.as_str(), MetricsRegistry::global()).await {
+            Ok(exporter) => {
+                tokio::spawn(async move {
+                    if let Err(error) = exporter.serve().await {
+                        error_log!("[METRICS]", "Metrics exporter stopped: {}", error);
+                    }
+                });
+            }
+            Err(error) => {
+                error_log!("[METRICS]", "Failed to serve metrics on {}: {}", config.metrics.address, error);
+            }
+        }
+    }
+
+    let conversations = ConversationStore::new(context.database().clone());
+    tokio::spawn(conversations.clone().run_expiry(context.telegram().clone(), CONVERSATION_EXPIRY_INTERVAL));
+    let deletions = DeletionQueue::new(context.database().clone());
+    tokio::spawn(deletions.run(context.telegram().clone(), MESSAGE_DELETION_INTERVAL));
+
+    let reset_password = ResetPasswordHandler::new(context.clone());
+    let user_admin = UserAdminHandler::new(context.clone());
+    let callbacks = CallbackRouter::new()
+        .with_route(reset_password.clone())
+        .with_route(user_admin.clone());
 
-fn main() {
-    LoggerBuilder::default()
-        .init()
+    Dispatcher::new(context.telegram().clone())
+        .with_handler(ActivityHandler::new(context.database().clone()))
+        .with_handler(LogLevelHandler::new(logger_handle, context.clone()))
+        .with_handler(ServersHandler::new(context.clone()))
+        .with_handler(RolesHandler::new(context.clone()))
+        .with_handler(CancelHandler::new(conversations))
+        .with_handler(BindHandler::new(context.clone()))
+        .with_handler(reset_password)
+        .with_handler(user_admin)
+        .with_handler(callbacks)
+        .with_poll_timeout(config.telegram.poll_timeout)
+        .run()
+        .await;
 }
@@ -0,0 +1,7 @@
+pub mod placement;
+pub mod password;
+pub mod permissions;
+
+pub use placement::{least_loaded, place_new_account, server_loads, PlacementError, ServerLoad};
+pub use password::{check_password, generate_password};
+pub use permissions::{check_role_change, configured_roles, role_of, seed_roles, Permission, Role};
@@ -0,0 +1,75 @@
+//! Generates and checks media server passwords.
+//!
+//! Generated passwords are drawn from the operating system's random number
+//! generator. They avoid characters that are easily confused when typed
+//! from a phone, such as `0`/`O` and `1`/`l`/`I`, and contain at least one
+//! lowercase letter, uppercase letter and digit.
+
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::infrastructure::config::password::{MAX_PASSWORD_LENGTH, MIN_GENERATED_LENGTH};
+
+/// Lowercase letters without `l` and `o`
+const LOWERCASE: &[u8] = b"abcdefghijkmnpqrstuvwxyz";
+
+/// Uppercase letters without `I` and `O`
+const UPPERCASE: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+
+/// Digits without `0` and `1`
+const DIGITS: &[u8] = b"23456789";
+
+/// Symbols that need no escaping in Telegram messages or shells
+const SYMBOLS: &[u8] = b"-_.!@#%+=";
+
+/// Generates a random password of the given length, but at least 8 characters.
+///
+/// # Examples
+///
+/// ```rust
+/// use pilipili_bot::services::password::generate_password;
+///
+/// let password = generate_password(16);
+/// assert_eq!(password.len(), 16);
+/// assert!(password.bytes().any(|b| b.is_ascii_digit()));
+/// ```
+pub fn generate_password(length: usize) -> String {
+    let length = length.max(MIN_GENERATED_LENGTH);
+    let all: Vec<u8> = [LOWERCASE, UPPERCASE, DIGITS, SYMBOLS].concat();
+    let mut rng = OsRng;
+
+    let mut password: Vec<u8> = [LOWERCASE, UPPERCASE, DIGITS]
+        .iter()
+        .map(|class| class[rng.gen_range(0..class.len())])
+        .collect();
+    while password.len() < length {
+        password.push(all[rng.gen_range(0..all.len())]);
+    }
+    password.shuffle(&mut rng);
+    String::from_utf8(password).expect("password characters are ASCII")
+}
+
+/// Checks a password chosen by a user, returning why it is rejected.
+///
+/// # Errors
+///
+/// Returns a message for the user if the password is shorter than
+/// `min_length` or longer than 128 characters, contains whitespace
+/// or control characters, or consists of letters only or digits only.
+pub fn check_password(password: &str, min_length: usize) -> Result<(), String> {
+    let length = password.chars().count();
+    if length < min_length {
+        return Err(format!("The password must have at least {} characters", min_length));
+    }
+    if length > MAX_PASSWORD_LENGTH {
+        return Err(format!("The password must have at most {} characters", MAX_PASSWORD_LENGTH));
+    }
+    if password.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("The password must not contain spaces or line breaks".to_string());
+    }
+    if password.chars().all(char::is_alphabetic) || password.chars().all(|c| c.is_ascii_digit()) {
+        return Err("The password must mix letters with digits or symbols".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,192 @@
+//! Decides who may do what.
+//!
+//! Every Telegram user holds one role: owner, admin, moderator or user, in
+//! decreasing order of power. Each permission requires a minimum role, so a
+//! role has all permissions of the roles below it. Users without a stored
+//! role are plain users.
+//!
+//! The users listed in `[roles]` and `[telegram] admin_ids` are written to
+//! the database by `seed_roles`. Other users get their role with `/grant`,
+//! which only hands out roles below the granting user's own.
+
+use std::fmt::{self, Display};
+
+use crate::infrastructure::config::Config;
+use crate::infrastructure::database::{Database, DatabaseError, RoleAssignment};
+use crate::{info_log, warn_log};
+
+/// Domain identifier for permission logs
+const PERMISSIONS_LOGGER_DOMAIN: &str = "[PERMISSIONS]";
+
+/// The role of a Telegram user, ordered from least to most powerful.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Role {
+    /// Anyone talking to the bot
+    User,
+    /// Looks after users and servers
+    Moderator,
+    /// Manages users, the bot and moderators
+    Admin,
+    /// Runs the bot and manages admins, only set in the configuration
+    Owner,
+}
+
+impl Role {
+    /// All roles, from least to most powerful.
+    pub const ALL: [Role; 4] = [Role::User, Role::Moderator, Role::Admin, Role::Owner];
+
+    /// Returns the name of the role, as stored and typed in commands.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+            Role::Owner => "owner",
+        }
+    }
+
+    /// Parses the name of a role, ignoring case.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|role| role.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Returns whether the role includes `permission`.
+    pub fn can(&self, permission: Permission) -> bool {
+        *self >= permission.required_role()
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Something only some roles may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    /// Show the load and sessions of the servers
+    ViewServers,
+    /// Look up users and their accounts
+    ViewUsers,
+    /// Disable, extend, reset, unbind or delete the accounts of users
+    ManageUsers,
+    /// Read and change the log filter
+    ChangeLogLevel,
+    /// Grant and revoke roles below one's own
+    ManageRoles,
+}
+
+impl Permission {
+    /// Returns the least powerful role with this permission.
+    pub fn required_role(&self) -> Role {
+        match self {
+            Permission::ViewServers => Role::Moderator,
+            Permission::ViewUsers => Role::Moderator,
+            Permission::ManageUsers => Role::Admin,
+            Permission::ChangeLogLevel => Role::Admin,
+            Permission::ManageRoles => Role::Admin,
+        }
+    }
+}
+
+/// Returns the role of a Telegram user.
+///
+/// A stored role that is not known any more is treated as `Role::User`.
+pub async fn role_of(database: &Database, telegram_id: i64) -> Result<Role, DatabaseError> {
+    let Some(assignment) = database.role_assignment(telegram_id).await? else {
+        return Ok(Role::User);
+    };
+    Ok(Role::parse(&assignment.role).unwrap_or_else(|| {
+        warn_log!(
+            PERMISSIONS_LOGGER_DOMAIN,
+            user_id = telegram_id,
+            "Ignoring unknown role {}",
+            assignment.role
+        );
+        Role::User
+    }))
+}
+
+/// Returns the roles set in the configuration, by user id.
+///
+/// A user listed more than once gets the most powerful role.
+pub fn configured_roles(config: &Config) -> Vec<(i64, Role)> {
+    let roles = &config.roles;
+    let listed = [
+        (&roles.owners, Role::Owner),
+        (&roles.admins, Role::Admin),
+        (&config.telegram.admin_ids, Role::Admin),
+        (&roles.moderators, Role::Moderator),
+    ];
+
+    let mut configured: Vec<(i64, Role)> = Vec::new();
+    for (ids, role) in listed {
+        for id in ids {
+            if !configured.iter().any(|(configured_id, _)| configured_id == id) {
+                configured.push((*id, role));
+            }
+        }
+    }
+    configured
+}
+
+/// Writes the roles set in the configuration to the database.
+///
+/// Roles seeded earlier for users that are no longer listed are removed,
+/// roles granted with `/grant` are kept unless the user is now listed.
+pub async fn seed_roles(database: &Database, config: &Config) -> Result<(), DatabaseError> {
+    let configured = configured_roles(config);
+    for assignment in database.role_assignments().await? {
+        let listed = configured.iter().any(|(id, _)| *id == assignment.telegram_id);
+        if assignment.granted_by.is_none() && !listed {
+            database.remove_role(assignment.telegram_id).await?;
+            info_log!(
+                PERMISSIONS_LOGGER_DOMAIN,
+                user_id = assignment.telegram_id,
+                "Removed {} role no longer in the configuration",
+                assignment.role
+            );
+        }
+    }
+
+    for (telegram_id, role) in configured {
+        let current = database.role_assignment(telegram_id).await?;
+        if current.is_some_and(|current| current.role == role.name() && current.granted_by.is_none()) {
+            continue;
+        }
+        database.assign_role(&RoleAssignment::new(telegram_id, role.name(), None)).await?;
+        info_log!(PERMISSIONS_LOGGER_DOMAIN, user_id = telegram_id, "Seeded {} role", role);
+    }
+    Ok(())
+}
+
+/// Checks whether a user with role `actor` may change the role of a user
+/// holding `current` to `role`.
+///
+/// # Errors
+///
+/// Returns a message for the user unless both `current` and `role` are
+/// below `actor` and `actor` may manage roles.
+///
+/// # Examples
+///
+/// ```rust
+/// use pilipili_bot::services::permissions::{check_role_change, Role};
+///
+/// assert!(check_role_change(Role::Admin, Role::User, Role::Moderator).is_ok());
+/// assert!(check_role_change(Role::Admin, Role::User, Role::Admin).is_err());
+/// assert!(check_role_change(Role::Admin, Role::Admin, Role::User).is_err());
+/// ```
+pub fn check_role_change(actor: Role, current: Role, role: Role) -> Result<(), String> {
+    if !actor.can(Permission::ManageRoles) {
+        return Err("You may not manage roles".to_string());
+    }
+    if current >= actor {
+        return Err(format!("Users with the {} role can only be changed by someone above it", current));
+    }
+    if role >= actor {
+        return Err(format!("The {} role can only be granted by someone above it", role));
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//! Chooses the server for a new account.
+//!
+//! Each server's load is the number of accounts bound to it or the number of
+//! its active sessions, depending on `[placement] strategy`. Servers that
+//! reached their `max_users` are skipped, as are servers whose sessions
+//! cannot be read when placing by sessions. Ties go to the server with fewer
+//! accounts, then to the first by name.
+
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use crate::context::AppContext;
+use crate::infrastructure::api::MediaServer;
+use crate::infrastructure::config::PlacementStrategy;
+use crate::infrastructure::database::DatabaseError;
+use crate::warn_log;
+
+/// Domain identifier for placement logs
+const PLACEMENT_LOGGER_DOMAIN: &str = "[PLACEMENT]";
+
+/// Represents an error while choosing a server.
+#[derive(Debug)]
+pub enum PlacementError {
+    /// Every server is full or unreachable
+    NoServerAvailable,
+    /// The account counts could not be read
+    Database(DatabaseError),
+}
+
+impl Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementError::NoServerAvailable => write!(f, "no server can take new accounts"),
+            PlacementError::Database(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PlacementError::NoServerAvailable => None,
+            PlacementError::Database(error) => Some(error),
+        }
+    }
+}
+
+impl From<DatabaseError> for PlacementError {
+    fn from(error: DatabaseError) -> Self {
+        PlacementError::Database(error)
+    }
+}
+
+/// The load of a single server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerLoad {
+    /// The name of the server
+    pub server: String,
+    /// The number of bound accounts
+    pub users: u64,
+    /// The number of active sessions, `None` if they could not be read
+    pub sessions: Option<u64>,
+    /// The most accounts allowed on the server
+    pub max_users: Option<u64>,
+}
+
+impl ServerLoad {
+    /// Returns whether the server can take another account.
+    pub fn has_capacity(&self) -> bool {
+        self.max_users.is_none_or(|max_users| self.users < max_users)
+    }
+
+    /// Returns the load compared under `strategy`, if known.
+    pub fn load(&self, strategy: PlacementStrategy) -> Option<u64> {
+        match strategy {
+            PlacementStrategy::Users => Some(self.users),
+            PlacementStrategy::Sessions => self.sessions,
+        }
+    }
+}
+
+/// Returns the least loaded server that can take another account.
+pub fn least_loaded(loads: &[ServerLoad], strategy: PlacementStrategy) -> Option<&ServerLoad> {
+    loads
+        .iter()
+        .filter(|load| load.has_capacity())
+        .filter_map(|load| Some((load.load(strategy)?, load)))
+        .min_by(|(a, a_load), (b, b_load)| {
+            a.cmp(b)
+                .then(a_load.users.cmp(&b_load.users))
+                .then(a_load.server.cmp(&b_load.server))
+        })
+        .map(|(_, load)| load)
+}
+
+/// Returns the load of every configured server.
+///
+/// Sessions are only requested if `with_sessions` is set.
+pub async fn server_loads(context: &AppContext, with_sessions: bool) -> Result<Vec<ServerLoad>, PlacementError> {
+    let counts = context.database().account_counts().await?;
+    let session_window = context.config().placement.session_window;
+
+    let mut loads = Vec::new();
+    for client in context.servers().all() {
+        let settings = client.settings();
+        let sessions = if with_sessions {
+            match client.sessions(session_window).await {
+                Ok(sessions) => Some(sessions.len() as u64),
+                Err(error) => {
+                    warn_log!(PLACEMENT_LOGGER_DOMAIN, server = %settings.name, "Failed to read sessions: {}", error);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        loads.push(ServerLoad {
+            server: settings.name.clone(),
+            users: counts.get(&settings.name).copied().unwrap_or_default(),
+            sessions,
+            max_users: settings.max_users,
+        });
+    }
+    Ok(loads)
+}
+
+/// Chooses the server for a new account using the configured strategy.
+///
+/// # Errors
+///
+/// Returns `PlacementError::NoServerAvailable` if every server is full or,
+/// when placing by sessions, unreachable.
+pub async fn place_new_account(context: &AppContext) -> Result<Arc<dyn MediaServer>, PlacementError> {
+    let strategy = context.config().placement.strategy;
+    let loads = server_loads(context, strategy == PlacementStrategy::Sessions).await?;
+    least_loaded(&loads, strategy)
+        .and_then(|load| context.servers().get(&load.server))
+        .ok_or(PlacementError::NoServerAvailable)
+}
@@ -0,0 +1,188 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use pilipili_bot::bot::{Dispatcher, HandlerError, UpdateHandler};
+    use pilipili_bot::bot::bind::BindRequest;
+    use pilipili_bot::bot::throttle::FailureLimiter;
+    use pilipili_bot::infrastructure::api::TelegramClient;
+    use pilipili_bot::infrastructure::api::telegram_types::Update;
+    use pilipili_bot::infrastructure::config::telegram::TelegramConfig;
+    use pilipili_bot::infrastructure::metrics::bot::COMMANDS_HANDLED;
+    use pilipili_bot::infrastructure::metrics::{BotMetrics, MetricsRegistry};
+    use pilipili_bot::infrastructure::network::*;
+
+    struct PingTarget {
+        base_url: String,
+    }
+
+    impl NetworkTarget for PingTarget {
+        fn base_url(&self) -> String {
+            self.base_url.clone()
+        }
+
+        fn path(&self) -> String {
+            "ping".to_string()
+        }
+
+        fn method(&self) -> HttpMethod {
+            HttpMethod::Get
+        }
+
+        fn task(&self) -> NetworkTask {
+            NetworkTask::RequestPlain
+        }
+    }
+
+    /// Sends two requests and remembers the request id of the interaction.
+    struct PingHandler {
+        target: PingTarget,
+        request_id: Arc<Mutex<Option<RequestId>>>,
+    }
+
+    #[async_trait]
+    impl UpdateHandler for PingHandler {
+        async fn handle(&self, _client: &TelegramClient, _update: &Update) -> Result<(), HandlerError> {
+            *self.request_id.lock().unwrap() = RequestId::current();
+            let provider = NetworkProvider::new(vec![]);
+            provider.send_request(&self.target).await?;
+            provider.send_request(&self.target).await?;
+            Ok(())
+        }
+    }
+
+    /// Answers `/start`, failing on `/start fail`.
+    struct StartHandler;
+
+    #[async_trait]
+    impl UpdateHandler for StartHandler {
+        async fn handle(&self, _client: &TelegramClient, update: &Update) -> Result<(), HandlerError> {
+            match update.message().and_then(|message| message.text.as_deref()) {
+                Some("/start fail") => Err("failed".into()),
+                _ => Ok(()),
+            }
+        }
+
+        fn commands(&self) -> &'static [&'static str] {
+            &["start"]
+        }
+    }
+
+    fn text_update(text: &str) -> Update {
+        serde_json::from_value(serde_json::json!({
+            "update_id": 7,
+            "message": {
+                "message_id": 1,
+                "from": { "id": 42, "is_bot": false, "first_name": "Alice" },
+                "chat": { "id": 42, "type": "private" },
+                "date": 0,
+                "text": text
+            }
+        }))
+        .unwrap()
+    }
+
+    /// Records the `x-request-id` header of every request.
+    async fn serve_recording() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorded = received.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buffer = vec![0; 4096];
+                let read = socket.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).to_lowercase();
+                if let Some(id) = request.lines().find_map(|line| line.strip_prefix("x-request-id: ")) {
+                    recorded.lock().unwrap().push(id.trim().to_string());
+                }
+                let response = "HTTP/1.1 204 No Content\r\nconnection: close\r\n\r\n";
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (format!("http://{}", address), received)
+    }
+
+    #[tokio::test]
+    async fn test_update_requests_share_request_id() {
+        let (base_url, received) = serve_recording().await;
+        let seen_request_id = Arc::new(Mutex::new(None));
+        let handler = PingHandler {
+            target: PingTarget { base_url },
+            request_id: seen_request_id.clone(),
+        };
+
+        let client = Arc::new(TelegramClient::new(NetworkProvider::new(vec![]), TelegramConfig::default()));
+        let dispatcher = Dispatcher::new(client).with_handler(handler);
+
+        dispatcher.dispatch(text_update("/start")).await;
+
+        let request_id = seen_request_id.lock().unwrap().clone().expect("Request id should be set");
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received, [request_id.to_string(), request_id.to_string()]);
+        assert!(RequestId::current().is_none(), "Request id must not leak out of the update");
+    }
+
+    #[tokio::test]
+    async fn test_handled_commands_are_counted() {
+        let registry = Arc::new(MetricsRegistry::new());
+        let client = Arc::new(TelegramClient::new(NetworkProvider::new(vec![]), TelegramConfig::default()));
+        let dispatcher = Dispatcher::new(client)
+            .with_handler(StartHandler)
+            .with_metrics(BotMetrics::new(registry.clone()));
+
+        for text in ["/start", "/start@PiliPiliBot", "/start fail", "/unknown", "hello"] {
+            dispatcher.dispatch(text_update(text)).await;
+        }
+
+        let count = |command, outcome| {
+            registry.counter_value(&COMMANDS_HANDLED, &[("command", command), ("outcome", outcome)])
+        };
+        assert_eq!(count("start", "success"), 2);
+        assert_eq!(count("start", "failure"), 1);
+        assert_eq!(count("unknown", "success"), 0, "commands no handler claims are not counted");
+    }
+
+    #[test]
+    fn test_parse_bind_request() {
+        let servers = ["eu".to_string(), "us".to_string()];
+        let request = BindRequest::parse("us alice secret", &servers).unwrap();
+        assert_eq!(request.server.as_deref(), Some("us"));
+        assert_eq!((request.username.as_str(), request.password.as_str()), ("alice", "secret"));
+
+        let request = BindRequest::parse("alice two words", &servers).unwrap();
+        assert_eq!(request.server, None);
+        assert_eq!(request.password, "two words");
+
+        // A user named like a server is still accepted when no server is given
+        let request = BindRequest::parse("eu secret", &servers).unwrap();
+        assert_eq!((request.server, request.username.as_str()), (None, "eu"));
+        assert!(BindRequest::parse("", &servers).is_none());
+    }
+
+    #[test]
+    fn test_failure_limiter_blocks_after_max_failures() {
+        let limiter = FailureLimiter::new(2, Duration::from_millis(200));
+        assert_eq!(limiter.record_failure(1), None);
+        assert!(limiter.cooldown(1).is_none());
+        assert_eq!(limiter.record_failure(1), Some(Duration::from_millis(200)));
+        assert!(limiter.cooldown(1).is_some());
+        assert!(limiter.cooldown(2).is_none(), "other users are not affected");
+
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(limiter.cooldown(1).is_none(), "the block ends after the cooldown");
+        assert_eq!(limiter.record_failure(1), None, "the count starts over");
+
+        limiter.reset(1);
+        limiter.record_failure(1);
+        assert!(limiter.cooldown(1).is_none());
+    }
+}
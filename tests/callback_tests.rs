@@ -0,0 +1,261 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use pilipili_bot::bot::callback::{CallbackError, MAX_CALLBACK_DATA_LENGTH};
+    use pilipili_bot::bot::{
+        CallbackData, CallbackHandler, CallbackRouter, CallbackSigner, Dispatcher, HandlerError, KeyboardBuilder, Page,
+    };
+    use pilipili_bot::infrastructure::api::TelegramClient;
+    use pilipili_bot::infrastructure::api::telegram_types::{CallbackQuery, Update};
+    use pilipili_bot::infrastructure::config::telegram::TelegramConfig;
+    use pilipili_bot::infrastructure::network::NetworkProvider;
+
+    const BOT_TOKEN: &str = "123456:ABC-DEF1234ghIkl-zyx57W2v1u123ew11";
+
+    #[derive(Debug, PartialEq)]
+    struct Disable {
+        server: String,
+        user_id: i64,
+    }
+
+    impl CallbackData for Disable {
+        const ACTION: &'static str = "dis";
+
+        fn fields(&self) -> Vec<String> {
+            vec![self.server.clone(), self.user_id.to_string()]
+        }
+
+        fn from_fields(fields: &[&str]) -> Option<Self> {
+            match fields {
+                [server, user_id] => Some(Self { server: server.to_string(), user_id: user_id.parse().ok()? }),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct ListPage(usize);
+
+    impl CallbackData for ListPage {
+        const ACTION: &'static str = "pg";
+
+        fn fields(&self) -> Vec<String> {
+            vec![self.0.to_string()]
+        }
+
+        fn from_fields(fields: &[&str]) -> Option<Self> {
+            Some(Self(fields.first()?.parse().ok()?))
+        }
+    }
+
+    fn signer() -> CallbackSigner {
+        CallbackSigner::new(BOT_TOKEN.as_bytes(), Duration::from_secs(3600))
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = signer();
+        let data = Disable { server: "eu".to_string(), user_id: 1_234_567_890 };
+        let now = 1_760_000_000;
+
+        let encoded = signer.sign_at(&data, Some(9_876_543_210), now).unwrap();
+        assert!(encoded.len() <= MAX_CALLBACK_DATA_LENGTH, "{} is too long", encoded);
+        assert!(encoded.starts_with("dis:eu:1234567890:"));
+
+        let callback = signer.verify_at(&encoded, 9_876_543_210, now + 3600).unwrap();
+        assert_eq!(callback.requester, Some(9_876_543_210));
+        assert_eq!(callback.issued_at, now);
+        assert_eq!(callback.decode::<Disable>(), Some(data));
+        assert_eq!(callback.decode::<ListPage>(), None, "another action does not decode");
+
+        let anyone = signer.sign_at(&ListPage(3), None, now).unwrap();
+        assert_eq!(signer.verify_at(&anyone, 1, now).unwrap().decode::<ListPage>(), Some(ListPage(3)));
+    }
+
+    #[test]
+    fn test_rejected_callbacks() {
+        let signer = signer();
+        let now = 1_760_000_000;
+        let encoded = signer.sign_at(&ListPage(1), Some(42), now).unwrap();
+
+        assert_eq!(signer.verify_at(&encoded, 42, now + 3601), Err(CallbackError::Expired));
+        assert_eq!(signer.verify_at(&encoded, 43, now), Err(CallbackError::WrongUser));
+
+        let tampered = encoded.replacen("pg:1", "pg:2", 1);
+        assert_eq!(signer.verify_at(&tampered, 42, now), Err(CallbackError::BadSignature));
+        let other_bot = CallbackSigner::new(b"654321:other", Duration::from_secs(3600));
+        assert_eq!(other_bot.verify_at(&encoded, 42, now), Err(CallbackError::BadSignature));
+        assert_eq!(signer.verify_at("no signature", 42, now), Err(CallbackError::Malformed));
+
+        let colon = Disable { server: "a:b".to_string(), user_id: 1 };
+        assert_eq!(signer.sign(&colon, None), Err(CallbackError::InvalidField("a:b".to_string())));
+        let long = Disable { server: "s".repeat(40), user_id: 1 };
+        assert!(matches!(signer.sign(&long, Some(42)), Err(CallbackError::TooLong(_))));
+    }
+
+    #[test]
+    fn test_keyboard_and_pages() {
+        let items: Vec<u32> = (1..=7).collect();
+        let first = Page::new(&items, 0, 3);
+        assert_eq!(first.items, &[1, 2, 3]);
+        assert_eq!((first.count, first.has_previous(), first.has_next()), (3, false, true));
+        let last = Page::new(&items, 5, 3);
+        assert_eq!((last.number, last.items), (2, &[7][..]));
+        let empty: Page<u32> = Page::new(&[], 0, 3);
+        assert_eq!((empty.count, empty.items.len(), empty.has_next()), (1, 0, false));
+
+        let signer = signer();
+        let middle = Page::new(&items, 1, 3);
+        let keyboard = KeyboardBuilder::new(&signer)
+            .with_requester(42)
+            .button("Disable", &Disable { server: "eu".to_string(), user_id: 7 })
+            .unwrap()
+            .url("Open", "https://emby.example.com")
+            .pagination(&middle, ListPage)
+            .unwrap()
+            .build();
+
+        let rows = &keyboard.inline_keyboard;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][1].url.as_deref(), Some("https://emby.example.com"));
+        let labels: Vec<&str> = rows[1].iter().map(|button| button.text.as_str()).collect();
+        assert_eq!(labels, ["‹ Previous", "Next ›"]);
+        let next = signer.verify(rows[1][1].callback_data.as_deref().unwrap(), 42).unwrap();
+        assert_eq!(next.decode::<ListPage>(), Some(ListPage(2)));
+
+        let single = KeyboardBuilder::new(&signer).pagination(&Page::new(&items, 0, 10), ListPage);
+        assert!(single.unwrap().build().inline_keyboard.is_empty());
+    }
+
+    /// Records the payloads the router hands over.
+    struct DisableHandler {
+        pressed: Arc<Mutex<Vec<Disable>>>,
+    }
+
+    #[async_trait]
+    impl CallbackHandler<Disable> for DisableHandler {
+        async fn handle(
+            &self,
+            _client: &TelegramClient,
+            _query: &CallbackQuery,
+            data: Disable,
+        ) -> Result<Option<String>, HandlerError> {
+            let reply = format!("Disabled {}", data.user_id);
+            self.pressed.lock().unwrap().push(data);
+            Ok(Some(reply))
+        }
+    }
+
+    /// Answers every Bot API call with `true` and records the request bodies.
+    async fn serve_bot_api() -> (String, Arc<Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorded = received.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = vec![0; 4096];
+                let body = loop {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|line| line.to_lowercase().strip_prefix("content-length: ").map(str::to_owned))
+                            .and_then(|length| length.trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if body.len() >= length || read == 0 {
+                            break body.to_string();
+                        }
+                    }
+                };
+                recorded.lock().unwrap().push(serde_json::from_str(&body).unwrap_or(Value::Null));
+                let payload = r#"{"ok":true,"result":true}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    payload.len(),
+                    payload
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (format!("http://{}", address), received)
+    }
+
+    fn press(user_id: i64, data: &str) -> Update {
+        serde_json::from_value(json!({
+            "update_id": 1,
+            "callback_query": {
+                "id": "query",
+                "from": { "id": user_id, "is_bot": false, "first_name": "Alice" },
+                "message": {
+                    "message_id": 5,
+                    "chat": { "id": -100, "type": "supergroup" },
+                    "date": 0,
+                    "text": "Disable 7?"
+                },
+                "data": data
+            }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_router_answers_presses() {
+        let (api_url, received) = serve_bot_api().await;
+        let settings = TelegramConfig {
+            bot_token: BOT_TOKEN.to_string(),
+            api_url,
+            ..TelegramConfig::default()
+        };
+        let signer = CallbackSigner::from_settings(&settings);
+        let client = Arc::new(TelegramClient::new(NetworkProvider::new(vec![]), settings));
+        let pressed = Arc::new(Mutex::new(Vec::new()));
+        let router = CallbackRouter::new().with_route(DisableHandler { pressed: pressed.clone() });
+        let dispatcher = Dispatcher::new(client).with_handler(router);
+
+        let data = signer.sign(&Disable { server: "eu".to_string(), user_id: 7 }, Some(42)).unwrap();
+        let update = press(42, &data);
+        assert_eq!(update.chat_id(), Some(-100));
+        assert_eq!(update.user_id(), Some(42));
+
+        dispatcher.dispatch(update).await;
+        dispatcher.dispatch(press(43, &data)).await;
+        let expired = signer.sign_at(&ListPage(0), None, 0).unwrap();
+        dispatcher.dispatch(press(42, &expired)).await;
+        let unrouted = signer.sign(&ListPage(0), None).unwrap();
+        dispatcher.dispatch(press(42, &unrouted)).await;
+
+        assert_eq!(*pressed.lock().unwrap(), [Disable { server: "eu".to_string(), user_id: 7 }]);
+        let answers: Vec<(String, bool)> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|body| {
+                assert_eq!(body["callback_query_id"], "query");
+                (body["text"].as_str().unwrap().to_string(), body["show_alert"].as_bool().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            answers,
+            [
+                ("Disabled 7".to_string(), false),
+                ("This button is not for you".to_string(), true),
+                ("⌛ This button has expired".to_string(), false),
+                ("⌛ This button has expired".to_string(), false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! Fixtures shared by the integration tests.
+
+use std::ops::Deref;
+
+use tempfile::TempDir;
+
+use pilipili_bot::infrastructure::config::DatabaseConfig;
+use pilipili_bot::infrastructure::database::Database;
+
+/// A migrated SQLite database in a temporary directory.
+///
+/// The directory is removed when the fixture is dropped.
+pub struct TestDatabase {
+    database: Database,
+    _dir: TempDir,
+}
+
+impl Deref for TestDatabase {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        &self.database
+    }
+}
+
+/// Connects to a new, empty database.
+pub async fn database() -> TestDatabase {
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("sqlite://{}", dir.path().join("pilipili.db").display());
+    let database = Database::connect(&DatabaseConfig { url }).await.unwrap();
+    TestDatabase { database, _dir: dir }
+}
@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::time::Duration;
+    use pilipili_bot::context::AppContext;
+    use pilipili_bot::infrastructure::config::{ConfigError, ConfigLoader, ConfigReloader, ConfigSection};
+    use pilipili_bot::infrastructure::network::NetworkProvider;
+
+    const VALID_CONFIG: &str = "[servers.main]\nbase_url = \"http://localhost:8096\"\napi_key = \"key\"\n\n\
+        [telegram]\nbot_token = \"123456:token\"\npoll_timeout = 5\n\n\
+        [database]\nurl = \"sqlite::memory:\"\n";
+
+    fn temp_file(name: &str, content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("pilipili_{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_changed_sections() {
+        let config = ConfigLoader::new().load().unwrap();
+        let mut other = config.clone();
+        assert!(config.changed_sections(&other).is_empty());
+
+        other.telegram.poll_timeout += 1;
+        other.logger.directives.push(String::from("NETWORK=debug"));
+        assert_eq!(
+            config.changed_sections(&other),
+            [ConfigSection::Telegram, ConfigSection::Logger]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reload_swaps_valid_config_and_keeps_invalid() {
+        let file = temp_file("reload.toml", VALID_CONFIG);
+        let loader = ConfigLoader::new().with_file(&file);
+        let context = AppContext::connect(loader.load().unwrap(), NetworkProvider::new(vec![]))
+            .await
+            .map(Arc::new)
+            .unwrap();
+        let reloader = ConfigReloader::new(loader).with_poll_interval(Duration::from_millis(20));
+        let mut changes = context.subscribe();
+        assert!(reloader.reload(&context).unwrap().is_empty(), "an unchanged file changes nothing");
+
+        std::fs::write(&file, VALID_CONFIG.replace("poll_timeout = 5", "poll_timeout = 0")).unwrap();
+        assert!(matches!(reloader.reload(&context), Err(ConfigError::Invalid(_))));
+        assert_eq!(context.config().telegram.poll_timeout, 5, "an invalid config is not applied");
+
+        // The watcher notices the file change and subscribers learn what changed
+        let watcher = tokio::spawn(reloader.run(context.clone()));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        std::fs::write(&file, VALID_CONFIG.replace("poll_timeout = 5", "poll_timeout = 10\nadmin_ids = [1]")).unwrap();
+        let change = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+            .await
+            .expect("the file change should be picked up")
+            .unwrap();
+        watcher.abort();
+
+        assert_eq!(change.sections, [ConfigSection::Telegram]);
+        assert!(change.contains(ConfigSection::Telegram));
+        assert_eq!(change.config.telegram.admin_ids, [1]);
+        assert_eq!(context.config().telegram.poll_timeout, 10);
+        assert_eq!(context.telegram().settings().poll_timeout, 10);
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_secret_file_change_triggers_reload() {
+        let secret = temp_file("reload_api_key", "old-key\n");
+        let config = VALID_CONFIG.replace(
+            "api_key = \"key\"",
+            &format!("api_key_file = \"{}\"", secret.display()),
+        );
+        let file = temp_file("reload_secret.toml", &config);
+        let loader = ConfigLoader::new().with_file(&file);
+        let (config, files) = loader.load_with_files().unwrap();
+        assert_eq!(files, [file.clone(), secret.clone()]);
+
+        let context = AppContext::connect(config, NetworkProvider::new(vec![]))
+            .await
+            .map(Arc::new)
+            .unwrap();
+        let mut changes = context.subscribe();
+        let reloader = ConfigReloader::new(loader).with_poll_interval(Duration::from_millis(20));
+        let watcher = tokio::spawn(reloader.run(context.clone()));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Ensure the modification time differs on coarse file systems
+        let file_handle = std::fs::File::options().write(true).open(&secret).unwrap();
+        std::fs::write(&secret, "new-key\n").unwrap();
+        file_handle
+            .set_modified(std::time::SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        let change = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+            .await
+            .expect("the secret change should be picked up")
+            .unwrap();
+        watcher.abort();
+
+        assert_eq!(change.sections, [ConfigSection::Servers]);
+        assert_eq!(change.config.server("main").unwrap().api_key, "new-key");
+        std::fs::remove_file(file).unwrap();
+        std::fs::remove_file(secret).unwrap();
+    }
+}
@@ -1,14 +1,292 @@
 #[cfg(test)]
 mod tests {
  
-    use std::sync::RwLockReadGuard;
-    use pilipili_bot::infrastructure::config::Config;
+    use pilipili_bot::infrastructure::config::{CliArgs, CliCommand, Config, ConfigError, ConfigLoader};
+    use pilipili_bot::infrastructure::logger::LogLevel;
+    use pilipili_bot::infrastructure::network::RedactionRules;
+
+    fn temp_file(name: &str, content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("pilipili_{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
 
     #[test]
     fn test_load_existing_config() {
-        let config: RwLockReadGuard<'static, Config> = Config::get();
+        let config = Config::init().unwrap();
+
+        let server = config.servers.first().expect("At least one server should be configured");
+        assert!(!server.base_url.is_empty(), "Base URL should not be empty");
+        assert!(!server.api_key.is_empty(), "API key should not be empty");
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let file = temp_file(
+            "layers.toml",
+            "[servers.main]\nbase_url = \"http://file:8096\"\napi_key = \"from-file\"\n\n[telegram]\npoll_timeout = 5\n",
+        );
+        let args = CliArgs::parse([
+            "--config",
+            file.to_str().unwrap(),
+            "--set",
+            "servers.main.base_url=http://cli:8096",
+        ])
+            .unwrap();
+
+        let config = ConfigLoader::new()
+            .with_env_vars([
+                ("PILIPILI__SERVERS__MAIN__BASE_URL", "http://env:8096"),
+                ("PILIPILI__SERVERS__MAIN__API_KEY", "12345"),
+                ("PILIPILI__LOGGER__LEVEL", "debug"),
+                ("UNRELATED", "ignored"),
+            ])
+            .with_cli_args(&args)
+            .load()
+            .unwrap();
+
+        let server = config.server("main").unwrap();
+        assert_eq!(server.base_url, "http://cli:8096");
+        assert_eq!(server.api_key, "12345");
+        assert_eq!(config.telegram.poll_timeout, 5);
+        assert_eq!(config.telegram.api_url, "https://api.telegram.org");
+        assert_eq!(config.logger.level, LogLevel::Debug);
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_secret_from_file() {
+        let secret = temp_file("secret", "s3cr3t\n");
+        let config = ConfigLoader::new()
+            .with_env_vars([("PILIPILI__TELEGRAM__BOT_TOKEN_FILE", secret.to_str().unwrap())])
+            .load()
+            .unwrap();
+        assert_eq!(config.telegram.bot_token, "s3cr3t");
+        std::fs::remove_file(secret).unwrap();
+
+        let result = ConfigLoader::new()
+            .with_override("servers.main.api_key_file", "/nonexistent/pilipili/secret")
+            .load();
+        assert!(matches!(result, Err(ConfigError::Io { .. })));
+    }
+
+    #[test]
+    fn test_secret_files_only_apply_to_secrets_in_their_source() {
+        let secret = temp_file("layered_secret", "from-file\n");
+        let file = temp_file(
+            "secret_layers.toml",
+            &format!(
+                "[servers.main]\nbase_url = \"http://emby:8096\"\napi_key_file = \"{}\"\n\n[logger]\ndirectory_file = \"{}\"\n",
+                secret.display(),
+                secret.display(),
+            ),
+        );
+
+        let config = ConfigLoader::new().with_file(&file).load().unwrap();
+        assert_eq!(config.server("main").unwrap().api_key, "from-file");
+        assert_eq!(config.logger.directory, "logs");
+
+        let config = ConfigLoader::new()
+            .with_file(&file)
+            .with_env_vars([("PILIPILI__SERVERS__MAIN__API_KEY", "from-env")])
+            .load()
+            .unwrap();
+        assert_eq!(config.server("main").unwrap().api_key, "from-env");
+
+        let result = ConfigLoader::new()
+            .with_override("telegram.bot_token", "token")
+            .with_override("telegram.bot_token_file", secret.to_str().unwrap())
+            .load();
+        assert!(matches!(result, Err(ConfigError::Invalid(_))));
+
+        std::fs::remove_file(file).unwrap();
+        std::fs::remove_file(secret).unwrap();
+    }
+
+    #[test]
+    fn test_overrides_take_the_type_of_their_field() {
+        let config = ConfigLoader::new()
+            .with_env_vars([
+                ("PILIPILI__SERVERS__BACKUP__BASE_URL", "http://backup:8096"),
+                ("PILIPILI__SERVERS__BACKUP__API_KEY", "12345"),
+                ("PILIPILI__SERVERS__BACKUP__MAX_USERS", "10"),
+                ("PILIPILI__LOGGER__DIRECTIVES", "[\"EMBY=debug\"]"),
+            ])
+            .with_override("logger.max_files", "3")
+            .with_override("logger.prefix", "2025")
+            .load()
+            .unwrap();
+
+        let server = config.server("backup").unwrap();
+        assert_eq!(server.api_key, "12345");
+        assert_eq!(server.max_users, Some(10));
+        assert_eq!(config.logger.directives, vec!["EMBY=debug"]);
+        assert_eq!(config.logger.max_files, Some(3));
+        assert_eq!(config.logger.prefix, "2025");
+
+        let result = ConfigLoader::new().with_override("telegram.poll_timeout", "soon").load();
+        assert!(matches!(result, Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_invalid_sources_are_errors() {
+        assert!(matches!(
+            ConfigLoader::new().with_file("/nonexistent/pilipili.toml").load(),
+            Err(ConfigError::Io { .. })
+        ));
+        match ConfigLoader::new().with_override("telegram.poll_timeout", "soon").load() {
+            Err(ConfigError::Invalid(issues)) => assert_eq!(issues[0].key, "telegram.poll_timeout"),
+            other => panic!("expected a type error, got {:?}", other),
+        }
+        assert!(matches!(
+            CliArgs::parse(["--config"]),
+            Err(ConfigError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let blocker = temp_file("db_blocker", "");
+        let config = ConfigLoader::new()
+            .with_override("servers.main.base_url", "localhost:8096")
+            .with_override("telegram.bot_token", "123456:token")
+            .with_override("telegram.poll_timeout", "0")
+            .with_override("logger.directives", "[\"NETWORK=loud\"]")
+            .with_override("metrics.enabled", "true")
+            .with_override("metrics.address", "localhost")
+            .with_override("database.url", format!("sqlite://{}/pilipili.db", blocker.display()))
+            .load()
+            .unwrap();
+
+        let Err(ConfigError::Invalid(issues)) = config.validate() else {
+            panic!("configuration should be invalid");
+        };
+        let keys: Vec<&str> = issues.iter().map(|issue| issue.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "servers.main.base_url",
+                "servers.main.api_key",
+                "telegram.poll_timeout",
+                "logger.directives[0]",
+                "metrics.address",
+                "database.url",
+            ]
+        );
+        assert!(config.validate().unwrap_err().to_string().starts_with("6 configuration problems:"));
+        std::fs::remove_file(blocker).unwrap();
+    }
+
+    #[test]
+    fn test_validate_accepts_sane_config() {
+        let config = ConfigLoader::new()
+            .with_override("servers.main.base_url", "http://localhost:8096")
+            .with_override("servers.main.api_key", "key")
+            .with_override("telegram.bot_token", "123456:token")
+            .with_override("database.url", "sqlite::memory:")
+            .load()
+            .unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_redaction_rules_from_config() {
+        let config = ConfigLoader::new().load().unwrap();
+        assert_eq!(config.logger.redaction.rules(), RedactionRules::default());
+
+        let config = ConfigLoader::new()
+            .with_override("logger.redaction.headers", "[\"X-Api-Key\"]")
+            .with_override("logger.redaction.json_fields", "[\"secret\"]")
+            .with_override("logger.redaction.telegram_bot_token", "false")
+            .load()
+            .unwrap();
+        let rules = config.logger.redaction.rules();
+        assert!(rules.is_secret_header("x-api-key"));
+        assert!(rules.is_secret_header("X-Emby-Token"), "built-in rules are kept");
+        assert_eq!(rules.redact_body(r#"{"secret":"s","Pw":"p"}"#), r#"{"Pw":"[REDACTED]","secret":"[REDACTED]"}"#);
+        let url = "https://api.telegram.org/bot123:abc/getMe".parse().unwrap();
+        assert_eq!(rules.redact_url(&url), "https://api.telegram.org/bot123:abc/getMe");
+
+        let config = ConfigLoader::new().with_override("logger.redaction.query_params", "[\" \"]").load().unwrap();
+        let Err(ConfigError::Invalid(issues)) = config.validate() else {
+            panic!("configuration should be invalid");
+        };
+        assert!(issues.iter().any(|issue| issue.key == "logger.redaction.query_params[0]"));
+    }
+
+    #[test]
+    fn test_parse_check_config_command() {
+        let args = CliArgs::parse(["check-config", "--config", "other.toml"]).unwrap();
+        assert_eq!(args.command, Some(CliCommand::CheckConfig));
+        assert!(CliArgs::parse(["check-config", "check-config"]).is_err());
+    }
 
-        assert!(!config.emby.base_url.is_empty(), "Base URL should not be empty");
-        assert!(!config.emby.api_key.is_empty(), "API key should not be empty");
+    #[test]
+    fn test_multiple_servers() {
+        let file = temp_file(
+            "servers.toml",
+            "[servers.us]\nbase_url = \"http://us:8096\"\napi_key = \"us-key\"\n\n\
+             [servers.eu]\nbase_url = \"http://eu:8096\"\napi_key = \"eu-key\"\nmax_users = 100\n",
+        );
+        let config = ConfigLoader::new().with_file(&file).load().unwrap();
+        let names: Vec<&str> = config.servers.iter().map(|server| server.name.as_str()).collect();
+        assert_eq!(names, ["eu", "us"]);
+        assert_eq!(config.server("eu").unwrap().max_users, Some(100));
+        assert!(config.server("main").is_none());
+        std::fs::remove_file(file).unwrap();
+
+        let config = ConfigLoader::new()
+            .with_override("servers.Main.base_url", "http://main:8096")
+            .with_override("servers.Main.api_key", "key")
+            .load()
+            .unwrap();
+        let Err(ConfigError::Invalid(issues)) = config.validate() else {
+            panic!("configuration should be invalid");
+        };
+        assert!(issues.iter().any(|issue| issue.key == "servers.Main"));
+        assert!(ConfigLoader::new().load().unwrap().validate().is_err(), "a server is required");
+    }
+
+    #[test]
+    fn test_legacy_emby_section_is_main_server() {
+        let config = ConfigLoader::new()
+            .with_env_vars([("PILIPILI__EMBY__BASE_URL", "http://legacy:8096")])
+            .with_override("emby.api_key", "legacy-key")
+            .load()
+            .unwrap();
+        assert_eq!(config.servers.len(), 1);
+        let server = config.server("main").unwrap();
+        assert_eq!(server.base_url, "http://legacy:8096");
+        assert_eq!(server.api_key, "legacy-key");
+
+        let result = ConfigLoader::new()
+            .with_override("emby.base_url", "http://legacy:8096")
+            .with_override("servers.main.base_url", "http://main:8096")
+            .load();
+        match result {
+            Err(ConfigError::Invalid(issues)) => assert_eq!(issues[0].key, "emby"),
+            other => panic!("both [emby] and [servers.main] should be rejected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_service_account_replaces_api_key() {
+        let issues = |kind: &str| {
+            let config = ConfigLoader::new()
+                .with_override("servers.main.base_url", "http://main:8096")
+                .with_override("servers.main.kind", kind)
+                .with_override("servers.main.username", "pilipili")
+                .with_override("telegram.bot_token", "123456:token")
+                .with_override("database.url", "sqlite::memory:")
+                .load()
+                .unwrap();
+            match config.validate() {
+                Ok(()) => Vec::new(),
+                Err(ConfigError::Invalid(issues)) => issues.into_iter().map(|issue| issue.key).collect(),
+                Err(error) => panic!("unexpected error: {}", error),
+            }
+        };
+        assert!(issues("emby").is_empty(), "an Emby service account needs no api_key");
+        assert_eq!(issues("jellyfin"), ["servers.main.username"]);
     }
-}
\ No newline at end of file
+}
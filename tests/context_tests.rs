@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+
+    use pilipili_bot::context::AppContext;
+    use pilipili_bot::infrastructure::api::EmbyAPI;
+    use pilipili_bot::infrastructure::config::{Config, ConfigLoader, DatabaseConfig};
+    use pilipili_bot::infrastructure::database::{Database, DatabaseError};
+    use pilipili_bot::infrastructure::network::{NetworkProvider, NetworkTarget, NetworkTask};
+
+    fn config(base_url: &str, api_key: &str) -> Config {
+        ConfigLoader::new()
+            .with_override("servers.main.base_url", base_url)
+            .with_override("servers.main.api_key", api_key)
+            .with_override("telegram.bot_token", "123456:token")
+            .with_override("database.url", "sqlite::memory:")
+            .load()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_emby_target_uses_given_settings() {
+        let settings = config("http://first:8096", "first-key").servers.remove(0);
+        let api = EmbyAPI::GetUser { user_id: "42".to_string() };
+        let target = api.target(&settings);
+
+        assert_eq!(target.base_url(), "http://first:8096");
+        assert_eq!(target.path(), "emby/Users/42");
+        let NetworkTask::RequestParameters(params) = target.task() else {
+            panic!("GetUser sends query parameters");
+        };
+        assert_eq!(params["api_key"], "first-key");
+    }
+
+    #[tokio::test]
+    async fn test_contexts_are_independent() {
+        let first = AppContext::connect(config("http://first:8096", "first-key"), NetworkProvider::new(vec![]))
+            .await
+            .unwrap();
+        let second = AppContext::connect(config("http://second:8096", "second-key"), NetworkProvider::new(vec![]))
+            .await
+            .unwrap();
+        let server = |context: &AppContext| context.servers().get("main").unwrap().settings();
+        assert_eq!(server(&first).base_url, "http://first:8096");
+        assert_eq!(server(&second).base_url, "http://second:8096");
+
+        let sections = first.replace_config(config("http://moved:8096", "first-key")).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(server(&first).base_url, "http://moved:8096");
+        assert_eq!(second.config().server("main").unwrap().base_url, "http://second:8096");
+    }
+
+    #[tokio::test]
+    async fn test_database_connect() {
+        let path = std::env::temp_dir().join(format!("pilipili_{}_db/nested/bot.db", std::process::id()));
+        let database = Database::connect(&DatabaseConfig { url: format!("sqlite://{}", path.display()) })
+            .await
+            .unwrap();
+        database.rbatis().exec("CREATE TABLE ping (id INTEGER)", vec![]).await.unwrap();
+        assert!(path.exists(), "missing directories are created");
+        std::fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+
+        let result = Database::connect(&DatabaseConfig { url: "oracle://db".to_string() }).await;
+        assert!(matches!(result, Err(DatabaseError::UnsupportedDriver { .. })));
+    }
+}
@@ -0,0 +1,267 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use pilipili_bot::bot::validators;
+    use pilipili_bot::bot::{Conversation, ConversationKey, ConversationState, ConversationStore, DeletionQueue};
+    use pilipili_bot::infrastructure::api::TelegramClient;
+    use pilipili_bot::infrastructure::config::telegram::TelegramConfig;
+    use pilipili_bot::infrastructure::database::{ConversationRecord, PendingDeletion};
+    use pilipili_bot::infrastructure::network::NetworkProvider;
+
+    use crate::common::database;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "step", rename_all = "snake_case")]
+    enum SignUp {
+        Name,
+        Confirm { name: String },
+    }
+
+    impl ConversationState for SignUp {
+        const FLOW: &'static str = "signup";
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Survey {
+        question: u32,
+    }
+
+    impl ConversationState for Survey {
+        const FLOW: &'static str = "survey";
+    }
+
+    #[tokio::test]
+    async fn test_conversation_steps() {
+        let database = database().await;
+        let store = ConversationStore::new(database.clone());
+        let key = ConversationKey::new(-100, 42);
+        let minute = Duration::from_secs(60);
+
+        assert_eq!(store.state::<SignUp>(key).await.unwrap(), None);
+        store.save(key, &SignUp::Name, minute).await.unwrap();
+        store.save(key, &SignUp::Confirm { name: "alice".to_string() }, minute).await.unwrap();
+        assert_eq!(
+            store.state::<SignUp>(key).await.unwrap(),
+            Some(SignUp::Confirm { name: "alice".to_string() })
+        );
+        assert!(store.state::<Survey>(key).await.unwrap().is_none(), "another flow is not returned");
+        assert_eq!(store.flow(key).await.unwrap().as_deref(), Some("signup"));
+        assert!(store.state::<SignUp>(ConversationKey::new(42, 42)).await.unwrap().is_none());
+
+        // Another flow replaces the conversation
+        store.save(key, &Survey { question: 1 }, minute).await.unwrap();
+        assert!(store.state::<SignUp>(key).await.unwrap().is_none());
+        assert!(store.finish(key).await.unwrap());
+        assert!(!store.finish(key).await.unwrap());
+
+        // A state that no longer decodes is dropped
+        store.save(key, &Survey { question: 1 }, minute).await.unwrap();
+        let mut record = database.conversation(key.chat_id, key.user_id).await.unwrap().unwrap();
+        record.flow = "signup".to_string();
+        database.save_conversation(&record).await.unwrap();
+        assert!(store.state::<SignUp>(key).await.unwrap().is_none());
+        assert!(database.conversation(key.chat_id, key.user_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_conversations() {
+        let database = database().await;
+        let store = ConversationStore::new(database.clone());
+        let now = chrono::Utc::now().timestamp();
+        let record = |user_id: i64, expires_at: i64| ConversationRecord {
+            chat_id: user_id,
+            user_id,
+            flow: "signup".to_string(),
+            state: r#"{"step":"name"}"#.to_string(),
+            expires_at,
+            updated_at: now,
+            expired_at: None,
+        };
+        database.save_conversation(&record(1, now - 1)).await.unwrap();
+        database.save_conversation(&record(2, now + 60)).await.unwrap();
+
+        assert!(store.state::<SignUp>(ConversationKey::new(1, 1)).await.unwrap().is_none());
+        assert_eq!(store.state::<SignUp>(ConversationKey::new(2, 2)).await.unwrap(), Some(SignUp::Name));
+        assert_eq!(
+            store.load::<SignUp>(ConversationKey::new(1, 1)).await.unwrap(),
+            Some(Conversation::Expired(SignUp::Name))
+        );
+        assert_eq!(
+            store.load::<SignUp>(ConversationKey::new(2, 2)).await.unwrap(),
+            Some(Conversation::Active(SignUp::Name))
+        );
+        assert!(store.load::<Survey>(ConversationKey::new(1, 1)).await.unwrap().is_none());
+
+        let expired = database.mark_expired_conversations(now).await.unwrap();
+        assert_eq!(expired, [ConversationRecord { expired_at: Some(now), ..record(1, now - 1) }]);
+        assert!(database.mark_expired_conversations(now).await.unwrap().is_empty());
+        assert!(store.flow(ConversationKey::new(2, 2)).await.unwrap().is_some());
+
+        // Expired conversations are kept until the grace period ends
+        assert_eq!(database.delete_expired_conversations(now - 1).await.unwrap(), 0);
+        assert!(store.load::<SignUp>(ConversationKey::new(1, 1)).await.unwrap().is_some());
+        assert_eq!(database.delete_expired_conversations(now).await.unwrap(), 1);
+        assert!(store.load::<SignUp>(ConversationKey::new(1, 1)).await.unwrap().is_none());
+        assert!(store.flow(ConversationKey::new(2, 2)).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_late_answers_after_the_reaper() {
+        let database = database().await;
+        let store = ConversationStore::new(database.clone());
+        let key = ConversationKey::new(7, 7);
+        store.save(key, &SignUp::Name, Duration::ZERO).await.unwrap();
+
+        let (api_url, received) = serve_bot_api().await;
+        let reaper = tokio::spawn(store.clone().run_expiry(Arc::new(telegram(api_url)), Duration::from_millis(10)));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        reaper.abort();
+
+        let sent: Vec<Value> = received.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1, "the user is told once: {:?}", sent);
+        assert_eq!(sent[0]["text"], "⌛ /signup timed out, send it again to start over");
+        assert_eq!(
+            store.load::<SignUp>(key).await.unwrap(),
+            Some(Conversation::Expired(SignUp::Name)),
+            "a late answer can still be cleaned up"
+        );
+        assert!(store.state::<SignUp>(key).await.unwrap().is_none());
+
+        // Starting over replaces the expired conversation
+        store.save(key, &SignUp::Name, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(database.mark_expired_conversations(chrono::Utc::now().timestamp()).await.unwrap(), []);
+        assert_eq!(store.state::<SignUp>(key).await.unwrap(), Some(SignUp::Name));
+    }
+
+    #[tokio::test]
+    async fn test_pending_deletions() {
+        let database = database().await;
+        let queue = DeletionQueue::new(database.clone());
+        let now = chrono::Utc::now().timestamp();
+        queue.schedule(7, 1, Duration::ZERO).await.unwrap();
+        queue.schedule(7, 2, Duration::from_secs(300)).await.unwrap();
+
+        let due = database.due_deletions(now + 1).await.unwrap();
+        assert_eq!(due, [PendingDeletion { chat_id: 7, message_id: 1, delete_at: due[0].delete_at }]);
+        assert_eq!(database.due_deletions(now + 301).await.unwrap().len(), 2);
+
+        database.remove_deletion(7, 1).await.unwrap();
+        assert!(database.due_deletions(now + 1).await.unwrap().is_empty());
+    }
+
+    /// Sends every message and records the request bodies. Answers
+    /// `deleteMessage` calls depending on the message id: 1 is deleted, 2 was
+    /// already gone, 3 hits a server error and 4 a rate limit.
+    async fn serve_bot_api() -> (String, Arc<Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorded = received.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = vec![0; 4096];
+                let body = loop {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((_, body)) = text.split_once("\r\n\r\n")
+                        && (body.ends_with('}') || read == 0)
+                    {
+                        break body.to_string();
+                    }
+                };
+                let body: Value = serde_json::from_str(&body).unwrap();
+                let (status, payload) = match body["message_id"].as_i64() {
+                    None => (
+                        "200 OK",
+                        r#"{"ok":true,"result":{"message_id":9,"chat":{"id":7,"type":"private"},"date":0}}"#,
+                    ),
+                    Some(1) => ("200 OK", r#"{"ok":true,"result":true}"#),
+                    Some(2) => (
+                        "400 Bad Request",
+                        r#"{"ok":false,"error_code":400,"description":"Bad Request: message to delete not found"}"#,
+                    ),
+                    Some(3) => (
+                        "500 Internal Server Error",
+                        r#"{"ok":false,"error_code":500,"description":"Internal Server Error"}"#,
+                    ),
+                    _ => (
+                        "429 Too Many Requests",
+                        r#"{"ok":false,"error_code":429,"description":"Too Many Requests","parameters":{"retry_after":1}}"#,
+                    ),
+                };
+                recorded.lock().unwrap().push(body);
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    payload.len(),
+                    payload
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (format!("http://{}", address), received)
+    }
+
+    fn telegram(api_url: String) -> TelegramClient {
+        let settings = TelegramConfig {
+            bot_token: "123456:token".to_string(),
+            api_url,
+            ..TelegramConfig::default()
+        };
+        TelegramClient::new(NetworkProvider::new(vec![]), settings)
+    }
+
+    #[tokio::test]
+    async fn test_failed_deletions_stay_pending() {
+        let database = database().await;
+        let queue = DeletionQueue::new(database.clone());
+        for message_id in 1..=4 {
+            queue.schedule(7, message_id, Duration::ZERO).await.unwrap();
+        }
+        let now = chrono::Utc::now().timestamp() + 1;
+        let pending = || async {
+            let due = database.due_deletions(now).await.unwrap();
+            due.into_iter().map(|deletion| deletion.message_id).collect::<Vec<_>>()
+        };
+
+        // Telegram cannot be reached
+        let unreachable = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        queue.delete_due(&telegram(format!("http://{}", unreachable)), now).await.unwrap();
+        assert_eq!(pending().await, [1, 2, 3, 4]);
+
+        // Deleted and refused messages are done, the others are tried again
+        let (api_url, _) = serve_bot_api().await;
+        queue.delete_due(&telegram(api_url), now).await.unwrap();
+        assert_eq!(pending().await, [3, 4]);
+    }
+
+    #[test]
+    fn test_validators() {
+        assert_eq!(validators::yes_no(" Yes"), Ok(true));
+        assert_eq!(validators::yes_no("n"), Ok(false));
+        assert!(validators::yes_no("maybe").is_err());
+
+        assert!(validators::username("").is_err());
+        assert!(validators::username(&"a".repeat(65)).is_err());
+        assert_eq!(validators::username("Jane Doe"), Ok("Jane Doe".to_string()));
+
+        let servers = ["eu".to_string(), "us".to_string()];
+        assert_eq!(validators::server(" us ", &servers), Ok("us".to_string()));
+        assert_eq!(validators::server("asia", &servers), Err("Choose one of: eu, us".to_string()));
+
+        assert!(validators::password("hunter2hunter2", 8).is_ok());
+        assert!(validators::password("hunter2", 8).is_err());
+    }
+}
@@ -1,16 +1,21 @@
 #[cfg(test)]
 mod tests {
-    
-    use tokio;
+
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
 
     use pilipili_bot::infrastructure::api::*;
+    use pilipili_bot::infrastructure::api::emby_types::{QueryResult, UserInfo};
+    use pilipili_bot::infrastructure::config::{Config, ConfigLoader, EmbyConfig, ServerKind};
     use pilipili_bot::infrastructure::logger::builder::LoggerBuilder;
     use pilipili_bot::infrastructure::logger::LogLevel;
     use pilipili_bot::infrastructure::network::*;
 
     #[tokio::test]
     async fn test_emby_api_request_with_provider() {
-        LoggerBuilder::default()
+        let _guard = LoggerBuilder::default()
             .with_level(LogLevel::Debug)
             .init();
         
@@ -18,9 +23,11 @@ mod tests {
             user_id: "56ed750c57e14553ba2b3bd9c531e1a3".to_string()
         };
 
-        let provider = NetworkProvider::new(vec![Box::new(CurlPlugin)]);
+        let provider = NetworkProvider::new(vec![Box::new(CurlPlugin::default())]);
+        let server = Config::init().unwrap().servers.remove(0);
+        let client = EmbyClient::new(provider, server);
 
-        match provider.send_request(&api).await {
+        match client.send(&api).await {
             Ok(res) => {
                 assert!(res.status().is_success(), "Request failed with status: {}", res.status());
                 let body = res.text().await.unwrap();
@@ -30,4 +37,217 @@ mod tests {
             Err(e) => panic!("Request failed: {}", e),
         }
     }
+
+    fn server(kind: ServerKind) -> EmbyConfig {
+        EmbyConfig {
+            kind,
+            base_url: "http://media:8096".to_string(),
+            api_key: "secret".to_string(),
+            ..EmbyConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_targets_per_server_kind() {
+        let api = EmbyAPI::GetUser { user_id: "42".to_string() };
+
+        let emby = server(ServerKind::Emby);
+        let target = api.target(&emby);
+        assert_eq!(target.path(), "emby/Users/42");
+        let NetworkTask::RequestParameters(params) = target.task() else {
+            panic!("GetUser sends query parameters");
+        };
+        assert_eq!(params["api_key"], "secret");
+
+        let jellyfin = server(ServerKind::Jellyfin);
+        let target = api.jellyfin_target(&jellyfin);
+        assert_eq!(target.path(), "Users/42");
+        assert_eq!(target.path_template(), "Users/{user_id}");
+        let NetworkTask::RequestParameters(params) = target.task() else {
+            panic!("GetUser sends query parameters");
+        };
+        assert!(params.is_empty(), "Jellyfin takes the key in a header");
+        let headers = target.headers().unwrap();
+        let authorization = headers.iter().find(|header| header.name() == "authorization").unwrap();
+        assert!(authorization.value().starts_with("MediaBrowser Client=\"PiliPili Bot\""));
+        assert!(authorization.value().ends_with(", Token=\"secret\""));
+        assert!(authorization.is_sensitive());
+
+        let api = EmbyAPI::UpdatePassword { user_id: "42".to_string(), new_password: "new".to_string() };
+        let target = api.target(&emby);
+        assert_eq!(target.path(), "emby/Users/42/Password");
+        assert!(matches!(target.method(), HttpMethod::Post));
+        let NetworkTask::RequestJson(body) = target.task() else {
+            panic!("UpdatePassword sends a JSON body");
+        };
+        assert_eq!(body["NewPw"], "new");
+        let headers = target.headers().unwrap();
+        let authorization = headers.iter().find(|header| header.name() == "X-Emby-Authorization").unwrap();
+        assert!(authorization.value().ends_with(", Token=\"secret\""), "the api_key authorizes JSON requests");
+        assert_eq!(api.jellyfin_target(&jellyfin).path(), "Users/42/Password");
+
+        assert_eq!(EmbyAPI::GetUsers.target(&emby).path(), "emby/Users/Query");
+        assert_eq!(EmbyAPI::GetUsers.jellyfin_target(&jellyfin).path(), "Users");
+
+        let api = EmbyAPI::UpdatePolicy { user_id: "42".to_string(), policy: serde_json::json!({"IsDisabled": true}) };
+        let target = api.target(&emby);
+        assert_eq!(target.path(), "emby/Users/42/Policy");
+        assert!(matches!(target.method(), HttpMethod::Post));
+        let NetworkTask::RequestJson(body) = target.task() else {
+            panic!("UpdatePolicy sends a JSON body");
+        };
+        assert_eq!(body["IsDisabled"], true);
+        assert_eq!(api.jellyfin_target(&jellyfin).path(), "Users/42/Policy");
+
+        let api = EmbyAPI::DeleteUser { user_id: "42".to_string() };
+        assert!(matches!(api.target(&emby).method(), HttpMethod::Delete));
+        assert_eq!(api.jellyfin_target(&jellyfin).path(), "Users/42");
+
+        let api = EmbyAPI::StopPlayback { session_id: "s1".to_string() };
+        assert_eq!(api.target(&emby).path(), "emby/Sessions/s1/Playing/Stop");
+        assert_eq!(api.jellyfin_target(&jellyfin).path(), "Sessions/s1/Playing/Stop");
+
+        let api = EmbyAPI::DeleteDevice { device_id: "d1".to_string() };
+        let target = api.target(&emby);
+        assert_eq!(target.path(), "emby/Devices");
+        assert!(matches!(target.method(), HttpMethod::Delete));
+        let NetworkTask::RequestParameters(params) = target.task() else {
+            panic!("DeleteDevice sends query parameters");
+        };
+        assert_eq!(params["Id"], "d1");
+    }
+
+    #[test]
+    fn test_emby_user_page() {
+        let body = r#"{"Items":[{"Id":"1","Name":"alice","Policy":{"IsDisabled":true}}],"TotalRecordCount":1}"#;
+        let page: QueryResult<UserInfo> = serde_json::from_str(body).unwrap();
+        assert_eq!(page.items[0].name, "alice");
+        assert!(page.items[0].policy.as_ref().unwrap().is_disabled);
+    }
+
+    #[test]
+    fn test_servers_follow_kind() {
+        let config = |kind: &str| {
+            ConfigLoader::new()
+                .with_override("servers.main.base_url", "http://media:8096")
+                .with_override("servers.main.api_key", "secret")
+                .with_override("servers.main.kind", kind)
+                .with_override("telegram.bot_token", "123456:token")
+                .load()
+                .unwrap()
+        };
+        let servers = EmbyServers::new(NetworkProvider::new(vec![]).into(), &config("jellyfin").servers);
+        let before = servers.get("main").unwrap();
+        assert_eq!(before.kind(), ServerKind::Jellyfin);
+
+        servers.update(&config("emby").servers);
+        assert_eq!(servers.get("main").unwrap().kind(), ServerKind::Emby);
+        assert_eq!(before.kind(), ServerKind::Jellyfin, "a changed kind gets a new client");
+        assert!(ConfigLoader::new().with_override("servers.main.kind", "plex").load().is_err());
+    }
+
+    /// Serves a fake Emby server that issues `token-1`, `token-2`, ... and
+    /// rejects `token-1`, recording the first line and token of each request.
+    async fn serve_emby() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorded = received.clone();
+
+        tokio::spawn(async move {
+            let mut issued = 0;
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buffer = vec![0; 8192];
+                let read = socket.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+                let line = request.lines().next().unwrap_or_default().to_string();
+                let header = |name: &str| {
+                    request
+                        .split(&format!("{}=\"", name))
+                        .nth(1)
+                        .and_then(|rest| rest.split('"').next())
+                        .unwrap_or("-")
+                        .to_string()
+                };
+                let token = header("Token");
+                if line.contains("AuthenticateByName") || line.contains("Logout") {
+                    recorded.lock().unwrap().push(format!("{} {} {}", line, token, header("DeviceId")));
+                } else {
+                    recorded.lock().unwrap().push(format!("{} {}", line, token));
+                }
+
+                let (status, body) = if line.contains("AuthenticateByName") {
+                    issued += 1;
+                    ("200 OK", format!(r#"{{"User":{{"Id":"s","Name":"pilipili"}},"AccessToken":"token-{}"}}"#, issued))
+                } else if token == "token-1" {
+                    ("401 Unauthorized", String::new())
+                } else {
+                    ("200 OK", r#"{"Items":[{"Id":"1","Name":"alice"}],"TotalRecordCount":1}"#.to_string())
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (format!("http://{}", address), received)
+    }
+
+    #[tokio::test]
+    async fn test_service_account_signs_in_again_on_401() {
+        let (base_url, received) = serve_emby().await;
+        let settings = EmbyConfig {
+            base_url,
+            username: Some("pilipili".to_string()),
+            password: Some("secret".to_string()),
+            ..EmbyConfig::default()
+        };
+        let client = EmbyClient::new(NetworkProvider::new(vec![]), settings);
+
+        let users = client.users().await.unwrap();
+        assert_eq!(users[0].name, "alice");
+        client.users().await.unwrap();
+
+        let received = received.lock().unwrap().clone();
+        let requests: Vec<&str> = received.iter().map(|request| request.as_str()).collect();
+        assert_eq!(
+            requests,
+            [
+                "POST /emby/Users/AuthenticateByName HTTP/1.1 - pilipili-bot-main",
+                "GET /emby/Users/Query HTTP/1.1 token-1",
+                "POST /emby/Users/AuthenticateByName HTTP/1.1 - pilipili-bot-main",
+                "GET /emby/Users/Query HTTP/1.1 token-2",
+                "GET /emby/Users/Query HTTP/1.1 token-2",
+            ],
+            "the token is cached and renewed once rejected, without an api_key"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_credential_check_uses_own_device_and_logs_out() {
+        let (base_url, received) = serve_emby().await;
+        let settings = EmbyConfig {
+            name: "main".to_string(),
+            base_url,
+            ..EmbyConfig::default()
+        };
+        let client = EmbyClient::new(NetworkProvider::new(vec![]), settings);
+
+        let result = client.authenticate("alice", "secret").await.unwrap();
+        assert_eq!(result.access_token, "token-1");
+
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 2);
+        let sign_in: Vec<&str> = received[0].split(' ').collect();
+        let logout: Vec<&str> = received[1].split(' ').collect();
+        assert_eq!(sign_in[..2], ["POST", "/emby/Users/AuthenticateByName"]);
+        assert!(sign_in[4].starts_with("pilipili-check-"), "unexpected device: {}", sign_in[4]);
+        assert_eq!(logout[..2], ["POST", "/emby/Sessions/Logout"]);
+        assert_eq!(logout[3], "token-1", "the check's own session is logged out");
+        assert_eq!(logout[4], sign_in[4]);
+    }
 }
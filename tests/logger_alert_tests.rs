@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+
+    use std::error::Error;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use tracing::Level;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use pilipili_bot::{error_log, info_log, warn_log};
+    use pilipili_bot::infrastructure::logger::*;
+
+    /// Records messages and logs an error while sending, like a failing sink would.
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        messages: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl AlertSink for RecordingSink {
+        async fn send_alert(&self, text: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.messages.lock().unwrap().push(text);
+            error_log!("[NETWORK]", "Request failed while sending the alert");
+            Ok(())
+        }
+    }
+
+    fn alert(message: &str) -> Alert {
+        Alert {
+            level: Level::ERROR,
+            domain: "[EMBY]".to_string(),
+            message: message.to_string(),
+            location: Some("src/emby.rs:42".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_batch_merges_identical_alerts() {
+        let mut batch = AlertBatch::new();
+        batch.push(alert("Emby is unreachable"));
+        batch.push(alert("Emby is unreachable"));
+        batch.push(alert("User not found"));
+
+        assert_eq!(
+            batch.take_message().unwrap(),
+            "⚠️ 3 log alerts\n\
+             \nERROR [EMBY] Emby is unreachable (src/emby.rs:42) ×2\
+             \nERROR [EMBY] User not found (src/emby.rs:42)"
+        );
+        assert!(batch.is_empty());
+        assert!(batch.take_message().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_forwarder_batches_burst_and_ignores_own_logs() {
+        let (layer, receiver) = AlertLayer::new(LogLevel::Warn);
+        let _default = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+
+        let sink = RecordingSink::default();
+        let forwarder = AlertForwarder::new(receiver, sink.clone())
+            .with_batch_window(Duration::from_millis(50))
+            .with_min_interval(Duration::from_millis(50));
+        tokio::spawn(forwarder.run());
+
+        for _ in 0..3 {
+            error_log!("[EMBY]", "Emby is unreachable");
+        }
+        warn_log!("[EMBY]", user_id = 42, "Slow response");
+        info_log!("[EMBY]", "Not forwarded");
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let messages = sink.messages.lock().unwrap();
+        assert_eq!(messages.len(), 1, "unexpected messages: {:?}", *messages);
+        assert!(messages[0].starts_with("⚠️ 4 log alerts"));
+        assert!(messages[0].contains("ERROR [EMBY] Emby is unreachable (tests/logger_alert_tests.rs:"));
+        assert!(messages[0].contains("×3"));
+        assert!(messages[0].contains("WARN [EMBY] Slow response user_id=42"));
+        assert!(!messages[0].contains("Not forwarded"));
+    }
+}
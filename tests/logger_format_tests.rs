@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod tests {
+
+    use pilipili_bot::info_log;
+    use pilipili_bot::infrastructure::logger::builder::LoggerBuilder;
+    use pilipili_bot::infrastructure::logger::{LogFormat, LogLevel, LogRotation};
+
+    #[test]
+    fn test_json_file_output_has_domain_field() {
+        let directory = std::env::temp_dir().join(format!("pilipili_json_logs_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let guard = LoggerBuilder::default()
+            .with_level(LogLevel::Info)
+            .with_directory(directory.to_str().unwrap())
+            .with_file_prefix("json")
+            .with_rolling(LogRotation::Never)
+            .with_file_format(LogFormat::Json)
+            .init();
+
+        info_log!("[CONFIG]", "Config loaded");
+        // Flushes the background file writer
+        drop(guard);
+
+        let content = std::fs::read_to_string(directory.join("json")).unwrap();
+        let line: serde_json::Value = serde_json::from_str(content.lines().last().unwrap()).unwrap();
+        assert_eq!(line["domain"], "CONFIG");
+        assert_eq!(line["message"], "Config loaded");
+        assert_eq!(line["level"], "INFO");
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_log_format_parsing() {
+        assert_eq!("JSON".parse::<LogFormat>(), Ok(LogFormat::Json));
+        assert_eq!("pretty".parse::<LogFormat>(), Ok(LogFormat::Pretty));
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod tests {
+
+    use pilipili_bot::infrastructure::config::LoggerConfig;
+    use pilipili_bot::infrastructure::logger::builder::LoggerBuilder;
+    use pilipili_bot::infrastructure::logger::{LogFormat, LogLevel, LogRotation};
+
+    #[test]
+    fn test_reload_filter() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut config: LoggerConfig = toml::from_str(
+            r#"
+            level = "trace"
+            directives = ["pilipili_bot::infrastructure::network=debug"]
+            directory = "logs"
+            rotation = "never"
+            file_format = "json"
+            console = false
+            "#,
+        )
+            .unwrap();
+        assert_eq!(config.level, LogLevel::Trace);
+        assert_eq!(config.rotation, LogRotation::Never);
+        assert_eq!(config.file_format, LogFormat::Json);
+        assert_eq!(config.console_format, LogFormat::Compact);
+        config.directory = directory.path().to_string_lossy().into_owned();
+
+        let guard = LoggerBuilder::from_config(&config)
+            .with_file_prefix("reload")
+            .init();
+        let logger = guard.handle().unwrap();
+
+        logger.set_filter("warn,pilipili_bot::infrastructure::network=debug").unwrap();
+        let filter = logger.current_filter().unwrap();
+        assert!(filter.contains("warn"), "unexpected filter: {}", filter);
+        assert!(filter.contains("pilipili_bot::infrastructure::network=debug"));
+
+        assert!(logger.set_filter("network=loud").is_err());
+        assert_eq!(logger.current_filter().unwrap(), filter);
+    }
+}
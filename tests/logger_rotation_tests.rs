@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod tests {
+
+    use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    use flate2::read::GzDecoder;
+    use pilipili_bot::infrastructure::logger::{LogRotation, RetentionPolicy, RollingFileWriter};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir()
+            .join(format!("pilipili_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    fn file_names(directory: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_size_rotation_compresses_and_keeps_max_files() {
+        let directory = temp_dir("size_rotation");
+        let policy = RetentionPolicy::new(LogRotation::Never)
+            .with_max_file_size(10)
+            .with_max_files(2)
+            .with_compression(true);
+        let writer = RollingFileWriter::new(&directory, "app", policy).unwrap();
+
+        for line in ["line one\n", "line two\n", "line three\n", "line four\n"] {
+            (&writer).write_all(line.as_bytes()).unwrap();
+        }
+        writer.run_maintenance().unwrap();
+
+        assert_eq!(file_names(&directory), vec!["app", "app.2.gz", "app.3.gz"]);
+        assert_eq!(std::fs::read_to_string(directory.join("app")).unwrap(), "line four\n");
+
+        let mut content = String::new();
+        GzDecoder::new(std::fs::File::open(directory.join("app.3.gz")).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "line three\n");
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_size_rotation_never_reuses_indices() {
+        let directory = temp_dir("rotation_indices");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("app.5.gz"), "").unwrap();
+
+        let policy = RetentionPolicy::new(LogRotation::Never).with_max_file_size(10);
+        let writer = RollingFileWriter::new(&directory, "app", policy).unwrap();
+        for line in ["line one\n", "line two\n", "line three\n"] {
+            (&writer).write_all(line.as_bytes()).unwrap();
+        }
+
+        assert_eq!(file_names(&directory), vec!["app", "app.5.gz", "app.6", "app.7"]);
+        assert_eq!(std::fs::read_to_string(directory.join("app.7")).unwrap(), "line two\n");
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_max_age_removes_only_old_log_files() {
+        let directory = temp_dir("max_age");
+        std::fs::create_dir_all(&directory).unwrap();
+        let old = SystemTime::now() - Duration::from_secs(3 * 24 * 60 * 60);
+        for name in ["app.2020-01-01", "app.2020-01-02", "unrelated.txt"] {
+            let file = std::fs::File::create(directory.join(name)).unwrap();
+            file.set_modified(old).unwrap();
+        }
+        std::fs::write(directory.join("app.2020-01-03"), "recent").unwrap();
+
+        let policy = RetentionPolicy::new(LogRotation::Daily)
+            .with_max_age(Duration::from_secs(24 * 60 * 60));
+        let writer = RollingFileWriter::new(&directory, "app", policy).unwrap();
+        writer.run_maintenance().unwrap();
+
+        let active = writer.current_path().file_name().unwrap().to_string_lossy().into_owned();
+        let mut expected = vec![active, "app.2020-01-03".to_owned(), "unrelated.txt".to_owned()];
+        expected.sort();
+        assert_eq!(file_names(&directory), expected);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -3,11 +3,11 @@ mod tests {
 
     use pilipili_bot::{debug_log, error_log, info_log, trace_log, warn_log};
     use pilipili_bot::infrastructure::logger::builder::LoggerBuilder;
-    use pilipili_bot::infrastructure::logger::LogLevel;
+    use pilipili_bot::infrastructure::logger::{LogCapture, LogLevel, LoggerError};
 
     #[test]
     fn test_log() {
-        LoggerBuilder::default()
+        let _guard = LoggerBuilder::default()
             .with_level(LogLevel::Trace)
             .init();
 
@@ -17,4 +17,86 @@ mod tests {
         error_log!("[DOMAIN4]", "This is a error log.");
         trace_log!("This is a trace log.");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_init_is_idempotent() {
+        let first = LoggerBuilder::default().init();
+        let second = LoggerBuilder::default().init();
+        assert!(first.handle().is_some());
+        assert!(second.handle().is_some());
+
+        let result = LoggerBuilder::default().try_init();
+        assert!(matches!(result, Err(LoggerError::AlreadyInitialized)));
+    }
+
+    #[test]
+    fn test_capture_log_lines() {
+        let capture = LogCapture::new();
+        capture.run(|| {
+            debug_log!("[DOMAIN1]", "This is a debug log.");
+            warn_log!("[DOMAIN2]", "This is a warn log.");
+        });
+
+        assert_eq!(
+            capture.lines(),
+            vec!["DEBUG [DOMAIN1] This is a debug log.", "WARN [DOMAIN2] This is a warn log."]
+        );
+        assert!(capture.contains("warn log"));
+    }
+
+    #[test]
+    fn test_log_with_fields_and_format_arguments() {
+        let capture = LogCapture::new();
+        let user_id = 42;
+        let name = "alice";
+        capture.run(|| {
+            info_log!("[EMBY]", user_id = %user_id, "Created user {}", name);
+            info_log!("[EMBY]", "Deleted {} users", 3);
+            warn_log!("[EMBY]", ?name, "Password reset");
+        });
+
+        assert_eq!(
+            capture.lines(),
+            vec![
+                "INFO [EMBY] Created user alice user_id=42",
+                "INFO [EMBY] Deleted 3 users",
+                "WARN [EMBY] Password reset name=\"alice\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_by_domain() {
+        let capture = LogCapture::new()
+            .with_filter("warn,EMBY=debug")
+            .unwrap();
+        capture.run(|| {
+            debug_log!("[EMBY]", "Fetching user");
+            debug_log!("[NETWORK]", "Sending request");
+            warn_log!("[NETWORK]", "Retrying request");
+        });
+
+        assert_eq!(
+            capture.lines(),
+            vec!["DEBUG [EMBY] Fetching user", "WARN [NETWORK] Retrying request"]
+        );
+        assert!(LogCapture::new().with_filter("EMBY=loud").is_err());
+    }
+
+    #[test]
+    fn test_filter_by_module_path() {
+        let capture = LogCapture::new()
+            .with_filter("warn,logger_tests=debug,NETWORK=error")
+            .unwrap();
+        capture.run(|| {
+            debug_log!("[EMBY]", "Fetching user");
+            warn_log!("[NETWORK]", "Retrying request");
+            error_log!("[NETWORK]", "Request failed");
+        });
+
+        assert_eq!(
+            capture.lines(),
+            vec!["DEBUG [EMBY] Fetching user", "ERROR [NETWORK] Request failed"]
+        );
+    }
+}
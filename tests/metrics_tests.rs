@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use pilipili_bot::infrastructure::metrics::*;
+    use pilipili_bot::infrastructure::network::*;
+    use pilipili_bot::infrastructure::network::metrics_plugin::HTTP_REQUESTS;
+
+    struct UserTarget {
+        base_url: String,
+        user_id: String,
+    }
+
+    impl NetworkTarget for UserTarget {
+        fn base_url(&self) -> String {
+            self.base_url.clone()
+        }
+
+        fn path(&self) -> String {
+            format!("emby/Users/{}", self.user_id)
+        }
+
+        fn path_template(&self) -> String {
+            "emby/Users/{user_id}".to_string()
+        }
+
+        fn method(&self) -> HttpMethod {
+            HttpMethod::Get
+        }
+
+        fn task(&self) -> NetworkTask {
+            NetworkTask::RequestPlain
+        }
+    }
+
+    /// Answers every request with `404 Not Found`.
+    async fn serve_not_found() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buffer = vec![0; 4096];
+                let _ = socket.read(&mut buffer).await.unwrap();
+                let response = "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_labelled_by_path_template() {
+        let base_url = serve_not_found().await;
+        let registry = Arc::new(MetricsRegistry::new());
+        let provider = NetworkProvider::new(vec![Box::new(MetricsPlugin::new(registry.clone()))]);
+
+        for user_id in ["a", "b"] {
+            let target = UserTarget { base_url: base_url.clone(), user_id: user_id.to_string() };
+            provider.send_request(&target).await.unwrap();
+        }
+
+        let labels = [
+            ("method", "GET"),
+            ("host", "127.0.0.1"),
+            ("target", "emby/Users/{user_id}"),
+            ("status", "4xx"),
+        ];
+        assert_eq!(registry.counter_value(&HTTP_REQUESTS, &labels), 2);
+        let rendered = registry.render();
+        assert!(rendered.contains("pilipili_http_request_duration_seconds_count{host=\"127.0.0.1\",method=\"GET\",target=\"emby/Users/{user_id}\"} 2"));
+        assert!(!rendered.contains("emby/Users/a"));
+    }
+
+    #[tokio::test]
+    async fn test_exporter_serves_prometheus_text() {
+        let registry = Arc::new(MetricsRegistry::new());
+        BotMetrics::new(registry.clone()).command_handled("start", true);
+        BotMetrics::new(registry.clone()).account_disabled("expired");
+
+        let exporter = MetricsExporter::bind("127.0.0.1:0", registry).await.unwrap();
+        let address = exporter.local_addr().unwrap();
+        tokio::spawn(exporter.serve());
+
+        let mut socket = TcpStream::connect(address).await.unwrap();
+        socket.write_all(b"GET /metrics HTTP/1.1\r\nhost: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        socket.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("# TYPE pilipili_bot_commands_total counter"));
+        assert!(response.contains("pilipili_bot_commands_total{command=\"start\",outcome=\"success\"} 1"));
+        assert!(response.contains("pilipili_bot_disables_total{reason=\"expired\"} 1"));
+    }
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        const LATENCY: Metric = Metric::histogram("latency_seconds", "Latency.", &[0.1, 1.0]);
+        let registry = MetricsRegistry::new();
+        registry.observe(&LATENCY, &[], 0.05);
+        registry.observe(&LATENCY, &[], 0.5);
+        registry.observe(&LATENCY, &[], 5.0);
+
+        let rendered = registry.render();
+        assert!(rendered.contains("latency_seconds_bucket{le=\"0.1\"} 1"));
+        assert!(rendered.contains("latency_seconds_bucket{le=\"1\"} 2"));
+        assert!(rendered.contains("latency_seconds_bucket{le=\"+Inf\"} 3"));
+        assert!(rendered.contains("latency_seconds_count 3"));
+    }
+}
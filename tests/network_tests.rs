@@ -0,0 +1,417 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use pilipili_bot::infrastructure::api::telegram_api::TelegramAPI;
+    use pilipili_bot::infrastructure::config::telegram::TelegramConfig;
+    use pilipili_bot::infrastructure::logger::LogCapture;
+    use pilipili_bot::infrastructure::network::*;
+
+    const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    struct LocalTarget {
+        base_url: String,
+        method: HttpMethod,
+        headers: Option<NetworkHeaders>,
+    }
+
+    impl NetworkTarget for LocalTarget {
+        fn base_url(&self) -> String {
+            self.base_url.clone()
+        }
+
+        fn path(&self) -> String {
+            "file".to_string()
+        }
+
+        fn method(&self) -> HttpMethod {
+            self.method
+        }
+
+        fn task(&self) -> NetworkTask {
+            NetworkTask::RequestPlain
+        }
+
+        fn headers(&self) -> Option<NetworkHeaders> {
+            self.headers.clone()
+        }
+    }
+
+    /// Serves `BODY` on a local port, honouring `Range: bytes=<n>-` headers.
+    async fn serve_body() -> LocalTarget {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buffer = vec![0; 4096];
+                let read = socket.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).to_lowercase();
+
+                let offset = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+
+                let (status, body) = match offset {
+                    Some(offset) => ("206 Partial Content", &BODY[offset..]),
+                    None => ("200 OK", BODY),
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(body).await.unwrap();
+            }
+        });
+
+        LocalTarget {
+            base_url: format!("http://{}", address),
+            method: HttpMethod::Get,
+            headers: None,
+        }
+    }
+
+    /// Answers every request with `503 Service Unavailable` until `failures`
+    /// requests have been served, then with `200 OK`.
+    async fn serve_flaky(failures: usize) -> (LocalTarget, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buffer = vec![0; 4096];
+                let _ = socket.read(&mut buffer).await.unwrap();
+                let served = counter.fetch_add(1, Ordering::SeqCst);
+                let status = if served < failures { "503 Service Unavailable" } else { "200 OK" };
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    status
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let target = LocalTarget {
+            base_url: format!("http://{}", address),
+            method: HttpMethod::Get,
+            headers: None,
+        };
+        (target, requests)
+    }
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("pilipili_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn test_stream_reads_whole_body() {
+        let target = serve_body().await;
+        let provider = NetworkProvider::new(vec![]);
+
+        let mut stream = provider.send_request_stream(&target).await.unwrap();
+        let mut body = Vec::new();
+        while let Some(chunk) = stream.next_chunk().await.unwrap() {
+            body.extend_from_slice(&chunk);
+        }
+
+        assert_eq!(body, BODY);
+        assert_eq!(stream.received(), BODY.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_download_reports_progress() {
+        let target = serve_body().await;
+        let provider = NetworkProvider::new(vec![]);
+        let path = temp_file("progress");
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let recorded = reports.clone();
+        let options = DownloadOptions::default()
+            .with_progress(move |progress| recorded.lock().unwrap().push(progress));
+
+        let size = provider.download(&target, &path, options).await.unwrap();
+
+        assert_eq!(size, BODY.len() as u64);
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+        let last = *reports.lock().unwrap().last().unwrap();
+        assert_eq!(last.downloaded, BODY.len() as u64);
+        assert_eq!(last.total, Some(BODY.len() as u64));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_partial_file() {
+        let target = serve_body().await;
+        let provider = NetworkProvider::new(vec![]);
+        let path = temp_file("resume");
+        std::fs::write(&path, &BODY[..10]).unwrap();
+
+        let options = DownloadOptions::default().with_resume(true);
+        let size = provider.download(&target, &path, options).await.unwrap();
+
+        assert_eq!(size, BODY.len() as u64);
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_rejects_oversized_body() {
+        let target = serve_body().await;
+        let provider = NetworkProvider::new(vec![]);
+        let path = temp_file("limit");
+
+        let options = DownloadOptions::default().with_max_size(8);
+        let result = provider.download(&target, &path, options).await;
+
+        assert!(matches!(result, Err(NetworkError::SizeLimitExceeded { limit: 8 })));
+        assert!(!path.exists(), "Oversized download should be removed");
+    }
+
+    #[tokio::test]
+    async fn test_oversized_resume_keeps_partial_file() {
+        let target = serve_body().await;
+        let provider = NetworkProvider::new(vec![]);
+        let path = temp_file("resume_limit");
+        std::fs::write(&path, &BODY[..10]).unwrap();
+
+        let options = DownloadOptions::default().with_resume(true).with_max_size(12);
+        let result = provider.download(&target, &path, options).await;
+
+        assert!(matches!(result, Err(NetworkError::SizeLimitExceeded { limit: 12 })));
+        assert_eq!(std::fs::read(&path).unwrap(), &BODY[..10], "the resumed part is kept");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_http_method_conversions() {
+        assert_eq!("patch".parse::<HttpMethod>(), Ok(HttpMethod::Patch));
+        assert_eq!("HEAD".parse::<HttpMethod>(), Ok(HttpMethod::Head));
+        assert!("FETCH".parse::<HttpMethod>().is_err());
+
+        assert_eq!(reqwest::Method::from(HttpMethod::Options), reqwest::Method::OPTIONS);
+        assert_eq!(HttpMethod::try_from(reqwest::Method::PATCH), Ok(HttpMethod::Patch));
+
+        assert!(HttpMethod::Head.is_safe());
+        assert!(HttpMethod::Put.is_idempotent());
+        assert!(!HttpMethod::Post.is_idempotent());
+        assert!(!HttpMethod::Patch.is_idempotent());
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_request_is_retried() {
+        let (target, requests) = serve_flaky(2).await;
+        let provider = NetworkProvider::new(vec![])
+            .with_retry_policy(RetryPolicy::new(3).with_base_delay(Duration::from_millis(1)));
+
+        let response = provider.send_request(&target).await.unwrap();
+
+        assert!(response.status().is_success());
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_non_idempotent_request_is_not_retried() {
+        let (mut target, requests) = serve_flaky(2).await;
+        target.method = HttpMethod::Post;
+        let provider = NetworkProvider::new(vec![])
+            .with_retry_policy(RetryPolicy::new(3).with_base_delay(Duration::from_millis(1)));
+
+        let response = provider.send_request(&target).await.unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_headers_keep_repeats_and_sensitivity() {
+        let header_map = NetworkHeaders::new()
+            .with("accept-language", "en")
+            .with(String::from("accept-language"), "zh")
+            .with_sensitive("x-emby-token", "secret")
+            .to_header_map()
+            .unwrap();
+
+        let languages: Vec<_> = header_map.get_all("accept-language").iter().collect();
+        assert_eq!(languages, ["en", "zh"]);
+        assert!(header_map["x-emby-token"].is_sensitive());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_header_is_an_error() {
+        let mut target = serve_body().await;
+        target.headers = Some(NetworkHeaders::new().with("x-user", "line\nbreak"));
+        let provider = NetworkProvider::new(vec![Box::new(CurlPlugin::default())]);
+
+        let result = provider.send_request(&target).await;
+
+        match result {
+            Err(NetworkError::InvalidHeader { name, .. }) => assert_eq!(name, "x-user"),
+            other => panic!("Expected an invalid header error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_default_redaction_masks_known_secrets() {
+        let rules = RedactionRules::default();
+
+        let emby = "http://emby.local/emby/Users/1?api_key=secret&Fields=Name".parse().unwrap();
+        assert_eq!(
+            rules.redact_url(&emby),
+            "http://emby.local/emby/Users/1?api_key=[REDACTED]&Fields=Name"
+        );
+
+        let telegram = "https://api.telegram.org/bot123456:ABC-def/sendMessage".parse().unwrap();
+        assert_eq!(
+            rules.redact_url(&telegram),
+            "https://api.telegram.org/bot[REDACTED]/sendMessage"
+        );
+
+        let body = rules.redact_body(r#"{"Name":"alice","NewPw":"hunter2"}"#);
+        assert!(!body.contains("hunter2"));
+        assert!(body.contains("alice"));
+    }
+
+    #[test]
+    fn test_curl_output_is_redacted() {
+        let request = reqwest::Client::new()
+            .post("http://emby.local/emby/Users/New?api_key=secret")
+            .header("X-Emby-Token", "token-value")
+            .header("x-custom", "visible")
+            .body(r#"{"Password":"hunter2"}"#)
+            .build()
+            .unwrap();
+
+        let curl = CurlPlugin::default()
+            .with_redaction(RedactionRules::default().with_header("x-extra"))
+            .request_to_curl(&request);
+
+        assert!(!curl.contains("secret"));
+        assert!(!curl.contains("token-value"));
+        assert!(!curl.contains("hunter2"));
+        assert!(curl.contains("visible"));
+    }
+
+    #[test]
+    fn test_sensitive_body_never_appears_in_curl_output() {
+        let password = "Xk7-secret-Pw9";
+        let settings = TelegramConfig { bot_token: "123:abc".to_string(), ..TelegramConfig::default() };
+        let api = TelegramAPI::SendMessage {
+            chat_id: 42,
+            text: format!("🔑 New password for alice on main:\n{}", password),
+            reply_markup: None,
+            sensitive: true,
+        };
+        let context = NetworkContext::for_target(&api.target(&settings));
+        assert!(context.has_sensitive_body());
+
+        let request = reqwest::Client::new()
+            .post("https://api.telegram.org/bot123:abc/sendMessage")
+            .body(format!(r#"{{"chat_id":42,"text":"{}"}}"#, password))
+            .build()
+            .unwrap();
+        let directory = tempfile::tempdir().unwrap();
+        let plugins = [
+            CurlPlugin::default(),
+            CurlPlugin::default().with_dump(directory.path(), CurlDumpFormat::Shell),
+            CurlPlugin::default().with_dump(directory.path(), CurlDumpFormat::Http),
+        ];
+        let capture = LogCapture::new().with_filter("debug").unwrap();
+        capture.run(|| {
+            for plugin in &plugins {
+                plugin.on_request(&context, &request);
+            }
+        });
+
+        assert_eq!(capture.lines().len(), 3);
+        for line in capture.lines() {
+            assert!(!line.contains(password), "{}", line);
+            assert!(line.contains("--data-raw '[REDACTED]'"), "{}", line);
+        }
+        for entry in std::fs::read_dir(directory.path()).unwrap() {
+            let dump = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            assert!(!dump.contains(password), "{}", dump);
+        }
+
+        let plain = CurlPlugin::default().request_to_curl(&request);
+        assert!(plain.contains(password), "other bodies are only masked field by field");
+    }
+
+    #[test]
+    fn test_curl_command_survives_the_shell() {
+        let body = r#"{"Name":"it's \"quoted\""}"#;
+        let request = reqwest::Client::new()
+            .post("http://emby.local/emby/Users/New")
+            .header("x-note", "don't")
+            .body(body)
+            .build()
+            .unwrap();
+
+        let curl = CurlPlugin::default().request_to_curl(&request);
+        let echo = curl.replacen("curl", "printf '%s\\n'", 1);
+        let output = std::process::Command::new("sh").arg("-c").arg(&echo).output().unwrap();
+        let arguments: Vec<_> = String::from_utf8(output.stdout).unwrap().lines().map(String::from).collect();
+
+        assert!(arguments.contains(&"x-note: don't".to_string()), "{:?}", arguments);
+        assert!(arguments.contains(&body.to_string()), "{:?}", arguments);
+        assert!(arguments.contains(&"--compressed".to_string()));
+    }
+
+    #[test]
+    fn test_curl_marks_uncaptured_binary_body() {
+        let request = reqwest::Client::new()
+            .put("http://emby.local/emby/Items/1/Images/Primary")
+            .body(vec![0xff, 0xd8, 0xff, 0xe0])
+            .build()
+            .unwrap();
+
+        let curl = CurlPlugin::default().request_to_curl(&request);
+        assert!(curl.contains("--data-binary '@<body-not-captured>'"), "{}", curl);
+        assert!(!curl.contains("@-"), "the command must not wait for stdin: {}", curl);
+    }
+
+    #[test]
+    fn test_curl_dump_references_binary_body() {
+        let directory = std::env::temp_dir().join(format!("pilipili_dump_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let plugin = CurlPlugin::default().with_dump(&directory, CurlDumpFormat::Shell);
+        let request = reqwest::Client::new()
+            .put("http://emby.local/emby/Items/1/Images/Primary")
+            .body(vec![0xff, 0xd8, 0xff, 0xe0])
+            .build()
+            .unwrap();
+
+        let context = NetworkContext::new(HttpMethod::Put, "emby/Items/{item_id}/Images/Primary");
+        plugin.on_request(&context, &request);
+
+        let mut files: Vec<_> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 2);
+        assert_eq!(std::fs::read(&files[0]).unwrap(), [0xff, 0xd8, 0xff, 0xe0]);
+        let script = std::fs::read_to_string(&files[1]).unwrap();
+        assert!(script.starts_with("#!/bin/sh"));
+        assert!(script.contains(&format!("--data-binary '@{}'", files[0].display())));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use pilipili_bot::services::password::{check_password, generate_password};
+
+    #[test]
+    fn test_generate_password() {
+        let passwords: HashSet<String> = (0..50).map(|_| generate_password(12)).collect();
+        assert_eq!(passwords.len(), 50, "passwords must not repeat");
+        for password in &passwords {
+            assert_eq!(password.len(), 12);
+            assert!(password.bytes().any(|b| b.is_ascii_lowercase()));
+            assert!(password.bytes().any(|b| b.is_ascii_uppercase()));
+            assert!(password.bytes().any(|b| b.is_ascii_digit()));
+            assert!(!password.contains(['0', 'O', '1', 'l', 'I']), "ambiguous characters in {}", password);
+            assert!(check_password(password, 8).is_ok());
+        }
+        assert_eq!(generate_password(3).len(), 8, "short lengths are raised");
+    }
+
+    #[test]
+    fn test_check_password() {
+        assert!(check_password("correct-horse", 8).is_ok());
+        assert!(check_password("密码密码密码-1", 8).is_ok());
+        assert!(check_password("short-1", 8).is_err());
+        assert!(check_password("two words 1", 8).is_err());
+        assert!(check_password("onlyletters", 8).is_err());
+        assert!(check_password("1234567890", 8).is_err());
+        assert!(check_password(&"a1".repeat(65), 8).is_err());
+    }
+}
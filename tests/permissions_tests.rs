@@ -0,0 +1,129 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use pilipili_bot::bot::access::{authorize, DENIED_RESULT};
+    use pilipili_bot::bot::{BotCommand, ResetPasswordHandler, RolesHandler, ServersHandler, UpdateHandler, UserAdminHandler};
+    use pilipili_bot::context::AppContext;
+    use pilipili_bot::infrastructure::config::{Config, ConfigError, RolesConfig};
+    use pilipili_bot::infrastructure::database::{AuditEntry, RoleAssignment};
+    use pilipili_bot::infrastructure::network::NetworkProvider;
+    use pilipili_bot::services::permissions::{
+        check_role_change, configured_roles, role_of, seed_roles, Permission, Role,
+    };
+
+    use crate::common::database;
+
+    fn config(owners: &[i64], admins: &[i64], moderators: &[i64]) -> Config {
+        Config {
+            roles: RolesConfig {
+                owners: owners.to_vec(),
+                admins: admins.to_vec(),
+                moderators: moderators.to_vec(),
+            },
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_roles_and_permissions() {
+        assert_eq!(Role::parse(" Admin"), Some(Role::Admin));
+        assert_eq!(Role::parse("root"), None);
+        assert!(Role::Owner > Role::Admin && Role::Moderator > Role::User);
+
+        assert!(Role::Moderator.can(Permission::ViewServers));
+        assert!(!Role::Moderator.can(Permission::ManageRoles));
+        assert!(Role::Owner.can(Permission::ChangeLogLevel));
+        assert!(!Role::User.can(Permission::ViewServers));
+
+        assert!(check_role_change(Role::Owner, Role::Moderator, Role::Admin).is_ok());
+        assert!(check_role_change(Role::Admin, Role::Moderator, Role::User).is_ok());
+        assert!(check_role_change(Role::Admin, Role::User, Role::Owner).is_err());
+        assert!(check_role_change(Role::Owner, Role::Owner, Role::Admin).is_err());
+        assert!(check_role_change(Role::Moderator, Role::User, Role::User).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_command_permissions() {
+        let database = database().await;
+        let context = Arc::new(AppContext::new(Config::default(), NetworkProvider::new(vec![]), database.clone()));
+        let servers = ServersHandler::new(context.clone());
+        let users = UserAdminHandler::new(context.clone());
+        let roles = RolesHandler::new(context.clone());
+        assert_eq!(servers.permission("servers"), Some(Permission::ViewServers));
+        assert_eq!(users.permission("user"), Some(Permission::ViewUsers));
+        for command in ["grant", "revoke", "roles"] {
+            assert_eq!(roles.permission(command), Some(Permission::ManageRoles));
+        }
+        assert_eq!(roles.permission("servers"), None);
+        assert_eq!(ResetPasswordHandler::new(context.clone()).permission("resetpw"), None);
+
+        database.assign_role(&RoleAssignment::new(1, "moderator", None)).await.unwrap();
+        let command = BotCommand::parse("/grant 5 admin").unwrap();
+        assert_eq!(authorize(&context, &roles, 1, &command).await.unwrap(), None);
+        let command = BotCommand::parse("/servers").unwrap();
+        assert_eq!(authorize(&context, &servers, 1, &command).await.unwrap(), Some(Role::Moderator));
+        assert_eq!(database.recent_audit(10).await.unwrap().len(), 1, "allowed commands are audited by the handler");
+
+        let denied = database.recent_audit(1).await.unwrap().remove(0);
+        assert_eq!((denied.actor_id, denied.action.as_str()), (1, "grant"));
+        assert_eq!((denied.arguments.as_str(), denied.result.as_str()), ("5 admin", DENIED_RESULT));
+    }
+
+    #[test]
+    fn test_configured_roles() {
+        let mut config = config(&[1], &[2], &[3, 2]);
+        config.telegram.admin_ids = vec![4, 1];
+        assert_eq!(
+            configured_roles(&config),
+            [(1, Role::Owner), (2, Role::Admin), (4, Role::Admin), (3, Role::Moderator)]
+        );
+
+        let Err(ConfigError::Invalid(issues)) = config.validate() else {
+            panic!("Duplicate role ids should be rejected");
+        };
+        let keys: Vec<&str> = issues.iter().map(|issue| issue.key.as_str()).collect();
+        assert!(keys.contains(&"roles.moderators[1]"), "{:?}", keys);
+    }
+
+    #[tokio::test]
+    async fn test_seed_roles() {
+        let database = database().await;
+        database.assign_role(&RoleAssignment::new(5, "moderator", Some(1))).await.unwrap();
+        database.assign_role(&RoleAssignment::new(6, "admin", Some(1))).await.unwrap();
+
+        seed_roles(&database, &config(&[1], &[2, 6], &[])).await.unwrap();
+        assert_eq!(role_of(&database, 1).await.unwrap(), Role::Owner);
+        assert_eq!(role_of(&database, 2).await.unwrap(), Role::Admin);
+        assert_eq!(role_of(&database, 5).await.unwrap(), Role::Moderator, "granted roles are kept");
+        assert_eq!(database.role_assignment(6).await.unwrap().unwrap().granted_by, None);
+        assert_eq!(role_of(&database, 7).await.unwrap(), Role::User);
+
+        seed_roles(&database, &config(&[2], &[], &[])).await.unwrap();
+        assert_eq!(role_of(&database, 1).await.unwrap(), Role::User, "unlisted owners are removed");
+        assert_eq!(role_of(&database, 2).await.unwrap(), Role::Owner);
+        assert_eq!(role_of(&database, 6).await.unwrap(), Role::User);
+        assert_eq!(role_of(&database, 5).await.unwrap(), Role::Moderator);
+
+        assert!(database.remove_role(5).await.unwrap());
+        assert!(!database.remove_role(5).await.unwrap());
+        database.assign_role(&RoleAssignment::new(8, "superuser", Some(2))).await.unwrap();
+        assert_eq!(role_of(&database, 8).await.unwrap(), Role::User, "unknown roles grant nothing");
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let database = database().await;
+        let mut granted = AuditEntry::new(1, Some("5".to_string()), "grant", "5 moderator", "ok");
+        granted.created_at -= 10;
+        let denied = AuditEntry::new(5, None, "loglevel", "trace", "denied");
+        database.record_audit(&granted).await.unwrap();
+        database.record_audit(&denied).await.unwrap();
+
+        assert_eq!(database.recent_audit(10).await.unwrap(), [denied.clone(), granted]);
+        assert_eq!(database.recent_audit(1).await.unwrap(), [denied]);
+    }
+}
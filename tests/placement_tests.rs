@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+
+    use pilipili_bot::context::AppContext;
+    use pilipili_bot::infrastructure::api::ServerLookupError;
+    use pilipili_bot::infrastructure::config::{Config, ConfigLoader, PlacementStrategy};
+    use pilipili_bot::infrastructure::database::AccountBinding;
+    use pilipili_bot::infrastructure::network::NetworkProvider;
+    use pilipili_bot::services::{least_loaded, place_new_account, PlacementError, ServerLoad};
+
+    fn config(servers: &[(&str, Option<u64>)]) -> Config {
+        let mut loader = ConfigLoader::new()
+            .with_override("telegram.bot_token", "123456:token")
+            .with_override("database.url", "sqlite::memory:");
+        for (name, max_users) in servers {
+            loader = loader
+                .with_override(format!("servers.{}.base_url", name), format!("http://{}:8096", name))
+                .with_override(format!("servers.{}.api_key", name), "key");
+            if let Some(max_users) = max_users {
+                loader = loader.with_override(format!("servers.{}.max_users", name), max_users.to_string());
+            }
+        }
+        loader.load().unwrap()
+    }
+
+    fn load(server: &str, users: u64, sessions: Option<u64>, max_users: Option<u64>) -> ServerLoad {
+        ServerLoad { server: server.to_string(), users, sessions, max_users }
+    }
+
+    #[test]
+    fn test_least_loaded() {
+        let loads = [
+            load("a", 10, Some(1), None),
+            load("b", 3, Some(5), None),
+            load("c", 2, None, Some(2)),
+        ];
+        let pick = |strategy| least_loaded(&loads, strategy).map(|load| load.server.as_str());
+
+        // `c` is full, and its sessions are unknown
+        assert_eq!(pick(PlacementStrategy::Users), Some("b"));
+        assert_eq!(pick(PlacementStrategy::Sessions), Some("a"));
+
+        let tied = [load("b", 1, Some(0), None), load("a", 1, Some(0), None)];
+        assert_eq!(least_loaded(&tied, PlacementStrategy::Users).unwrap().server, "a");
+        assert!(least_loaded(&[load("a", 1, None, Some(1))], PlacementStrategy::Users).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bindings_and_placement() {
+        let context = AppContext::connect(
+            config(&[("eu", None), ("us", Some(1))]),
+            NetworkProvider::new(vec![]),
+        )
+            .await
+            .unwrap();
+        let database = context.database();
+
+        assert_eq!(place_new_account(&context).await.unwrap().name(), "eu");
+        database.bind_account(&AccountBinding::new(42, "eu", "e1", "alice")).await.unwrap();
+        assert!(database.bind_account(&AccountBinding::new(42, "eu", "e2", "alice")).await.is_err());
+        assert_eq!(place_new_account(&context).await.unwrap().name(), "us");
+
+        database.bind_account(&AccountBinding::new(42, "us", "u1", "alice")).await.unwrap();
+        database.bind_account(&AccountBinding::new(7, "eu", "e3", "bob")).await.unwrap();
+        let servers: Vec<String> = database.accounts_of(42).await.unwrap().into_iter().map(|a| a.server).collect();
+        assert_eq!(servers, ["eu", "us"]);
+        assert_eq!(database.account_on(7, "eu").await.unwrap().unwrap().emby_name, "bob");
+        assert_eq!(database.account_counts().await.unwrap()["eu"], 2);
+
+        // `us` is full, so `eu` is chosen despite its load
+        assert_eq!(place_new_account(&context).await.unwrap().name(), "eu");
+        assert!(database.unbind_account(42, "us").await.unwrap());
+        assert!(!database.unbind_account(42, "us").await.unwrap());
+        assert_eq!(place_new_account(&context).await.unwrap().name(), "us");
+
+        context.replace_config(config(&[("us", Some(1))])).unwrap();
+        database.bind_account(&AccountBinding::new(1, "us", "u2", "carol")).await.unwrap();
+        assert!(matches!(place_new_account(&context).await, Err(PlacementError::NoServerAvailable)));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_server() {
+        let context = AppContext::connect(config(&[("eu", None), ("us", None)]), NetworkProvider::new(vec![]))
+            .await
+            .unwrap();
+        let servers = context.servers();
+
+        assert_eq!(servers.resolve(Some("us")).unwrap().name(), "us");
+        assert!(matches!(
+            servers.resolve(None),
+            Err(ServerLookupError::Ambiguous { available }) if available == ["eu", "us"]
+        ));
+        assert!(matches!(servers.resolve(Some("asia")), Err(ServerLookupError::Unknown { .. })));
+
+        let eu = servers.get("eu").unwrap();
+        context.replace_config(config(&[("eu", Some(5))])).unwrap();
+        assert_eq!(servers.names(), ["eu"]);
+        assert_eq!(servers.resolve(None).unwrap().settings().max_users, Some(5));
+        assert_eq!(eu.settings().max_users, Some(5), "existing clients get the new settings");
+    }
+}
@@ -0,0 +1,204 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use pilipili_bot::bot::callback::MAX_CALLBACK_DATA_LENGTH;
+    use pilipili_bot::bot::user_admin::{describe_profile, extended_expiry, UserAction, UserActionKind, EXTEND_DAYS};
+    use pilipili_bot::bot::{CallbackHandler, CallbackSigner, UserAdminHandler};
+    use pilipili_bot::context::AppContext;
+    use pilipili_bot::infrastructure::api::telegram_types::CallbackQuery;
+    use pilipili_bot::infrastructure::config::ConfigLoader;
+    use pilipili_bot::infrastructure::database::{AccountBinding, RoleAssignment, TelegramUser};
+    use pilipili_bot::infrastructure::network::NetworkProvider;
+    use pilipili_bot::services::permissions::Role;
+
+    use crate::common::database;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_user_action_data() {
+        let signer = CallbackSigner::new(b"secret", Duration::from_secs(3600));
+        let now = 1_760_000_000;
+        let delete = UserAction::new(UserActionKind::Delete, 9_876_543_210, "europe");
+        assert!(delete.kind.needs_confirmation() && !delete.confirmed);
+
+        for action in [delete.clone(), delete.confirm(), UserAction::new(UserActionKind::Show, 5, "")] {
+            let encoded = signer.sign_at(&action, Some(1_234_567_890), now).unwrap();
+            assert!(encoded.len() <= MAX_CALLBACK_DATA_LENGTH, "{} is too long", encoded);
+            let callback = signer.verify_at(&encoded, 1_234_567_890, now).unwrap();
+            assert_eq!(callback.decode::<UserAction>(), Some(action));
+        }
+        assert!(signer.sign_at(&delete.confirm(), None, now).unwrap().starts_with("usr:r!:9876543210:europe:"));
+        assert!(!UserActionKind::Kick.needs_confirmation());
+    }
+
+    #[test]
+    fn test_extended_expiry() {
+        assert_eq!(extended_expiry(Some(100 * DAY), 10 * DAY), Some((100 + EXTEND_DAYS) * DAY));
+        assert_eq!(extended_expiry(Some(DAY), 10 * DAY), Some((10 + EXTEND_DAYS) * DAY), "expired accounts restart now");
+        assert_eq!(extended_expiry(None, 10 * DAY), None, "unlimited accounts stay unlimited");
+    }
+
+    #[tokio::test]
+    async fn test_telegram_users() {
+        let database = database().await;
+        assert_eq!(database.telegram_user(1).await.unwrap(), None);
+
+        let mut alice = TelegramUser::new(1, Some("Alice".to_string()), "Alice");
+        alice.last_seen_at = 100;
+        database.touch_telegram_user(&alice).await.unwrap();
+        assert_eq!(database.telegram_user(1).await.unwrap(), Some(alice.clone()));
+
+        let renamed = TelegramUser::new(1, Some("alice_w".to_string()), "Alice W.");
+        database.touch_telegram_user(&renamed).await.unwrap();
+        database.touch_telegram_user(&TelegramUser::new(2, None, "Bob")).await.unwrap();
+        let stored = database.telegram_user(1).await.unwrap().unwrap();
+        assert_eq!(stored, renamed);
+        assert!(stored.last_seen_at > alice.last_seen_at);
+
+        assert_eq!(database.telegram_user_named("@ALICE_W").await.unwrap(), Some(renamed));
+        assert_eq!(database.telegram_user_named("alice").await.unwrap(), None, "old usernames are forgotten");
+        assert_eq!(database.telegram_user(2).await.unwrap().unwrap().username, None);
+    }
+
+    #[tokio::test]
+    async fn test_points_and_strikes() {
+        let database = database().await;
+        let mut carol = TelegramUser::new(3, Some("carol".to_string()), "Carol");
+        assert_eq!((carol.points, carol.strikes), (0, 0));
+        carol.points = 42;
+        carol.strikes = 2;
+        database.touch_telegram_user(&carol).await.unwrap();
+
+        database.touch_telegram_user(&TelegramUser::new(3, None, "Carol")).await.unwrap();
+        let stored = database.telegram_user(3).await.unwrap().unwrap();
+        assert_eq!((stored.points, stored.strikes), (42, 2), "talking to the bot keeps points and strikes");
+
+        let card = describe_profile(3, Some(&stored), Role::User);
+        assert!(card.contains("\nPoints: 42 · Strikes: 2\n"), "{}", card);
+        assert!(!describe_profile(4, None, Role::User).contains("Points"));
+    }
+
+    #[tokio::test]
+    async fn test_account_lookup_and_expiry() {
+        let database = database().await;
+        database.bind_account(&AccountBinding::new(1, "eu", "a1", "Alice")).await.unwrap();
+        database.bind_account(&AccountBinding::new(2, "us", "a2", "alice")).await.unwrap();
+        database.bind_account(&AccountBinding::new(3, "eu", "b1", "bob")).await.unwrap();
+
+        let named: Vec<(i64, String)> = database
+            .accounts_named("ALICE")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|binding| (binding.telegram_id, binding.server))
+            .collect();
+        assert_eq!(named, [(1, "eu".to_string()), (2, "us".to_string())]);
+
+        assert_eq!(database.account_on(1, "eu").await.unwrap().unwrap().expires_at, None);
+        assert!(database.set_account_expiry(1, "eu", Some(30 * DAY)).await.unwrap());
+        assert_eq!(database.account_on(1, "eu").await.unwrap().unwrap().expires_at, Some(30 * DAY));
+        assert!(database.set_account_expiry(1, "eu", None).await.unwrap());
+        assert_eq!(database.account_on(1, "eu").await.unwrap().unwrap().expires_at, None);
+        assert!(!database.set_account_expiry(1, "us", Some(DAY)).await.unwrap());
+    }
+
+    /// Serves both the Bot API and the media server, refusing to send
+    /// messages as if the user had blocked the bot. Records the paths asked for.
+    async fn serve_blocked_user() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorded = received.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = vec![0; 4096];
+                loop {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|line| line.to_lowercase().strip_prefix("content-length: ").map(str::to_owned))
+                            .and_then(|length| length.trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if body.len() >= length || read == 0 {
+                            break;
+                        }
+                    }
+                }
+                let text = String::from_utf8_lossy(&request).to_string();
+                let path = text.split_whitespace().nth(1).unwrap_or_default().to_string();
+                let (status, payload) = if path.ends_with("/sendMessage") {
+                    (
+                        "403 Forbidden",
+                        r#"{"ok":false,"error_code":403,"description":"Forbidden: bot was blocked by the user"}"#,
+                    )
+                } else {
+                    ("200 OK", r#"{"ok":true,"result":{"message_id":5,"chat":{"id":42,"type":"private"},"date":0}}"#)
+                };
+                recorded.lock().unwrap().push(path);
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    payload.len(),
+                    payload
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (format!("http://{}", address), received)
+    }
+
+    #[tokio::test]
+    async fn test_reset_password_needs_delivery() {
+        let (url, received) = serve_blocked_user().await;
+        let config = ConfigLoader::new()
+            .with_override("telegram.bot_token", "123456:token")
+            .with_override("telegram.api_url", url.as_str())
+            .with_override("database.url", "sqlite::memory:")
+            .with_override("servers.eu.base_url", url.as_str())
+            .with_override("servers.eu.api_key", "key")
+            .load()
+            .unwrap();
+        let context = Arc::new(AppContext::connect(config, NetworkProvider::new(vec![])).await.unwrap());
+        let database = context.database();
+        database.assign_role(&RoleAssignment::new(42, "admin", None)).await.unwrap();
+        database.bind_account(&AccountBinding::new(7, "eu", "e7", "bob")).await.unwrap();
+
+        let query: CallbackQuery = serde_json::from_value(json!({
+            "id": "query",
+            "from": { "id": 42, "is_bot": false, "first_name": "Alice" },
+            "message": { "message_id": 5, "chat": { "id": 42, "type": "private" }, "date": 0 },
+            "data": ""
+        }))
+        .unwrap();
+        let handler = UserAdminHandler::new(context.clone());
+        let action = UserAction::new(UserActionKind::ResetPassword, 7, "eu");
+        let notice = CallbackHandler::handle(&handler, context.telegram(), &query, action).await.unwrap();
+
+        assert_eq!(
+            notice.as_deref(),
+            Some("The password of bob on eu was not changed, as the new one could not be sent")
+        );
+        let paths = received.lock().unwrap().clone();
+        assert!(paths.iter().any(|path| path.ends_with("/sendMessage")), "{:?}", paths);
+        assert!(!paths.iter().any(|path| path.contains("/Password")), "the password was changed: {:?}", paths);
+        let entry = database.recent_audit(1).await.unwrap().remove(0);
+        assert_eq!((entry.target.as_deref(), entry.arguments.as_str()), (Some("7"), "resetpw eu"));
+        assert_eq!(entry.result, "failed");
+    }
+}